@@ -0,0 +1,18 @@
+//! Captures the commit `project` is built from, for `--version`.
+
+use std::process::Command;
+
+fn main() {
+  let sha = Command::new("git")
+    .args(["rev-parse", "--short", "HEAD"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success())
+    .and_then(|output| String::from_utf8(output.stdout).ok())
+    .map(|sha| sha.trim().to_string())
+    .unwrap_or_else(|| "unknown".to_string());
+
+  println!("cargo:rustc-env=PROJECT_GIT_SHA={}", sha);
+  println!("cargo:rerun-if-changed=.git/HEAD");
+  println!("cargo:rerun-if-changed=.git/refs");
+}
@@ -6,7 +6,7 @@ use console::style;
 
 fn main() {
   let project = ProjectInfo::from("./my-project");
-  let options = TemplateOptions::new("../project", None);
+  let options = TemplateOptions::new("../project", None).unwrap();
 
   let template = Template::new(&project, &options);
   match &template.generate() {
@@ -7,9 +7,10 @@ use console::style;
 fn main() {
   let project = ProjectInfo::from("./my-project");
   let options =
-    TemplateOptions::new("https://github.com/victor-iyi/project", None);
+    TemplateOptions::new("https://github.com/victor-iyi/project", None)
+      .unwrap();
   /* -- OR -- */
-  // let options = TemplateOptions::new("victor-iyi/project", None);
+  // let options = TemplateOptions::new("victor-iyi/project", None).unwrap();
 
   let template = Template::new(&project, &options);
   match template.generate() {
@@ -1,24 +1,25 @@
-use crate::{emoji, error::Result};
+use crate::error::{Error, ErrorKind, Result};
 
-use console::style;
 use git2::{Config as GitConfig, Repository as GitRepository};
 
 use std::env;
 
-// /// Taken from cargo and thus (c) 2020 Cargo Developers
-// ///
-// /// cf. https://github.com/rust-lang/cargo/blob/2d5c2381e4e50484bf281fc1bfe19743aa9eb37a/src/cargo/ops/cargo_new.rs#L769-L851
-// pub(crate) fn get_authors() -> Result<String> {
-//   let author = match discover_author()? {
-//     (name, Some(email)) => format!("{} <{}>", name, email),
-//     (name, None) => name,
-//   };
+/// Taken from cargo and thus (c) 2020 Cargo Developers
+///
+/// cf. https://github.com/rust-lang/cargo/blob/2d5c2381e4e50484bf281fc1bfe19743aa9eb37a/src/cargo/ops/cargo_new.rs#L769-L851
+pub(crate) fn get_authors() -> Result<String> {
+  let author = match discover_author()? {
+    (name, Some(email)) => format!("{} <{}>", name, email),
+    (name, None) => name,
+  };
 
-//   Ok(author)
-// }
+  Ok(author)
+}
 
 /// Retrieve author information from environment variables.
 ///
+/// Returns an error if no name is found, e.g. without `$USER` nor a `git`
+/// user.
 pub(crate) fn discover_author() -> Result<(String, Option<String>)> {
   let git_config = find_real_git_config();
   let git_config = git_config.as_ref();
@@ -40,14 +41,13 @@ pub(crate) fn discover_author() -> Result<(String, Option<String>)> {
     Some(name) => name,
     None => {
       let username_var = if cfg!(windows) { "USERNAME" } else { "USER" };
-      panic!(
-        "{} {}{}",
-        emoji::WRENCH,
-        style("Could not determine the current user, please set $")
-          .bold()
-          .red(),
-        style(&username_var).bold().red()
-      );
+      return Err(Error::new(
+        ErrorKind::NotFound,
+        &format!(
+          "could not determine the current user, please set ${}",
+          username_var
+        ),
+      ));
     }
   };
 
@@ -0,0 +1,370 @@
+//! Generate several projects from a manifest, i.e. `batch`.
+//!
+//! The manifest lists every project as a `[[project]]` entry. Its top-level
+//! `template`, `branch` & `[vars]` are shared by every project, which can
+//! override them:
+//!
+//! ```toml
+//! template = "owner/service-template"
+//!
+//! [vars]
+//! license = "MIT"
+//!
+//! [[project]]
+//! name = "billing"
+//!
+//! [[project]]
+//! name = "auth"
+//! template = "path/to/other/template"
+//! vars = { port = "8081" }
+//! ```
+//!
+//! ```sh
+//! $ project batch services.toml --jobs 4
+//! ```
+//!
+use crate::{
+  emoji,
+  error::{Error, ErrorKind, Result},
+  git::{CachePolicy, GitOptions},
+  info::TemplateOptions,
+  template::{GenerationStats, Template, TemplateMeta, Verbosity},
+  Arguments,
+};
+
+use console::style;
+use serde::Deserialize;
+
+use std::{
+  collections::HashMap,
+  fmt, fs,
+  io::{self, Write},
+  panic,
+  path::{Path, PathBuf},
+  thread,
+};
+
+/// Projects generated by `batch`, with their shared defaults.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+  /// Template of the projects which don't set their own.
+  #[serde(default)]
+  pub template: Option<String>,
+  /// Branch of a remote `template`.
+  #[serde(default)]
+  pub branch: Option<String>,
+  /// Variables of every project, overridden by the project's own.
+  #[serde(default)]
+  pub vars: HashMap<String, String>,
+  /// Projects, generated in order.
+  #[serde(default, rename = "project")]
+  pub projects: Vec<ManifestProject>,
+}
+
+/// A `[[project]]` entry of a [`Manifest`].
+///
+/// [`Manifest`]: struct.Manifest.html
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestProject {
+  /// Name of the project, i.e. its directory.
+  pub name: String,
+  /// Local path or remote repository of the template, instead of the
+  /// manifest's.
+  #[serde(default)]
+  pub template: Option<String>,
+  /// Branch of a remote template, instead of the manifest's.
+  #[serde(default)]
+  pub branch: Option<String>,
+  /// Variables of the project, overriding the manifest's.
+  #[serde(default)]
+  pub vars: HashMap<String, String>,
+}
+
+/// Outcome of generating one project of a manifest.
+pub struct BatchResult {
+  /// Name of the project, as given in the manifest.
+  pub name: String,
+  /// Stats of the generated project, or why it failed.
+  pub result: Result<GenerationStats>,
+}
+
+impl fmt::Display for BatchResult {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.result {
+      Ok(stats) => write!(
+        f,
+        "{}{} {}",
+        emoji::SPARKLE,
+        style(&self.name).bold().green(),
+        style(format!("({} files)", stats.generated.len())).dim()
+      ),
+      Err(err) => write!(
+        f,
+        "{}{} {}",
+        emoji::ERROR,
+        style(format!("{}:", self.name)).bold().red(),
+        style(err).red()
+      ),
+    }
+  }
+}
+
+impl Manifest {
+  /// Read the manifest from the TOML file at `path`.
+  pub fn read(path: &Path) -> Result<Manifest> {
+    Manifest::parse(&fs::read_to_string(path)?)
+  }
+
+  /// Parse & validate the manifest from its TOML `content`.
+  ///
+  /// Returns an error if it has no project, or if a project has no name or
+  /// no template.
+  pub fn parse(content: &str) -> Result<Manifest> {
+    let manifest: Manifest = toml::from_str(content).map_err(|err| {
+      Error::new(
+        ErrorKind::ParseError,
+        &format!("invalid batch manifest: {}", err),
+      )
+    })?;
+
+    if manifest.projects.is_empty() {
+      return Err(Error::new(
+        ErrorKind::Config,
+        "invalid batch manifest: it has no [[project]]",
+      ));
+    }
+    for (index, project) in manifest.projects.iter().enumerate() {
+      let problem = if project.name.trim().is_empty() {
+        "has no name"
+      } else if project.template.is_none() && manifest.template.is_none() {
+        "has no template"
+      } else {
+        continue;
+      };
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!(
+          "invalid batch manifest: [[project]] #{} {}",
+          index + 1,
+          problem
+        ),
+      ));
+    }
+
+    Ok(manifest)
+  }
+
+  /// Arguments generating each project, with the command line's `args`.
+  ///
+  /// Nothing is asked, as with `--assume-yes`, and `-D key=value` overrides
+  /// the manifest's variables.
+  pub fn arguments(&self, args: &Arguments) -> Result<Vec<Arguments>> {
+    let cache = if args.no_cache {
+      CachePolicy::Refresh
+    } else {
+      CachePolicy::Use
+    };
+
+    let mut arguments = Vec::new();
+    for project in &self.projects {
+      let template = project.template.as_ref().or(self.template.as_ref());
+      let branch = project.branch.as_ref().or(self.branch.as_ref());
+      let mut project_args = Arguments::new(
+        &project.name,
+        template.map(String::as_str).unwrap_or_default(),
+        branch.map(String::as_str),
+      )?;
+      if let TemplateOptions::Remote(opts) = &mut project_args.template {
+        opts.set_cache_policy(cache);
+        if let Some(dir) = &args.cache_dir {
+          opts.set_cache_dir(dir);
+        }
+        if let Some(bytes) = args.max_clone_size {
+          opts.set_max_clone_size(bytes);
+        }
+      }
+
+      project_args.verbosity = args.verbosity;
+      project_args.assume_yes = true;
+      project_args.force = args.force;
+      project_args.keep_going = args.keep_going;
+      project_args.abort_on_warning = args.abort_on_warning;
+      project_args.config_name = args.config_name.clone();
+      project_args.defines = self.vars.clone();
+      project_args.defines.extend(project.vars.clone());
+      project_args.defines.extend(args.defines.clone());
+      arguments.push(project_args);
+    }
+
+    Ok(arguments)
+  }
+}
+
+/// Generate the projects of `manifest` with the command line's `args`, up to
+/// `jobs` at once, each in its own thread.
+///
+/// A project failing, even with a panic, doesn't stop the others. Results are
+/// in the manifest's order.
+///
+/// Remote templates are cloned once, before any project is generated, since
+/// the projects sharing one would otherwise race on its cached clone.
+pub fn run(
+  manifest: &Manifest,
+  args: &Arguments,
+  jobs: usize,
+) -> Result<Vec<BatchResult>> {
+  let mut arguments = manifest.arguments(args)?;
+  let names: Vec<&String> = manifest.projects.iter().map(|p| &p.name).collect();
+
+  // Why each remote template couldn't be cloned, by cached path.
+  let mut clones: HashMap<PathBuf, std::result::Result<(), String>> =
+    HashMap::new();
+  let failures: Vec<Option<String>> = arguments
+    .iter_mut()
+    .map(|project_args| match &mut project_args.template {
+      TemplateOptions::Remote(opts) => {
+        let clone = clones
+          .entry(opts.path())
+          .or_insert_with(|| clone(opts, args.verbosity));
+        // The fresh clone is only read by the projects.
+        opts.set_cache_policy(CachePolicy::Use);
+        clone.clone().err()
+      }
+      _ => None,
+    })
+    .collect();
+
+  let mut results = Vec::new();
+  let mut pending = arguments.into_iter().zip(names).zip(failures).peekable();
+  while pending.peek().is_some() {
+    let chunk: Vec<((Arguments, &String), Option<String>)> =
+      pending.by_ref().take(jobs.max(1)).collect();
+    thread::scope(|scope| {
+      let handles: Vec<_> = chunk
+        .into_iter()
+        .map(|((project_args, name), failure)| {
+          let handle = scope.spawn(move || match failure {
+            Some(err) => Err(Error::new(ErrorKind::GitError, &err)),
+            None => Template::from(&project_args).generate(),
+          });
+          (name, handle)
+        })
+        .collect();
+      for (name, handle) in handles {
+        let result = handle.join().unwrap_or_else(|_| {
+          Err(Error::new(ErrorKind::Error, "generation panicked"))
+        });
+        results.push(BatchResult {
+          name: name.to_string(),
+          result,
+        });
+      }
+    });
+  }
+
+  Ok(results)
+}
+
+/// Clone the remote template of `opts` into the cache, as its projects
+/// would, returning why it failed if it did.
+fn clone(
+  opts: &GitOptions,
+  verbosity: Verbosity,
+) -> std::result::Result<(), String> {
+  panic::catch_unwind(|| TemplateMeta::load_remote(opts, verbosity))
+    .map_err(|_| format!("couldn't clone {}", opts.remote()))?
+    .map_err(|err| err.to_string())
+}
+
+/// Write the outcome of every project into `out`, one per line, followed by
+/// how many failed, if any.
+pub fn write_report<W: Write>(
+  results: &[BatchResult],
+  out: &mut W,
+) -> io::Result<()> {
+  for result in results {
+    writeln!(out, "{}", result)?;
+  }
+  let failed = results.iter().filter(|r| r.result.is_err()).count();
+  if failed > 0 {
+    writeln!(
+      out,
+      "{}",
+      style(format!("{} of {} projects failed", failed, results.len()))
+        .bold()
+        .red()
+    )?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_manifest() {
+    let manifest = Manifest::parse(
+      "template = \"path/to/template\"\n\n[vars]\nlicense = \"MIT\"\n\n[[project]]\nname = \"billing\"\n\n[[project]]\nname = \"auth\"\ntemplate = \"other\"\nvars = { license = \"Apache-2.0\" }\n",
+    )
+    .unwrap();
+    assert_eq!(manifest.template.as_deref(), Some("path/to/template"));
+    assert_eq!(manifest.projects.len(), 2);
+    assert_eq!(manifest.projects[1].template.as_deref(), Some("other"));
+    assert_eq!(manifest.projects[1].vars["license"], "Apache-2.0");
+
+    let err = Manifest::parse("[[project]]\nname = \"auth\"\n").unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "invalid batch manifest: [[project]] #1 has no template"
+    );
+    let err = Manifest::parse("template = \"t\"\n").unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+  }
+
+  #[test]
+  fn test_run_shared_remote() {
+    let origin = tempfile::tempdir().unwrap();
+    fs::write(
+      origin.path().join("template.toml"),
+      "[filters]\nexclude = []\n",
+    )
+    .unwrap();
+    fs::write(origin.path().join("README.md.hbs"), "# {{project-name}}\n")
+      .unwrap();
+    crate::git::init_commit(origin.path(), "Initial commit").unwrap();
+    let url = url::Url::from_file_path(origin.path()).unwrap();
+
+    // Every project refreshes the same cached clone.
+    let dir = tempfile::tempdir().unwrap();
+    let projects: String = (0..4)
+      .map(|i| {
+        let name = dir.path().join(format!("project-{}", i));
+        format!("[[project]]\nname = {:?}\n", name.to_string_lossy())
+      })
+      .collect();
+    let manifest =
+      Manifest::parse(&format!("template = \"{}\"\n{}", url, projects))
+        .unwrap();
+    let args = Arguments {
+      no_cache: true,
+      cache_dir: Some(dir.path().join("cache")),
+      ..Arguments::default()
+    };
+
+    let results = run(&manifest, &args, 4).unwrap();
+    for (i, result) in results.iter().enumerate() {
+      assert!(result.result.is_ok(), "{}", result);
+      let readme = dir.path().join(format!("project-{}/README.md", i));
+      assert_eq!(
+        fs::read_to_string(readme).unwrap(),
+        format!("# project_{}\n", i)
+      );
+    }
+  }
+}
@@ -50,12 +50,33 @@
 //! ```
 //!
 use crate::{
+  batch::Manifest,
+  defaults::{self, ConfigCommand},
   emoji,
+  error::{Error, ErrorKind, Result},
+  git::{self, CacheCommand, CachePolicy},
   info::{ProjectInfo, TemplateOptions},
+  logger,
+  template::{archive, inherit, Case, Verbosity},
+  version::Version,
 };
 
 use clap::{App, AppSettings, Arg};
 use console::style;
+use log::Level;
+use serde::Deserialize;
+
+use std::{
+  collections::HashMap,
+  convert::TryFrom,
+  env,
+  ffi::OsString,
+  fmt::Display,
+  fs,
+  io::{self, Read, Write},
+  path::{Path, PathBuf},
+  process,
+};
 
 /// Useable argument passed into `Cli`.
 pub struct Arguments {
@@ -64,30 +85,224 @@ pub struct Arguments {
   /// Template options.
   pub template: TemplateOptions,
   /// Verbosity level.
-  pub verbose: bool,
-  /// Supress output.
-  pub quiet: bool,
+  pub verbosity: Verbosity,
+  /// Report the generated project as JSON, i.e. `--format json`.
+  pub json: bool,
+  /// Only regenerate the template files changed since the project was
+  /// generated.
+  pub update: bool,
+  /// Commit the project was generated from, instead of its lock file's.
+  pub since: Option<String>,
+  /// Glob of the template files re-rendered into the existing project.
+  pub render_only: Option<String>,
+  /// Regenerate the project whenever its local template changes.
+  pub watch: bool,
+  /// Keep the modification time of the template's files.
+  pub preserve_timestamps: bool,
+  /// SHA the template's git tree, or commit, must have.
+  pub expect_tree_sha: Option<String>,
+  /// Names of the template's `[scripts]` to run, in order.
+  pub run: Vec<String>,
+  /// Print what would be generated, without generating it.
+  pub dry_run: bool,
+  /// Arguments passed to the template's `[scripts]` as `{{args}}`.
+  pub template_args: Vec<String>,
+  /// Open the generated project in an editor or the file manager.
+  pub open: bool,
+  /// Print the template's next steps, without generating the project.
+  pub print_next_steps: bool,
+  /// Subdirectory of the template, the only one generated.
+  pub only: Option<PathBuf>,
+  /// Generate the `only` subdirectory as the project's root.
+  pub only_as_root: bool,
+  /// Generate the project into this tar archive instead of a directory.
+  pub archive: Option<PathBuf>,
+  /// File the debug log is written into.
+  pub log_file: Option<PathBuf>,
+  /// Log the variables each rendered file references, with their values.
+  pub trace_renders: bool,
+  /// Log the generation's events into stderr as NDJSON, instead of the human
+  /// output.
+  pub json_logs: bool,
+  /// Don't create the user-level defaults on the first run.
+  pub no_first_run: bool,
+  /// Ask for values of the template's `[prompts]`.
+  pub interactive: bool,
+  /// Treat the template as a `cargo-generate` template.
+  pub cargo_generate: bool,
+  /// Treat warnings as errors.
+  pub abort_on_warning: bool,
+  /// Always clone a fresh copy of remote templates.
+  pub no_cache: bool,
+  /// Record the commit a remote template resolved to in `project.lock`.
+  pub write_lock: bool,
+  /// Refuse to generate unless the remote template matches `project.lock`.
+  pub locked: bool,
+  /// Template to compare `template` against, instead of generating a project.
+  pub diff: Option<TemplateOptions>,
+  /// Vars file checked against the template's variables, instead of
+  /// generating a project.
+  pub vars_file: Option<PathBuf>,
+  /// Compare the project with its template, instead of generating it.
+  pub check: bool,
+  /// Projects of a `batch` manifest, generated instead of `project`.
+  pub batch: Option<Manifest>,
+  /// Projects of the `batch` generated at once.
+  pub jobs: usize,
+  /// Dotenv file with additional variables.
+  pub env_file: Option<PathBuf>,
+  /// Lowercase the keys of `env_file`.
+  pub env_lowercase: bool,
+  /// Prefix of the environment variables imported as variables, instead of
+  /// the user's `env-prefix` default.
+  pub env_prefix: Option<String>,
+  /// Variables inherited from the `Cargo.toml` or `package.json` of a parent
+  /// project, i.e. `--inherit-from`.
+  pub inherited: HashMap<String, String>,
+  /// Fail on files & directories of the template that can't be read.
+  pub strict_walk: bool,
+  /// Skip files which fail to generate instead of stopping.
+  pub keep_going: bool,
+  /// Read or write the user-level defaults, instead of generating a project.
+  pub config: Option<ConfigCommand>,
+  /// Print the generated project's path, to `cd` into it.
+  pub cd: bool,
+  /// Answer every prompt with its default & confirm overwriting files.
+  pub assume_yes: bool,
+  /// Ask to confirm a summary of the project before generating it.
+  pub confirm: bool,
+  /// Copy the template's `template.toml` into the generated project.
+  pub keep_template_toml: bool,
+  /// Overwrite the project directory if it already exists.
+  pub force: bool,
+  /// Report the files the generation added or changed in the project
+  /// directory.
+  pub print_diff_after: bool,
+  /// Generate into the root, home or a system directory, which is refused
+  /// otherwise.
+  pub i_know_what_im_doing: bool,
+  /// Case of the components renamed by `[rename]`.
+  pub rename_case: Option<Case>,
+  /// Initialize a git repository in the generated project & commit its files.
+  pub git_init: bool,
+  /// Message of the `--git-init` commit, overriding the template's.
+  pub commit_message: Option<String>,
+  /// Name of the template's config file, instead of `template.toml`.
+  pub config_name: Option<String>,
+  /// Reject the unknown keys of the template's config file.
+  pub template_toml_strict: bool,
+  /// Directory remote templates are cloned into, instead of the default
+  /// cache directory.
+  pub cache_dir: Option<PathBuf>,
+  /// Bytes a remote template's clone may receive before it's aborted.
+  pub max_clone_size: Option<u64>,
+  /// `cache` subcommand to run, instead of generating a project.
+  pub cache: Option<CacheCommand>,
+  /// Ignore the template's config file, using the default filters & only the
+  /// built-in variables.
+  pub bare: bool,
+  /// Variables given with `-D key=value`, overriding every other value.
+  pub defines: HashMap<String, String>,
+  /// Environment whose overlay of the template's `[variables]` is merged.
+  pub env: Option<String>,
+  /// Patterns excluded from the project on top of the template's filters.
+  pub excludes: Vec<String>,
+  /// Request of `generate --input-json`, combined with the flags once they're
+  /// parsed.
+  pub input: Option<InputRequest>,
+  /// Print the resolved variables as JSON.
+  pub dump_vars: bool,
+  /// Exit once the variables are printed, without generating the project.
+  pub dump_vars_only: bool,
+  /// List the built-in Handlebars helpers, instead of generating a project.
+  pub helpers: bool,
 }
 
 impl Arguments {
-  pub fn new(name: &str, path: &str, branch: Option<&str>) -> Arguments {
-    Arguments {
+  pub fn new(
+    name: &str,
+    path: &str,
+    branch: Option<&str>,
+  ) -> Result<Arguments> {
+    Ok(Arguments {
       project: ProjectInfo::from(name),
-      template: TemplateOptions::new(path, branch),
-      verbose: false,
-      quiet: false,
+      template: TemplateOptions::new(path, branch)?,
+      ..Arguments::default()
+    })
+  }
+
+  /// Write what to do once the project is generated into `out`, followed by
+  /// the template's `steps`, see [`Template::next_steps`].
+  ///
+  /// [`Template::next_steps`]: ../template/struct.Template.html#method.next_steps
+  ///
+  /// With `cd`, the project's absolute path is written last on its own line,
+  /// and alone with `quiet` or `summary-only`, so it can be used by a shell
+  /// function e.g:
+  ///
+  /// ```sh
+  /// function pnew() { cd "$(project --cd --quiet new "$@")"; }
+  /// ```
+  pub fn write_next_steps<W: Write>(
+    &self,
+    steps: &[String],
+    out: &mut W,
+  ) -> io::Result<()> {
+    if self.verbosity.shows_progress() {
+      writeln!(out, "{}", style("Go to project's directory:").bold())?;
+      let path = self.project.rel_path();
+      if cfg!(unix) {
+        writeln!(out, "\t$ cd {}", path.display())?;
+        writeln!(out, "\t$ ls")?;
+      } else {
+        writeln!(out, "\t> cd {}", path.display())?;
+        writeln!(out, "\t> dir")?;
+      }
+      if !steps.is_empty() {
+        writeln!(out, "{}", style("Next steps:").bold())?;
+        for step in steps {
+          writeln!(out, "\t{}", step)?;
+        }
+      }
+    }
+    if self.cd {
+      writeln!(out, "{}", self.project.path().display())?;
     }
+
+    Ok(())
+  }
+
+  /// Whether the arguments generate a project, rather than running a
+  /// subcommand such as `config`, `cache`, `--check` or `batch`.
+  pub fn generates(&self) -> bool {
+    self.config.is_none()
+      && self.cache.is_none()
+      && self.diff.is_none()
+      && self.vars_file.is_none()
+      && !self.check
+      && self.batch.is_none()
+      && !self.helpers
+  }
+
+  /// Open the generated project, i.e. `--open`: with VS Code when running in
+  /// its terminal, else with `$VISUAL` or `$EDITOR`, else with the platform's
+  /// file opener e.g. `xdg-open`.
+  ///
+  /// Editors which can't be run fall back to the file opener.
+  pub fn open_project(&self) -> io::Result<()> {
+    open_with(&self.project.path(), |key| env::var_os(key))
   }
 }
 
-impl From<&str> for Arguments {
-  fn from(path: &str) -> Arguments {
-    Arguments {
+impl TryFrom<&str> for Arguments {
+  type Error = Error;
+
+  fn try_from(path: &str) -> Result<Arguments> {
+    Ok(Arguments {
       project: ProjectInfo::default(),
-      template: TemplateOptions::new(path, None),
-      verbose: false,
-      quiet: false,
-    }
+      template: TemplateOptions::new(path, None)?,
+      ..Arguments::default()
+    })
   }
 }
 
@@ -96,12 +311,183 @@ impl Default for Arguments {
     Arguments {
       project: ProjectInfo::default(),
       template: TemplateOptions::default(),
-      verbose: false,
-      quiet: false,
+      verbosity: Verbosity::Normal,
+      json: false,
+      update: false,
+      since: None,
+      render_only: None,
+      watch: false,
+      preserve_timestamps: false,
+      expect_tree_sha: None,
+      run: Vec::new(),
+      dry_run: false,
+      template_args: Vec::new(),
+      open: false,
+      print_next_steps: false,
+      only: None,
+      only_as_root: false,
+      archive: None,
+      log_file: None,
+      trace_renders: false,
+      json_logs: false,
+      no_first_run: false,
+      interactive: false,
+      cargo_generate: false,
+      abort_on_warning: false,
+      no_cache: false,
+      write_lock: false,
+      locked: false,
+      diff: None,
+      vars_file: None,
+      check: false,
+      batch: None,
+      jobs: 1,
+      env_file: None,
+      env_prefix: None,
+      inherited: HashMap::new(),
+      env_lowercase: false,
+      strict_walk: false,
+      keep_going: false,
+      config: None,
+      cd: false,
+      assume_yes: false,
+      confirm: false,
+      keep_template_toml: false,
+      force: false,
+      print_diff_after: false,
+      i_know_what_im_doing: false,
+      rename_case: None,
+      git_init: false,
+      commit_message: None,
+      config_name: None,
+      template_toml_strict: false,
+      cache_dir: None,
+      max_clone_size: None,
+      cache: None,
+      bare: false,
+      defines: HashMap::new(),
+      env: None,
+      excludes: Vec::new(),
+      input: None,
+      dump_vars: false,
+      dump_vars_only: false,
+      helpers: false,
     }
   }
 }
 
+/// Generation request of `generate --input-json`, with the template & the
+/// project's name, and optionally everything else given on the command line
+/// otherwise, e.g:
+///
+/// ```json
+/// {
+///   "template": "owner/repo",
+///   "name": "my-project",
+///   "branch": "develop",
+///   "variables": { "license": "MIT" },
+///   "exclude": ["*.log"],
+///   "force": true
+/// }
+/// ```
+///
+/// Flags of the request are combined with the ones of the command line.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputRequest {
+  /// Local path or remote repository of the template.
+  pub template: String,
+  /// Name of the project, i.e. its directory.
+  pub name: String,
+  /// Branch of a remote template.
+  #[serde(default)]
+  pub branch: Option<String>,
+  /// Ignore the template's config file, see `new --bare`.
+  #[serde(default)]
+  pub bare: bool,
+  /// Variables overriding every other value, see `-D key=value`.
+  #[serde(default)]
+  pub variables: HashMap<String, String>,
+  /// Patterns excluded from the project on top of the template's filters.
+  #[serde(default)]
+  pub exclude: Vec<String>,
+  /// Subdirectory of the template, the only one generated, see `--only`.
+  #[serde(default)]
+  pub only: Option<PathBuf>,
+  /// Names of the template's `[scripts]` to run, see `--run`.
+  #[serde(default)]
+  pub run: Vec<String>,
+  /// Arguments passed to the `[scripts]`, see `--template-arg`.
+  #[serde(default)]
+  pub template_args: Vec<String>,
+  /// Overwrite the project's directory, see `--force`.
+  #[serde(default)]
+  pub force: bool,
+  /// Initialize a git repository in the project, see `--git-init`.
+  #[serde(default)]
+  pub git_init: bool,
+  /// Message of the `git_init` commit, see `--commit-message`.
+  #[serde(default)]
+  pub commit_message: Option<String>,
+  /// Keep generating when a file fails, see `--keep-going`.
+  #[serde(default)]
+  pub keep_going: bool,
+}
+
+impl InputRequest {
+  /// Read the request from the JSON file at `path`, or stdin if it's `-`.
+  pub fn read(path: &str) -> Result<InputRequest> {
+    let content = if path == "-" {
+      let mut content = String::new();
+      io::stdin().read_to_string(&mut content)?;
+      content
+    } else {
+      fs::read_to_string(path)?
+    };
+    InputRequest::parse(&content)
+  }
+
+  /// Parse & validate the request from its JSON `content`.
+  pub fn parse(content: &str) -> Result<InputRequest> {
+    let request: InputRequest =
+      serde_json::from_str(content).map_err(|err| {
+        Error::new(
+          ErrorKind::ParseError,
+          &format!("invalid --input-json request: {}", err),
+        )
+      })?;
+
+    let required = [("template", &request.template), ("name", &request.name)];
+    if let Some((field, _)) =
+      required.iter().find(|(_, value)| value.trim().is_empty())
+    {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!("invalid --input-json request: {:?} is empty", field),
+      ));
+    }
+
+    Ok(request)
+  }
+
+  /// Combine the request's options with the command line's `args`.
+  ///
+  /// Nothing is asked, as with `--assume-yes`.
+  fn apply(self, args: &mut Arguments) {
+    args.assume_yes = true;
+    args.defines.extend(self.variables);
+    args.excludes.extend(self.exclude);
+    args.only = self.only.or_else(|| args.only.take());
+    args.run.extend(self.run);
+    args.template_args.extend(self.template_args);
+    args.force |= self.force;
+    args.git_init |= self.git_init;
+    args.commit_message =
+      self.commit_message.or_else(|| args.commit_message.take());
+    args.keep_going |= self.keep_going;
+  }
+}
+
 /// Project command line utilities.
 pub struct Cli<'a> {
   /// Command line arguments.
@@ -138,10 +524,17 @@ impl<'a> Cli<'a> {
 impl<'a> Cli<'a> {
   /// Creates default `clap::ArgMatches` and builts it in `Cli::parse_args(...)`.
   fn default_args() -> clap::ArgMatches<'a> {
+    Self::app().get_matches()
+  }
+
+  /// Command line application, with its subcommands & flags.
+  fn app() -> App<'a, 'a> {
     App::new(clap::crate_name!())
       .version(clap::crate_version!())
       .about(clap::crate_description!())
       .author(clap::crate_authors!())
+      // `--version` is handled in `Cli::parse_args(...)` for `--format`.
+      .setting(AppSettings::DisableVersion)
       // Create a new project project
       .subcommand(
         // $ project new <template-path> <project-name>
@@ -155,6 +548,9 @@ impl<'a> Cli<'a> {
             Arg::with_name("name")
               .help("Name of the project / directory name.")
               .index(2).allow_hyphen_values(true),
+            Arg::with_name("bare")
+              .long("bare")
+              .help("Ignore the template's config file, only substituting the built-in placeholders e.g. {{project-name}}."),
           ])
       )
       .subcommand(
@@ -200,11 +596,185 @@ impl<'a> Cli<'a> {
               .long("branch")
               .help("Branch name to checkout.")
               .takes_value(true),
+          )
+          .arg(
+            Arg::with_name("name")
+              .long("name")
+              .help("Name of the project, instead of the current directory's name.")
+              .takes_value(true),
+          )
+          .arg(
+            Arg::with_name("name-from-remote")
+              .long("name-from-remote")
+              .conflicts_with("name")
+              .help("Name the project after the repository of the current directory's `origin` remote, if it has one."),
+          ),
+      )
+      .subcommand(
+        // $ project diff-templates <a> <b>
+        App::new("diff-templates")
+          .about("Compare the projects generated by two templates.")
+          .setting(AppSettings::ArgRequiredElseHelp)
+          .args(&[
+            Arg::with_name("a")
+              .help("Path or URL to a template. Append `#<branch>` to select a branch of a remote template.")
+              .index(1)
+              .required(true),
+            Arg::with_name("b")
+              .help("Path or URL to the template to compare against.")
+              .index(2)
+              .required(true),
+          ]),
+      )
+      .subcommand(
+        // $ project batch services.toml --jobs 4
+        App::new("batch")
+          .about("Generates every project of a TOML manifest, reporting which failed.")
+          .setting(AppSettings::ArgRequiredElseHelp)
+          .args(&[
+            Arg::with_name("manifest")
+              .help("TOML manifest listing each project as a `[[project]]` with its name, template & vars.")
+              .index(1)
+              .required(true),
+            Arg::with_name("jobs")
+              .long("jobs")
+              .short("j")
+              .takes_value(true)
+              .value_name("n")
+              .default_value("1")
+              .validator(|jobs| match jobs.parse::<usize>() {
+                Ok(jobs) if jobs > 0 => Ok(()),
+                _ => Err(format!("expected a positive number, got {:?}", jobs)),
+              })
+              .help("Number of projects generated at once."),
+          ]),
+      )
+      .subcommand(
+        // $ project check <template> <path>
+        App::new("check")
+          .about("Compare a generated project with its template, failing if any generated file differs or is missing.")
+          .setting(AppSettings::ArgRequiredElseHelp)
+          .args(&[
+            Arg::with_name("template")
+              .help("Path or URL to the template. Append `#<branch>` to select a branch of a remote template.")
+              .index(1)
+              .required(true),
+            Arg::with_name("path")
+              .help("Directory of the generated project. Defaults to the current directory.")
+              .index(2),
+          ]),
+      )
+      .subcommand(
+        // $ project render-only <template> <glob> <path>
+        App::new("render-only")
+          .about("Re-renders the template files matching a glob into an existing project, leaving the others untouched.")
+          .setting(AppSettings::ArgRequiredElseHelp)
+          .args(&[
+            Arg::with_name("template")
+              .help("Path or URL to the template. Append `#<branch>` to select a branch of a remote template.")
+              .index(1)
+              .required(true),
+            Arg::with_name("glob")
+              .help("Glob of the template files to re-render, relative to the template, e.g. \"src/*.py\".")
+              .index(2)
+              .required(true),
+            Arg::with_name("path")
+              .help("Directory of the generated project. Defaults to the current directory.")
+              .index(3),
+          ]),
+      )
+      .subcommand(
+        // $ project validate-vars <template> --vars-file vars.toml
+        App::new("validate-vars")
+          .about("Check a vars file against a template's `[prompts]` & `[validate]`, without generating a project.")
+          .setting(AppSettings::ArgRequiredElseHelp)
+          .args(&[
+            Arg::with_name("template")
+              .help("Path or URL to a template. Append `#<branch>` to select a branch of a remote template.")
+              .index(1)
+              .required(true),
+            Arg::with_name("vars-file")
+              .long("vars-file")
+              .takes_value(true)
+              .value_name("file")
+              .required(true)
+              .help("TOML file of the variables' values, or JSON if its extension is `.json`, as strings, numbers or booleans."),
+          ]),
+      )
+      .subcommand(
+        // $ project config get <key>
+        // $ project config set <key> <value>
+        App::new("config")
+          .about("Read or write user defaults.")
+          .setting(AppSettings::SubcommandRequiredElseHelp)
+          .subcommand(
+            App::new("get")
+              .about("Print the value of a default.")
+              .arg(
+                Arg::with_name("key")
+                  .required(true)
+                  .possible_values(defaults::KEYS),
+              ),
+          )
+          .subcommand(
+            App::new("set")
+              .about("Set the value of a default.")
+              .args(&[
+                Arg::with_name("key")
+                  .index(1)
+                  .required(true)
+                  .possible_values(defaults::KEYS),
+                Arg::with_name("value").index(2).required(true),
+              ]),
           ),
       )
+      .subcommand(
+        // $ project cache clean --dry-run
+        App::new("cache")
+          .about("Manage the cache of remote templates.")
+          .setting(AppSettings::SubcommandRequiredElseHelp)
+          .subcommand(
+            App::new("clean")
+              .about("Remove the cached remote templates.")
+              .args(&[
+                Arg::with_name("dry-run")
+                  .long("dry-run")
+                  .help("List the cached templates without removing them."),
+                Arg::with_name("force")
+                  .long("force")
+                  .help("Clean the `--cache-dir` even if it isn't the default cache."),
+              ]),
+          ),
+      )
+      .subcommand(
+        // $ project generate --input-json request.json
+        App::new("generate")
+          .about("Generates a project from a JSON request, without prompting.")
+          .args(&[
+            Arg::with_name("input-json")
+              .long("input-json")
+              .takes_value(true)
+              .value_name("file")
+              .required(true)
+              .help("JSON request with the template, the project's name, its variables & flags, or `-` for stdin."),
+          ])
+      )
+      .subcommand(
+        // $ project helpers
+        App::new("helpers")
+          .about("List the built-in Handlebars helpers, with their arity & usage."),
+      )
       .args(&[
         // Flags: [must have `.short()` or `.long()`]
         // Options: [must have either `.short()` or `.long()` & `takes_value(true)]
+        Arg::with_name("version")
+          .long("version")
+          .help("Prints version information"),
+        Arg::with_name("format")
+          .long("format")
+          .takes_value(true)
+          .possible_values(&["text", "json"])
+          .help("Format of the version information, or of the generated project's summary."),
         Arg::with_name("verbose")
           .short("V")
           .long("verbose")
@@ -212,37 +782,513 @@ impl<'a> Cli<'a> {
         Arg::with_name("quiet")
           .short("q")
           .long("quiet")
+          .conflicts_with("verbose")
           .help("Supress all output. Progress is not reported to the standard error stream."),
+        Arg::with_name("silent")
+          .long("silent")
+          .conflicts_with_all(&["quiet", "verbose"])
+          .help("Supress all output, even errors. Only the exit code reports a failure."),
+        Arg::with_name("summary-only")
+          .long("summary-only")
+          .conflicts_with_all(&["silent", "quiet", "verbose"])
+          .help("Only print a one-line summary of the generated project."),
+        Arg::with_name("assume-yes")
+          .short("y")
+          .long("assume-yes")
+          .help("Answer every prompt with its default, and overwrite existing files without asking."),
+        Arg::with_name("interactive")
+          .short("i")
+          .long("interactive")
+          .help("Prompt for the template's `[prompts]` variables, and confirm the project's summary like `--confirm`."),
+        Arg::with_name("confirm")
+          .long("confirm")
+          .help("Show the template, target, variables & number of files, and ask to confirm before generating anything. Needs a terminal, unless with `--assume-yes`."),
+        Arg::with_name("cargo-generate-compat")
+          .long("cargo-generate-compat")
+          .help("Render a `cargo-generate` template, converting its `{{ var | filter }}` syntax."),
+        Arg::with_name("abort-on-warning")
+          .long("abort-on-warning")
+          .help("Treat warnings as errors and stop generating the project."),
+        Arg::with_name("no-cache")
+          .long("no-cache")
+          .help("Clone a fresh copy of remote templates instead of using the cached one."),
+        Arg::with_name("write-lock")
+          .long("write-lock")
+          .help("Record the commit a remote template resolved to in `project.lock`."),
+        Arg::with_name("locked")
+          .long("locked")
+          .help("Refuse to generate unless the remote template matches `project.lock`."),
+        Arg::with_name("env-file")
+          .long("env-file")
+          .takes_value(true)
+          .value_name("path")
+          .help("Load additional variables from a `.env` file. They override the ones in `template.toml`."),
+        Arg::with_name("env-lowercase")
+          .long("env-lowercase")
+          .requires("env-file")
+          .help("Lowercase the keys of `--env-file` e.g. `SERVICE_NAME` is used as `{{ service_name }}`."),
+        Arg::with_name("env-prefix")
+          .long("env-prefix")
+          .takes_value(true)
+          .value_name("prefix")
+          .help("Import the environment variables starting with this prefix as variables, without it & lowercased, e.g. `PROJECT_VAR_DB` as `{{db}}` with `PROJECT_VAR_`. `-D` overrides them."),
+        Arg::with_name("inherit-from")
+          .long("inherit-from")
+          .takes_value(true)
+          .value_name("manifest")
+          .help("Inherit the authors, license, edition & name prefix of a parent project from its `Cargo.toml` or `package.json`. `-D` overrides them."),
+        Arg::with_name("strict-walk")
+          .long("strict-walk")
+          .help("Fail instead of skipping template files & directories that can't be read."),
+        Arg::with_name("keep-going")
+          .long("keep-going")
+          .help("Skip files which fail to render or copy, and report them once done."),
+        Arg::with_name("workspace")
+          .long("workspace")
+          .takes_value(true)
+          .value_name("dir")
+          .help("Create the project in `<dir>/<name>`, creating `<dir>` if needed."),
+        Arg::with_name("force")
+          .long("force")
+          .help("Overwrite the project directory if it already exists."),
+        Arg::with_name("watch")
+          .long("watch")
+          .help("Regenerate the project whenever a file of its local template changes, while authoring the template."),
+        Arg::with_name("print-diff-after")
+          .long("print-diff-after")
+          .help("Report the files the generation added or changed in the project directory, e.g. when generating over existing files with `--force`."),
+        Arg::with_name("i-know-what-im-doing")
+          .long("i-know-what-im-doing")
+          .help("Generate into the root, home or a system directory, which is refused otherwise."),
+        Arg::with_name("rename-case")
+          .long("rename-case")
+          .takes_value(true)
+          .value_name("case")
+          .possible_values(Case::NAMES)
+          .help("Convert the names renamed by `[rename]` to a case, e.g. `snake` for Python packages."),
+        Arg::with_name("define")
+          .short("D")
+          .long("define")
+          .takes_value(true)
+          .multiple(true)
+          .number_of_values(1)
+          .value_name("key=value")
+          .validator(|define| {
+            if define.contains('=') {
+              Ok(())
+            } else {
+              Err(format!("expected `key=value`, got {:?}", define))
+            }
+          })
+          .help("Set a template variable, overriding its value from every other source."),
+        Arg::with_name("env")
+          .long("env")
+          .takes_value(true)
+          .value_name("name")
+          .help("Merge the template's `[variables.<name>]` overlay over its `[variables]`, e.g. `--env prod`."),
+        Arg::with_name("dump-vars")
+          .long("dump-vars")
+          .takes_value(true)
+          .value_name("format")
+          .possible_values(&["json"])
+          .help("Print the resolved template variables, e.g. for other tools to reuse."),
+        Arg::with_name("dump-vars-only")
+          .long("dump-vars-only")
+          .requires("dump-vars")
+          .help("Exit once the variables are printed with `--dump-vars`, without generating the project."),
+        Arg::with_name("git-init")
+          .long("git-init")
+          .help("Initialize a git repository in the generated project and commit its files."),
+        Arg::with_name("update")
+          .long("update")
+          .conflicts_with("locked")
+          .help("Only regenerate the template files changed since the commit in `project.lock`, then update it."),
+        Arg::with_name("since")
+          .long("since")
+          .takes_value(true)
+          .value_name("commit")
+          .conflicts_with("locked")
+          .help("Only regenerate the template files changed since <commit>, instead of the one in `project.lock`."),
+        Arg::with_name("preserve-timestamps")
+          .long("preserve-timestamps")
+          .help("Keep the modification time of the template's files. Rendered files get $SOURCE_DATE_EPOCH instead, if it's set."),
+        Arg::with_name("expect-tree-sha")
+          .long("expect-tree-sha")
+          .takes_value(true)
+          .value_name("sha")
+          .help("Abort unless the template's git tree, or the commit it's at, has this full 40-character SHA, and it has no uncommitted changes."),
+        Arg::with_name("dry-run")
+          .long("dry-run")
+          .conflicts_with("run")
+          .help("Print the files the template would generate, and how, without generating them."),
+        Arg::with_name("run")
+          .long("run")
+          .takes_value(true)
+          .value_name("script")
+          .multiple(true)
+          .number_of_values(1)
+          .help("Run a script of the template's `[scripts]` in the generated project. Can be repeated, scripts run in order."),
+        Arg::with_name("template-arg")
+          .long("template-arg")
+          .takes_value(true)
+          .value_name("arg")
+          .multiple(true)
+          .number_of_values(1)
+          .allow_hyphen_values(true)
+          .help("Pass an argument to the template's `[scripts]`, as `{{args}}`. Can be repeated."),
+        Arg::with_name("print-next-steps")
+          .long("print-next-steps")
+          .help("Print the template's `next_steps`, rendered for the project, without generating it."),
+        Arg::with_name("only")
+          .long("only")
+          .takes_value(true)
+          .value_name("path")
+          .help("Only generate the template's subdirectory <path>, e.g. \"backend\". Its files keep their path in the template."),
+        Arg::with_name("only-as-root")
+          .long("only-as-root")
+          .requires("only")
+          .help("Generate the `--only` subdirectory as the project's root, with filters & renames relative to it."),
+        Arg::with_name("no-first-run")
+          .long("no-first-run")
+          .help("Don't create the user-level defaults, with the discovered author, if they're missing."),
+        Arg::with_name("log-file")
+          .long("log-file")
+          .takes_value(true)
+          .value_name("path")
+          .help("Write a debug log into <path>, whatever the verbosity. The file is truncated first."),
+        Arg::with_name("trace-renders")
+          .long("trace-renders")
+          .help("Log the variables referenced by each rendered file, with their values, into the `--log-file` or stderr."),
+        Arg::with_name("json-logs")
+          .long("json-logs")
+          .conflicts_with("log-file")
+          .help("Log each event of the generation into stderr as a line of JSON, e.g. {\"event\":\"file_rendered\",\"path\":\"...\"}, instead of the human output."),
+        Arg::with_name("max-clone-size")
+          .long("max-clone-size")
+          .takes_value(true)
+          .value_name("bytes")
+          .validator(|bytes| bytes.parse::<u64>().map(|_| ()).map_err(|err| err.to_string()))
+          .help("Abort cloning a remote template once it received more than <bytes>."),
+        Arg::with_name("cache-dir")
+          .long("cache-dir")
+          .takes_value(true)
+          .value_name("path")
+          .help("Directory remote templates are cloned into. Defaults to the user's cache directory, e.g. ~/.cache/project."),
+        Arg::with_name("config-name")
+          .long("config-name")
+          .takes_value(true)
+          .value_name("file")
+          .help("Name of the template's config file, e.g. \"scaffold.toml\". Defaults to \"template.toml\"."),
+        Arg::with_name("template-toml-strict")
+          .long("template-toml-strict")
+          .help("Fail on unknown keys of the template's config file, e.g. a misspelled `[filtres]`, instead of ignoring them with a warning."),
+        Arg::with_name("commit-message")
+          .long("commit-message")
+          .takes_value(true)
+          .value_name("message")
+          .requires("git-init")
+          .help("Message of the `--git-init` commit, rendered with the template's variables e.g. \"Initial commit for {{project-name}}\"."),
+        Arg::with_name("keep-template-toml")
+          .long("keep-template-toml")
+          .help("Copy the template's `template.toml` into the generated project, with its placeholders unresolved, but without its `extends`, `[git]`, `[validate]` & `[scripts]`."),
+        Arg::with_name("archive")
+          .long("archive")
+          .takes_value(true)
+          .value_name("file")
+          .validator(|file| archive::gzipped(Path::new(&file)).map(|_| ()).map_err(|err| err.to_string()))
+          .conflicts_with_all(&["update", "since", "git-init", "run", "open", "cd", "dry-run"])
+          .help("Generate the project into a .tar, .tar.gz or .tgz archive instead of a directory."),
+        Arg::with_name("open")
+          .long("open")
+          .help("Open the generated project with VS Code, $VISUAL or $EDITOR, or the file manager."),
+        Arg::with_name("cd")
+          .long("cd")
+          .help("Print the generated project's path last, to `cd` into it. Only the path is printed with `--quiet`."),
       ])
-      .get_matches()
   }
 
   /// Builds the default argument created in `Cli::default_args()` and retrives the values.
   fn parse_args(matches: &clap::ArgMatches) -> Arguments {
+    if matches.is_present("version") {
+      let version = Version::new();
+      match matches.value_of("format") {
+        Some("json") => println!("{}", version.to_json()),
+        _ => println!("{}", version),
+      }
+      std::process::exit(0);
+    }
+
     // Process subcommands.
-    let mut args = match matches.subcommand() {
+    let verbosity = verbosity(matches);
+    let mut args = Self::parse_subcommand(matches)
+      .unwrap_or_else(|err| exit_with_error(err, matches, verbosity));
+
+    args.verbosity = verbosity;
+    if args.generates() && verbosity.shows_progress() {
+      eprintln!(
+        "{} {} {}",
+        emoji::WRENCH,
+        style("Creating project: ").bold().white(),
+        style(&args.project.name).bold().yellow()
+      );
+    }
+    args.json = matches.value_of("format") == Some("json");
+    args.since = matches.value_of("since").map(String::from);
+    args.update = matches.is_present("update") || args.since.is_some();
+    args.preserve_timestamps = matches.is_present("preserve-timestamps");
+    args.expect_tree_sha =
+      matches.value_of("expect-tree-sha").map(String::from);
+    args.run = matches
+      .values_of("run")
+      .into_iter()
+      .flatten()
+      .map(String::from)
+      .collect();
+    args.dry_run = matches.is_present("dry-run");
+    args.template_args = matches
+      .values_of("template-arg")
+      .into_iter()
+      .flatten()
+      .map(String::from)
+      .collect();
+    args.interactive = matches.is_present("interactive");
+    args.cargo_generate = matches.is_present("cargo-generate-compat");
+    args.abort_on_warning = matches.is_present("abort-on-warning");
+    args.no_cache = matches.is_present("no-cache");
+    args.write_lock = matches.is_present("write-lock");
+    args.locked = matches.is_present("locked");
+    args.env_file = matches.value_of("env-file").map(PathBuf::from);
+    args.env_prefix = matches.value_of("env-prefix").map(String::from);
+    if let Some(manifest) = matches.value_of("inherit-from") {
+      args.inherited = inherit::inherit_from(Path::new(manifest))
+        .unwrap_or_else(|err| {
+          let message =
+            format!("couldn't inherit variables from {:?}: {}", manifest, err);
+          exit_with_error(message, matches, verbosity)
+        });
+    }
+    args.env_lowercase = matches.is_present("env-lowercase");
+    args.strict_walk = matches.is_present("strict-walk");
+    args.keep_going = matches.is_present("keep-going");
+    args.cd = matches.is_present("cd");
+    args.open = matches.is_present("open");
+    args.print_next_steps = matches.is_present("print-next-steps");
+    args.only = matches.value_of("only").map(PathBuf::from);
+    args.only_as_root = matches.is_present("only-as-root");
+    args.archive = matches.value_of("archive").map(PathBuf::from);
+    args.log_file = matches.value_of("log-file").map(PathBuf::from);
+    args.trace_renders = matches.is_present("trace-renders");
+    args.json_logs = matches.is_present("json-logs");
+    args.no_first_run = matches.is_present("no-first-run");
+    args.assume_yes = matches.is_present("assume-yes");
+    args.confirm = matches.is_present("confirm");
+    args.keep_template_toml = matches.is_present("keep-template-toml");
+    args.force = matches.is_present("force");
+    args.print_diff_after = matches.is_present("print-diff-after");
+    args.watch = matches.is_present("watch");
+    args.i_know_what_im_doing = matches.is_present("i-know-what-im-doing");
+    args.defines = matches
+      .values_of("define")
+      .into_iter()
+      .flatten()
+      .filter_map(|define| define.split_once('='))
+      .map(|(key, value)| (key.to_string(), value.to_string()))
+      .collect();
+    args.env = matches.value_of("env").map(String::from);
+    args.dump_vars = matches.is_present("dump-vars");
+    args.dump_vars_only = matches.is_present("dump-vars-only");
+    args.git_init = matches.is_present("git-init");
+    args.commit_message = matches.value_of("commit-message").map(String::from);
+    args.config_name = matches.value_of("config-name").map(String::from);
+    args.template_toml_strict = matches.is_present("template-toml-strict");
+    args.cache_dir = matches.value_of("cache-dir").map(PathBuf::from);
+    args.max_clone_size = matches
+      .value_of("max-clone-size")
+      .and_then(|bytes| bytes.parse().ok());
+    args.rename_case = matches
+      .value_of("rename-case")
+      .and_then(|case| case.parse().ok());
+    if let Some(request) = args.input.take() {
+      request.apply(&mut args);
+    }
+
+    // Ask before using a cached remote template, unless told otherwise.
+    let cache = if args.no_cache {
+      CachePolicy::Refresh
+    } else if !args.verbosity.shows_progress() || args.assume_yes {
+      CachePolicy::Use
+    } else {
+      CachePolicy::Ask
+    };
+    for template in std::iter::once(&mut args.template).chain(&mut args.diff) {
+      if let TemplateOptions::Remote(opts) = template {
+        opts.set_cache_policy(cache);
+        if let Some(dir) = &args.cache_dir {
+          opts.set_cache_dir(dir);
+        }
+        if let Some(bytes) = args.max_clone_size {
+          opts.set_max_clone_size(bytes);
+        }
+      }
+    }
+    args
+  }
+
+  /// Returns the arguments of the subcommand used.
+  fn parse_subcommand(matches: &clap::ArgMatches) -> Result<Arguments> {
+    let args = match matches.subcommand() {
       // "new" subcommand.
       ("new", Some(sub_new)) => {
         // project new <local> <name>
         let path = sub_new.value_of("template").unwrap();
-        let name = sub_new.value_of("name").unwrap();
-        Arguments::new(name, path, None)
+        let name = project_path(matches, sub_new.value_of("name").unwrap())?;
+        Arguments {
+          bare: sub_new.is_present("bare"),
+          ..Arguments::new(&name, path, None)?
+        }
       }
       // "git" subcommand.
       ("git", Some(sub_git)) => {
         // project git <remote> <name>
         let path = sub_git.value_of("remote").unwrap();
-        let name = sub_git.value_of("name").unwrap();
+        let name = project_path(matches, sub_git.value_of("name").unwrap())?;
         let branch = sub_git.value_of("branch");
-        Arguments::new(name, path, branch)
+        Arguments::new(&name, path, branch)?
       }
       // "init" subcommand.
       ("init", Some(sub_init)) => {
-        // project init <repo>
+        // project init <repo> --name <name>
         let path = sub_init.value_of("repo").unwrap();
         // TODO: Add `branch` to arguments.
-        Arguments::from(path)
+        let mut args = Arguments::try_from(path)?;
+        // The project is still generated into the current directory.
+        if let Some(name) = sub_init.value_of("name") {
+          args.project.name = name.to_string();
+        } else if sub_init.is_present("name-from-remote") {
+          // Keep the directory's name otherwise.
+          match git::origin_repo_name(&args.project.path) {
+            Some(name) => args.project.name = name,
+            None => log::warn!("no `origin` remote to name the project after"),
+          }
+        }
+        args
       }
+      // "diff-templates" subcommand.
+      ("diff-templates", Some(sub_diff)) => {
+        // project diff-templates <a> <b>
+        let (a, a_branch) = split_branch(sub_diff.value_of("a").unwrap());
+        let (b, b_branch) = split_branch(sub_diff.value_of("b").unwrap());
+        Arguments {
+          template: TemplateOptions::new(a, a_branch)?,
+          diff: Some(TemplateOptions::new(b, b_branch)?),
+          ..Arguments::default()
+        }
+      }
+      // "batch" subcommand.
+      ("batch", Some(sub_batch)) => {
+        // project batch <manifest> --jobs <n>
+        let path = Path::new(sub_batch.value_of("manifest").unwrap());
+        let mut manifest = Manifest::read(path)?;
+        for project in &mut manifest.projects {
+          project.name = project_path(matches, &project.name)?;
+        }
+        Arguments {
+          batch: Some(manifest),
+          jobs: sub_batch
+            .value_of("jobs")
+            .and_then(|jobs| jobs.parse().ok())
+            .unwrap_or(1),
+          ..Arguments::default()
+        }
+      }
+      // "check" subcommand.
+      ("check", Some(sub_check)) => {
+        // project check <template> <path>
+        let (path, branch) =
+          split_branch(sub_check.value_of("template").unwrap());
+        Arguments {
+          project: existing_project(sub_check.value_of("path"))?,
+          template: TemplateOptions::new(path, branch)?,
+          check: true,
+          ..Arguments::default()
+        }
+      }
+      // "render-only" subcommand.
+      ("render-only", Some(sub_render)) => {
+        // project render-only <template> <glob> <path>
+        let (path, branch) =
+          split_branch(sub_render.value_of("template").unwrap());
+        Arguments {
+          project: existing_project(sub_render.value_of("path"))?,
+          template: TemplateOptions::new(path, branch)?,
+          render_only: sub_render.value_of("glob").map(String::from),
+          ..Arguments::default()
+        }
+      }
+      // "validate-vars" subcommand.
+      ("validate-vars", Some(sub_validate)) => {
+        // project validate-vars <template> --vars-file <file>
+        let (path, branch) =
+          split_branch(sub_validate.value_of("template").unwrap());
+        Arguments {
+          template: TemplateOptions::new(path, branch)?,
+          vars_file: sub_validate.value_of("vars-file").map(PathBuf::from),
+          ..Arguments::default()
+        }
+      }
+      // "config" subcommand.
+      ("config", Some(sub_config)) => {
+        // project config get <key>
+        // project config set <key> <value>
+        let command = match sub_config.subcommand() {
+          ("get", Some(get)) => {
+            ConfigCommand::Get(get.value_of("key").unwrap().to_string())
+          }
+          ("set", Some(set)) => ConfigCommand::Set(
+            set.value_of("key").unwrap().to_string(),
+            set.value_of("value").unwrap().to_string(),
+          ),
+          _ => unreachable!(),
+        };
+        Arguments {
+          config: Some(command),
+          ..Arguments::default()
+        }
+      }
+      // "cache" subcommand.
+      ("cache", Some(sub_cache)) => {
+        // project cache clean --dry-run
+        let command = match sub_cache.subcommand() {
+          ("clean", Some(clean)) => CacheCommand::Clean {
+            dry_run: clean.is_present("dry-run"),
+            force: clean.is_present("force"),
+          },
+          _ => unreachable!(),
+        };
+        Arguments {
+          cache: Some(command),
+          ..Arguments::default()
+        }
+      }
+      // "generate" subcommand.
+      ("generate", Some(sub_generate)) => {
+        // project generate --input-json <file>
+        let request =
+          InputRequest::read(sub_generate.value_of("input-json").unwrap())?;
+        let name = project_path(matches, &request.name)?;
+        let mut args = Arguments {
+          bare: request.bare,
+          ..Arguments::new(&name, &request.template, request.branch.as_deref())?
+        };
+        // Flags are only parsed afterwards.
+        args.input = Some(request);
+        args
+      }
+      // "helpers" subcommand.
+      ("helpers", Some(_)) => Arguments {
+        helpers: true,
+        ..Arguments::default()
+      },
       _ => {
         // Unrecognized command or above subcommands was not used.
         eprintln!(
@@ -255,8 +1301,463 @@ impl<'a> Cli<'a> {
       }
     };
 
-    args.verbose = matches.is_present("verbose");
-    args.quiet = matches.is_present("quiet");
+    Ok(args)
+  }
+}
+
+/// Path of the project named `name`, which is `<workspace>/<name>` with
+/// `--workspace`. The workspace is created if needed, but the project
+/// mustn't exist unless `--force` is used.
+fn project_path(matches: &clap::ArgMatches, name: &str) -> Result<String> {
+  let workspace = match matches.value_of("workspace") {
+    Some(workspace) => Path::new(workspace),
+    None => return Ok(name.to_string()),
+  };
+
+  let path = workspace.join(name);
+  if path.exists() && !matches.is_present("force") {
+    return Err(Error::new(
+      ErrorKind::Error,
+      &format!(
+        "{} already exists, use `--force` to overwrite it.",
+        path.display()
+      ),
+    ));
+  }
+  fs::create_dir_all(workspace)?;
+
+  Ok(path.to_string_lossy().to_string())
+}
+
+/// The existing project in directory `path`, or the current directory.
+fn existing_project(path: Option<&str>) -> Result<ProjectInfo> {
+  match path {
+    Some(path) if !Path::new(path).is_dir() => Err(Error::new(
+      ErrorKind::NotADirectory,
+      &format!("{:?} isn't a project directory", path),
+    )),
+    Some(path) => Ok(ProjectInfo::new(Path::new(path))),
+    None => Ok(ProjectInfo::default()),
+  }
+}
+
+/// Report `err` before any logger is set, as JSON with `--json-logs`, and
+/// exit with a failure.
+fn exit_with_error(
+  err: impl Display,
+  matches: &clap::ArgMatches,
+  verbosity: Verbosity,
+) -> ! {
+  if matches.is_present("json-logs") {
+    let message = err.to_string();
+    eprintln!("{}", logger::json_record(Level::Error, "project", &message));
+  } else if verbosity.shows_errors() {
+    eprintln!(
+      "{} {}",
+      style("ERROR:").bold().red(),
+      style(err).bold().red()
+    );
+  }
+  std::process::exit(1);
+}
+
+/// Verbosity from `--quiet`, `--summary-only` & `--verbose`, which conflict
+/// with each other.
+fn verbosity(matches: &clap::ArgMatches) -> Verbosity {
+  // Every line of stderr is JSON with `--json-logs`.
+  if matches.is_present("silent") || matches.is_present("json-logs") {
+    Verbosity::Silent
+  } else if matches.is_present("quiet") {
+    Verbosity::Quiet
+  } else if matches.is_present("summary-only") {
+    Verbosity::SummaryOnly
+  } else if matches.is_present("verbose") {
+    Verbosity::Verbose
+  } else {
+    Verbosity::Normal
+  }
+}
+
+/// Open `path` with the editor found in the environment by `var`, see
+/// [`Arguments::open_project`].
+///
+/// [`Arguments::open_project`]: struct.Arguments.html#method.open_project
+fn open_with(
+  path: &Path,
+  var: impl Fn(&str) -> Option<OsString>,
+) -> io::Result<()> {
+  let editor = match var("TERM_PROGRAM") {
+    Some(program) if program == "vscode" => Some(OsString::from("code")),
+    _ => var("VISUAL").or_else(|| var("EDITOR")),
+  };
+
+  // Editors may come with their own arguments, e.g. `code --new-window`.
+  let editor = editor.map(|editor| editor.to_string_lossy().into_owned());
+  let mut words = editor.iter().flat_map(|editor| editor.split_whitespace());
+  if let Some(program) = words.next() {
+    let status = process::Command::new(program)
+      .args(words)
+      .arg(path)
+      .status();
+    if let Ok(true) = status.as_ref().map(process::ExitStatus::success) {
+      return Ok(());
+    }
+    log::warn!("couldn't open {:?} with {:?}: {:?}", path, program, status);
+  }
+
+  opener::open(path).map_err(io::Error::other)
+}
+
+/// Split a template path into its path & branch, e.g `owner/repo#develop`.
+///
+/// Only a `#` after the last `/` names a branch, so that local paths like
+/// `./c#/template` are kept as they are, as are existing paths.
+fn split_branch(template: &str) -> (&str, Option<&str>) {
+  let after_slash = template.rfind('/').map_or(0, |i| i + 1);
+  match template[after_slash..].rfind('#') {
+    Some(i) if !Path::new(template).exists() => {
+      let i = after_slash + i;
+      (&template[..i], Some(&template[i + 1..]))
+    }
+    _ => (template, None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::template::Template;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_split_branch() {
+    assert_eq!(
+      split_branch("owner/repo#develop"),
+      ("owner/repo", Some("develop"))
+    );
+    assert_eq!(
+      split_branch("https://github.com/owner/repo.git#main"),
+      ("https://github.com/owner/repo.git", Some("main"))
+    );
+    assert_eq!(split_branch("owner/repo"), ("owner/repo", None));
+    assert_eq!(split_branch("./c#/template"), ("./c#/template", None));
+
+    let dir = tempfile::tempdir().unwrap();
+    let template = dir.path().join("c#");
+    fs::create_dir(&template).unwrap();
+    let template = template.to_string_lossy();
+    assert_eq!(split_branch(&template), (&*template, None));
+  }
+
+  #[test]
+  fn test_cd_quiet_prints_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let args = Arguments {
+      project: ProjectInfo {
+        name: "my_project".to_string(),
+        path: dir.path().to_path_buf(),
+        created: false,
+      },
+      verbosity: Verbosity::Quiet,
+      cd: true,
+      ..Arguments::default()
+    };
+
+    let mut out = Vec::new();
     args
+      .write_next_steps(&["make setup".to_string()], &mut out)
+      .unwrap();
+    assert_eq!(
+      String::from_utf8(out).unwrap(),
+      format!("{}\n", dir.path().display())
+    );
+  }
+
+  #[test]
+  fn test_input_request() {
+    let request = InputRequest::parse(
+      r#"{ "template": "owner/repo", "name": "app", "force": true }"#,
+    )
+    .unwrap();
+    let mut args = Arguments {
+      git_init: true,
+      ..Arguments::default()
+    };
+    request.apply(&mut args);
+    assert!(args.force);
+    assert!(args.git_init);
+
+    let err =
+      InputRequest::parse(r#"{ "template": " ", "name": "app" }"#).unwrap_err();
+    assert!(err.to_string().contains("\"template\" is empty"));
+    let err = InputRequest::parse(
+      r#"{ "template": "owner/repo", "name": "app", "verbose": true }"#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("unknown field `verbose`"));
+  }
+
+  #[test]
+  fn test_verbosity_flags() {
+    let parse = |flags: &[&str]| {
+      let argv = std::iter::once("project").chain(flags.iter().copied());
+      Cli::app()
+        .get_matches_from_safe(argv)
+        .map(|m| verbosity(&m))
+    };
+
+    assert_eq!(parse(&[]).unwrap(), Verbosity::Normal);
+    assert_eq!(parse(&["--silent"]).unwrap(), Verbosity::Silent);
+    assert_eq!(parse(&["-q"]).unwrap(), Verbosity::Quiet);
+    assert_eq!(parse(&["--summary-only"]).unwrap(), Verbosity::SummaryOnly);
+    assert_eq!(parse(&["-V"]).unwrap(), Verbosity::Verbose);
+    assert!(parse(&["-q", "-V"]).is_err());
+    assert!(parse(&["--summary-only", "-V"]).is_err());
+    assert!(parse(&["--summary-only", "-q"]).is_err());
+    assert!(parse(&["--silent", "-q"]).is_err());
+
+    assert!(!Verbosity::Silent.shows_errors());
+    assert!(Verbosity::Quiet.shows_errors());
+    assert!(!Verbosity::Quiet.shows_progress());
+    assert!(!Verbosity::SummaryOnly.shows_progress());
+    assert!(Verbosity::Normal.shows_progress());
+    assert!(!Verbosity::Normal.shows_details());
+    assert!(Verbosity::Verbose.shows_details());
+  }
+
+  #[test]
+  fn test_workspace_project_path() {
+    let template = tempfile::tempdir().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let workspace = dir.path().join("ws");
+    let parse = |force: bool| {
+      let mut argv = vec![
+        "project".to_string(),
+        "--workspace".to_string(),
+        workspace.display().to_string(),
+      ];
+      if force {
+        argv.push("--force".to_string());
+      }
+      argv.extend(vec![
+        "new".to_string(),
+        template.path().display().to_string(),
+        "foo".to_string(),
+      ]);
+      Cli::parse_subcommand(&Cli::app().get_matches_from(argv))
+    };
+
+    let args = parse(false).unwrap();
+    assert_eq!(
+      args.project.path(),
+      workspace.join("foo").canonicalize().unwrap()
+    );
+
+    // The project already exists.
+    assert!(parse(false).is_err());
+    assert!(parse(true).is_ok());
+  }
+
+  #[test]
+  fn test_dump_vars_defines() {
+    let template = tempfile::tempdir().unwrap();
+    fs::write(
+      template.path().join("template.toml"),
+      "[filters]\nexclude = []\n\n[variables]\nlicense = \"MIT\"\n",
+    )
+    .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("my-project");
+
+    let args = Cli::parse_args(&Cli::app().get_matches_from(vec![
+      "project".to_string(),
+      "--dump-vars".to_string(),
+      "json".to_string(),
+      "-D".to_string(),
+      "license=Apache-2.0".to_string(),
+      "new".to_string(),
+      template.path().display().to_string(),
+      project.display().to_string(),
+    ]));
+    assert!(args.dump_vars);
+
+    let json = Template::from(&args).variables_json();
+    let variables: HashMap<String, String> =
+      serde_json::from_str(&json).unwrap();
+    assert_eq!(variables["project-name"], "my_project");
+    assert_eq!(variables["license"], "Apache-2.0");
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_open_project() {
+    let dir = tempfile::tempdir().unwrap();
+    let opened = dir.path().join("opened.txt");
+    let editor = dir.path().join("editor.sh");
+    fs::write(&editor, format!("echo \"$@\" > {}\n", opened.display()))
+      .unwrap();
+    let project = dir.path().join("my-project");
+
+    let env = |key: &str| match key {
+      "EDITOR" => Some(OsString::from(format!(
+        "sh {} --new-window",
+        editor.display()
+      ))),
+      _ => None,
+    };
+    open_with(&project, env).unwrap();
+    assert_eq!(
+      fs::read_to_string(&opened).unwrap(),
+      format!("--new-window {}\n", project.display())
+    );
+
+    let args = Cli::parse_args(&Cli::app().get_matches_from(vec![
+      "project".to_string(),
+      "--open".to_string(),
+      "new".to_string(),
+      dir.path().display().to_string(),
+      project.display().to_string(),
+    ]));
+    assert!(args.open);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_template_args() {
+    let template = tempfile::tempdir().unwrap();
+    fs::write(
+      template.path().join("template.toml"),
+      "[filters]\nexclude = []\n\n[scripts]\ngenerate = \"printf '%s\\\\n' {{args}} > args.txt\"\n",
+    )
+    .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("my-project");
+
+    let args = Cli::parse_args(&Cli::app().get_matches_from(vec![
+      "project".to_string(),
+      "--quiet".to_string(),
+      "--template-arg".to_string(),
+      "--fast".to_string(),
+      "--template-arg".to_string(),
+      "x".to_string(),
+      "--template-arg".to_string(),
+      "it's <mine>".to_string(),
+      "--run".to_string(),
+      "generate".to_string(),
+      "new".to_string(),
+      template.path().display().to_string(),
+      project.display().to_string(),
+    ]));
+    assert_eq!(args.template_args, vec!["--fast", "x", "it's <mine>"]);
+    Template::from(&args).generate().unwrap();
+
+    assert_eq!(
+      fs::read_to_string(project.join("args.txt")).unwrap(),
+      "--fast\nx\nit's <mine>\n"
+    );
+  }
+
+  #[test]
+  fn test_config_name() {
+    let template = tempfile::tempdir().unwrap();
+    fs::write(
+      template.path().join("scaffold.toml"),
+      "[filters]\nexclude = [\"*.log\"]\n\n[rename]\nsrc = \"{{project-name}}\"\n",
+    )
+    .unwrap();
+    fs::create_dir(template.path().join("src")).unwrap();
+    fs::write(template.path().join("src/lib.rs"), "").unwrap();
+    fs::write(template.path().join("debug.log"), "").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("my-project");
+
+    let args = Cli::parse_args(&Cli::app().get_matches_from(vec![
+      "project".to_string(),
+      "--quiet".to_string(),
+      "--config-name".to_string(),
+      "scaffold.toml".to_string(),
+      "new".to_string(),
+      template.path().display().to_string(),
+      project.display().to_string(),
+    ]));
+    Template::from(&args).generate().unwrap();
+
+    assert!(project.join("my_project/lib.rs").exists());
+    assert!(!project.join("debug.log").exists());
+    // The config file isn't generated, like `template.toml`.
+    assert!(!project.join("scaffold.toml").exists());
+  }
+
+  #[test]
+  fn test_init_name() {
+    let template = tempfile::tempdir().unwrap();
+    fs::write(
+      template.path().join("template.toml"),
+      "[filters]\nexclude = [\"template.toml\"]\n",
+    )
+    .unwrap();
+    fs::write(
+      template.path().join("README.md.hbs"),
+      "# {{project-name}}\n",
+    )
+    .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut args = Cli::parse_subcommand(&Cli::app().get_matches_from(vec![
+      "project".to_string(),
+      "init".to_string(),
+      template.path().display().to_string(),
+      "--name".to_string(),
+      "custom".to_string(),
+    ]))
+    .unwrap();
+    assert_eq!(args.project.path, std::env::current_dir().unwrap());
+    assert_eq!(args.project.name, "custom");
+
+    // Generate into a temporary directory, instead of the current one.
+    args.project.path = dir.path().to_path_buf();
+    args.verbosity = Verbosity::Quiet;
+    Template::from(&args).generate().unwrap();
+    assert_eq!(
+      fs::read_to_string(dir.path().join("README.md")).unwrap(),
+      "# custom\n"
+    );
+  }
+
+  #[test]
+  fn test_bare_ignores_config() {
+    let template = tempfile::tempdir().unwrap();
+    fs::write(
+      template.path().join("template.toml"),
+      "[filters]\nexclude = []\n\n[rename]\nsrc = \"lib\"\n",
+    )
+    .unwrap();
+    fs::create_dir(template.path().join("src")).unwrap();
+    fs::write(template.path().join("src/main.rs"), "").unwrap();
+    fs::write(
+      template.path().join("README.md.hbs"),
+      "# {{project-name}}\n",
+    )
+    .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("my-project");
+
+    let args = Cli::parse_args(&Cli::app().get_matches_from(vec![
+      "project".to_string(),
+      "--quiet".to_string(),
+      "new".to_string(),
+      "--bare".to_string(),
+      template.path().display().to_string(),
+      project.display().to_string(),
+    ]));
+    assert!(args.bare);
+    Template::from(&args).generate().unwrap();
+
+    assert!(project.join("src/main.rs").exists());
+    assert!(!project.join("lib").exists());
+    assert_eq!(
+      fs::read_to_string(project.join("README.md")).unwrap(),
+      "# my_project\n"
+    );
   }
 }
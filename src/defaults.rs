@@ -0,0 +1,233 @@
+//! User-level defaults.
+//!
+//! Defaults are stored in `<config-dir>/project/defaults.toml` and managed
+//! with the `config` subcommand.
+//!
+//! ```sh
+//! $ project config set author "Jane Doe"
+//! $ project config get author
+//! Jane Doe
+//! ```
+//!
+//! On the first run, it's created with the author discovered from the `git`
+//! or `cargo` configuration, unless running with `--no-first-run`.
+//!
+//! Its `[defaults]` section overrides the default values of template
+//! variables, e.g to always use the same license:
+//!
+//! ```toml
+//! [defaults]
+//! license = "Apache-2.0"
+//! ```
+//!
+use crate::{
+  authors,
+  error::{Error, ErrorKind, Result},
+};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+  collections::HashMap,
+  env, fs,
+  path::{Path, PathBuf},
+};
+
+/// File containing the user-level defaults.
+pub const DEFAULTS_FILE: &str = "defaults.toml";
+
+/// Known keys of the user-level defaults.
+pub const KEYS: &[&str] = &["author", "license", "templates-dir", "env-prefix"];
+
+/// `config` subcommand.
+#[derive(Debug, PartialEq)]
+pub enum ConfigCommand {
+  /// Print the value of a key.
+  Get(String),
+  /// Set the value of a key.
+  Set(String, String),
+}
+
+/// User-level defaults i.e. `defaults.toml`.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Defaults {
+  /// Default author of generated projects.
+  pub author: Option<String>,
+  /// Default license of generated projects.
+  pub license: Option<String>,
+  /// Directory containing local templates.
+  #[serde(rename = "templates-dir")]
+  pub templates_dir: Option<String>,
+  /// Prefix of the environment variables imported as variables, unless
+  /// given with `--env-prefix`. Templates can't set it, lest they read
+  /// secrets from the environment.
+  #[serde(rename = "env-prefix", skip_serializing_if = "Option::is_none")]
+  pub env_prefix: Option<String>,
+  /// Values of template variables, overriding the template's defaults.
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub defaults: HashMap<String, String>,
+}
+
+impl Defaults {
+  /// Load the defaults from `dir`. Missing defaults file is empty.
+  pub fn load(dir: &Path) -> Result<Defaults> {
+    Self::load_file(&dir.join(DEFAULTS_FILE))
+  }
+
+  /// Load the defaults from the file at `path`, which is empty if missing.
+  pub fn load_file(path: &Path) -> Result<Defaults> {
+    if !path.exists() {
+      return Ok(Defaults::default());
+    }
+
+    Ok(toml::from_str(&fs::read_to_string(path)?)?)
+  }
+
+  /// Save the defaults into `dir`, creating it if needed.
+  pub fn save(&self, dir: &Path) -> Result<()> {
+    let content = toml::to_string(self)
+      .map_err(|e| Error::new(ErrorKind::ParseError, &e.to_string()))?;
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(DEFAULTS_FILE), content)?;
+    Ok(())
+  }
+
+  /// Returns the value of `key`, or an error if `key` isn't known.
+  pub fn get(&self, key: &str) -> Result<Option<&str>> {
+    let value = match key {
+      "author" => &self.author,
+      "license" => &self.license,
+      "templates-dir" => &self.templates_dir,
+      "env-prefix" => &self.env_prefix,
+      _ => return Err(unknown_key(key)),
+    };
+    Ok(value.as_deref())
+  }
+
+  /// Set the value of `key`, or return an error if `key` isn't known.
+  pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+    *self.field(key)? = Some(value.to_string());
+    Ok(())
+  }
+
+  /// Create the defaults file in `dir` on the first run, with the author
+  /// discovered from the `git` or `cargo` configuration, if any.
+  ///
+  /// Returns the created defaults, or `None` if the file already exists.
+  pub fn first_run(dir: &Path) -> Result<Option<Defaults>> {
+    Self::first_run_with(dir, authors::get_authors)
+  }
+
+  /// Same as [`first_run`], with the author returned by `author`.
+  ///
+  /// [`first_run`]: #method.first_run
+  fn first_run_with(
+    dir: &Path,
+    author: impl FnOnce() -> Result<String>,
+  ) -> Result<Option<Defaults>> {
+    if dir.join(DEFAULTS_FILE).exists() {
+      return Ok(None);
+    }
+
+    // Defaults are written anyway, e.g. without `$USER` nor a `git` user.
+    let defaults = Defaults {
+      author: author().ok(),
+      ..Defaults::default()
+    };
+    defaults.save(dir)?;
+    Ok(Some(defaults))
+  }
+
+  /// Run a `config` subcommand against the defaults in `dir`.
+  pub fn run(command: &ConfigCommand, dir: &Path) -> Result<Option<String>> {
+    let mut defaults = Defaults::load(dir)?;
+    match command {
+      ConfigCommand::Get(key) => Ok(defaults.get(key)?.map(String::from)),
+      ConfigCommand::Set(key, value) => {
+        defaults.set(key, value)?;
+        defaults.save(dir)?;
+        Ok(None)
+      }
+    }
+  }
+
+  fn field(&mut self, key: &str) -> Result<&mut Option<String>> {
+    match key {
+      "author" => Ok(&mut self.author),
+      "license" => Ok(&mut self.license),
+      "templates-dir" => Ok(&mut self.templates_dir),
+      "env-prefix" => Ok(&mut self.env_prefix),
+      _ => Err(unknown_key(key)),
+    }
+  }
+}
+
+fn unknown_key(key: &str) -> Error {
+  Error::new(
+    ErrorKind::Config,
+    &format!(
+      "unknown config key {:?}, expected one of: {}",
+      key,
+      KEYS.join(", ")
+    ),
+  )
+}
+
+/// Directory of the user-level defaults, i.e. `<config-dir>/project`.
+pub fn config_dir() -> PathBuf {
+  dirs::config_dir()
+    .unwrap_or_else(env::temp_dir)
+    .join(env!("CARGO_PKG_NAME"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_config_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().join("project");
+
+    let set = ConfigCommand::Set("author".to_string(), "X".to_string());
+    assert_eq!(Defaults::run(&set, &dir).unwrap(), None);
+
+    let get = ConfigCommand::Get("author".to_string());
+    assert_eq!(Defaults::run(&get, &dir).unwrap(), Some("X".to_string()));
+
+    let get = ConfigCommand::Get("license".to_string());
+    assert_eq!(Defaults::run(&get, &dir).unwrap(), None);
+
+    let get = ConfigCommand::Get("auther".to_string());
+    let err = Defaults::run(&get, &dir).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+  }
+
+  #[test]
+  fn test_first_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().join("project");
+    let author = || Ok("Jane Doe <jane@example.com>".to_string());
+
+    let created = Defaults::first_run_with(&dir, author).unwrap().unwrap();
+    assert_eq!(
+      created.author.as_deref(),
+      Some("Jane Doe <jane@example.com>")
+    );
+    assert_eq!(Defaults::load(&dir).unwrap(), created);
+
+    // Later runs keep the user's changes.
+    let set = ConfigCommand::Set("author".to_string(), "X".to_string());
+    Defaults::run(&set, &dir).unwrap();
+    let author = || Ok("Someone Else".to_string());
+    assert_eq!(Defaults::first_run_with(&dir, author).unwrap(), None);
+    assert_eq!(Defaults::load(&dir).unwrap().author.as_deref(), Some("X"));
+
+    // Without an author, the defaults are created without it.
+    let dir = dir.with_file_name("anonymous");
+    let author = || Err(Error::new(ErrorKind::NotFound, "no user"));
+    let created = Defaults::first_run_with(&dir, author).unwrap().unwrap();
+    assert_eq!(created.author, None);
+  }
+}
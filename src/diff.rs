@@ -0,0 +1,315 @@
+//! Compare the projects generated by two templates.
+//!
+//! Both templates are generated into temporary directories with the same
+//! project name, so they share the same variables. The generated files are
+//! then compared to list which files were added, removed or changed.
+//!
+//! ```sh
+//! $ project diff-templates path/to/template owner/repo#develop
+//! ```
+//!
+//! An existing project can also be compared with the files its template
+//! would generate for it, which fails if it drifted from its template:
+//!
+//! ```sh
+//! $ project check path/to/template path/to/my-project
+//! ```
+//!
+//! A directory with existing content can be snapshotted before a project is
+//! generated over it, to report which files the generation added or changed:
+//!
+//! ```sh
+//! $ project --force --print-diff-after new path/to/template my-project
+//! ```
+//!
+use crate::{
+  error::Result,
+  info::{ProjectInfo, TemplateOptions},
+  template::Template,
+};
+
+use console::style;
+use similar::TextDiff;
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+use std::{
+  collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet},
+  fmt, fs,
+  hash::{Hash, Hasher},
+  io,
+  path::{Path, PathBuf},
+};
+
+/// A file which differs between two generated projects.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+  /// File only generated by the second template.
+  Added(PathBuf),
+  /// File only generated by the first template.
+  Removed(PathBuf),
+  /// File generated by both templates with different contents, along with
+  /// its unified diff. The diff is empty for binary files.
+  Changed(PathBuf, String),
+}
+
+impl fmt::Display for Change {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Change::Added(path) => {
+        write!(f, "{}", style(format!("+ {}", path.display())).green())
+      }
+      Change::Removed(path) => {
+        write!(f, "{}", style(format!("- {}", path.display())).red())
+      }
+      Change::Changed(path, _) => {
+        write!(f, "{}", style(format!("~ {}", path.display())).yellow())
+      }
+    }
+  }
+}
+
+/// Generate templates `a` & `b` as project `name` and compare the generated
+/// files. Changes are sorted by path.
+pub fn diff_templates(
+  a: &TemplateOptions,
+  b: &TemplateOptions,
+  name: &str,
+) -> Result<Vec<Change>> {
+  let (_dir_a, project_a) = generate(a, name)?;
+  let (_dir_b, project_b) = generate(b, name)?;
+
+  diff_dirs(&project_a, &project_b)
+}
+
+/// Compare the files in directories `a` & `b`.
+pub fn diff_dirs(a: &Path, b: &Path) -> Result<Vec<Change>> {
+  let files_a = files(a)?;
+  let files_b = files(b)?;
+
+  let mut changes = Vec::new();
+  for path in files_a.union(&files_b) {
+    if !files_b.contains(path) {
+      changes.push(Change::Removed(path.to_path_buf()));
+    } else if !files_a.contains(path) {
+      changes.push(Change::Added(path.to_path_buf()));
+    } else {
+      let old = fs::read(a.join(path))?;
+      let new = fs::read(b.join(path))?;
+      if old != new {
+        changes.push(Change::Changed(
+          path.to_path_buf(),
+          unified_diff(path, old, new),
+        ));
+      }
+    }
+  }
+
+  Ok(changes)
+}
+
+/// Compare the existing project in `project_dir` with the files `template`
+/// renders for it in memory, e.g. to detect drift in CI with `check`.
+///
+/// Files the template generates which are missing from the project are
+/// `Removed`, and the ones which differ are `Changed`, with the diff from
+/// the project's file to the template's. Other files of the project are
+/// ignored. Changes are sorted by path.
+pub fn check_project(
+  template: &Template,
+  project_dir: &Path,
+) -> Result<Vec<Change>> {
+  let mut changes = Vec::new();
+  for (path, expected) in template.render_in_memory()? {
+    let actual = match fs::read(project_dir.join(&path)) {
+      Ok(actual) => actual,
+      Err(err) if err.kind() == io::ErrorKind::NotFound => {
+        changes.push(Change::Removed(path));
+        continue;
+      }
+      Err(err) => return Err(err.into()),
+    };
+    if actual != expected {
+      let diff = unified_diff(&path, actual, expected);
+      changes.push(Change::Changed(path, diff));
+    }
+  }
+
+  Ok(changes)
+}
+
+/// Hashes of the files of a directory, taken before a project is generated
+/// into it, i.e. `--print-diff-after`.
+pub struct Snapshot {
+  dir: PathBuf,
+  hashes: BTreeMap<PathBuf, u64>,
+}
+
+/// Files of a directory compared with its [`Snapshot`].
+///
+/// [`Snapshot`]: struct.Snapshot.html
+pub struct SnapshotReport {
+  /// Files which were `Added`, `Removed` or `Changed` since the snapshot,
+  /// sorted by path. Only hashes are kept, so their diff is empty.
+  pub changes: Vec<Change>,
+  /// Files which are the same as in the snapshot.
+  pub unchanged: Vec<PathBuf>,
+}
+
+impl Snapshot {
+  /// Hash every file in `dir`, which may not exist yet.
+  pub fn take(dir: &Path) -> Result<Snapshot> {
+    Ok(Snapshot {
+      dir: dir.to_path_buf(),
+      hashes: hashes(dir)?,
+    })
+  }
+
+  /// Compare the files of the snapshotted directory with the snapshot.
+  pub fn compare(&self) -> Result<SnapshotReport> {
+    let mut report = SnapshotReport {
+      changes: Vec::new(),
+      unchanged: Vec::new(),
+    };
+    let hashes = hashes(&self.dir)?;
+    let paths: BTreeSet<&PathBuf> =
+      self.hashes.keys().chain(hashes.keys()).collect();
+    for path in paths {
+      let path = path.to_path_buf();
+      match (self.hashes.get(&path), hashes.get(&path)) {
+        (None, _) => report.changes.push(Change::Added(path)),
+        (_, None) => report.changes.push(Change::Removed(path)),
+        (Some(old), Some(new)) if old != new => {
+          report.changes.push(Change::Changed(path, String::new()))
+        }
+        _ => report.unchanged.push(path),
+      }
+    }
+
+    Ok(report)
+  }
+}
+
+impl fmt::Display for SnapshotReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for change in &self.changes {
+      writeln!(f, "{}", change)?;
+    }
+    let added = self
+      .changes
+      .iter()
+      .filter(|change| matches!(change, Change::Added(_)))
+      .count();
+    let removed = self
+      .changes
+      .iter()
+      .filter(|change| matches!(change, Change::Removed(_)))
+      .count();
+    writeln!(
+      f,
+      "{}",
+      style(format!(
+        "{} added, {} changed, {} removed, {} unchanged",
+        added,
+        self.changes.len() - added - removed,
+        removed,
+        self.unchanged.len()
+      ))
+      .bold()
+    )
+  }
+}
+
+/// Hashes of the contents of all files in `dir`, by path relative to `dir`.
+fn hashes(dir: &Path) -> Result<BTreeMap<PathBuf, u64>> {
+  let mut hashes = BTreeMap::new();
+  if !dir.exists() {
+    return Ok(hashes);
+  }
+  for path in files(dir)? {
+    let mut hasher = DefaultHasher::new();
+    fs::read(dir.join(&path))?.hash(&mut hasher);
+    hashes.insert(path, hasher.finish());
+  }
+
+  Ok(hashes)
+}
+
+/// Unified diff of file `path` from `old` to `new`, which is empty for binary
+/// files.
+fn unified_diff(path: &Path, old: Vec<u8>, new: Vec<u8>) -> String {
+  match (String::from_utf8(old), String::from_utf8(new)) {
+    (Ok(old), Ok(new)) => {
+      let name = path.display().to_string();
+      TextDiff::from_lines(&old, &new)
+        .unified_diff()
+        .header(&name, &name)
+        .to_string()
+    }
+    // Binary files.
+    _ => String::new(),
+  }
+}
+
+/// Generate `template` as project `name` in a temporary directory.
+///
+/// Returns the temporary directory, which is deleted once dropped, and the
+/// generated project's directory.
+fn generate(
+  template: &TemplateOptions,
+  name: &str,
+) -> Result<(TempDir, PathBuf)> {
+  let dir = tempfile::tempdir()?;
+  let project = ProjectInfo::new(&dir.path().join(name));
+
+  Template::new(&project, template).generate()?;
+  Ok((dir, project.path()))
+}
+
+/// Paths of all files in `dir`, relative to `dir`.
+fn files(dir: &Path) -> Result<BTreeSet<PathBuf>> {
+  let mut files = BTreeSet::new();
+  for entry in WalkDir::new(dir) {
+    let entry = entry.map_err(std::io::Error::from)?;
+    if entry.file_type().is_file() {
+      files.insert(entry.path().strip_prefix(dir)?.to_path_buf());
+    }
+  }
+
+  Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::template::tests::template_dir;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_diff_templates() {
+    let a = template_dir(&[
+      ("README.md", "# Project\n"),
+      ("src/main.rs", "fn main() {}\n"),
+    ]);
+    let b = template_dir(&[
+      ("README.md", "# Project\n"),
+      ("src/main.rs", "fn main() {\n  println!(\"Hello\");\n}\n"),
+    ]);
+
+    let changes = diff_templates(
+      &TemplateOptions::Local(a.path().to_path_buf()),
+      &TemplateOptions::Local(b.path().to_path_buf()),
+      "my-project",
+    )
+    .unwrap();
+
+    assert_eq!(changes.len(), 1);
+    match &changes[0] {
+      Change::Changed(path, diff) => {
+        assert_eq!(path, Path::new("src/main.rs"));
+        assert!(diff.contains("+  println!(\"Hello\");"));
+      }
+      change => panic!("Expected a changed file: {:?}", change),
+    }
+  }
+}
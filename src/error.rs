@@ -1,6 +1,14 @@
 //! Error handler.
 //!
-use std::{fmt, io, path, str::FromStr};
+use crate::{emoji, logger};
+
+use console::style;
+
+use std::{
+  fmt, io,
+  path::{self, PathBuf},
+  str::FromStr,
+};
 
 /// Project Result type.
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -35,6 +43,12 @@ pub enum ErrorKind {
   /// Parser error.
   ParseError,
 
+  /// A warning treated as an error i.e. `--abort-on-warning`.
+  Warning,
+
+  /// Invalid template configuration i.e. `template.toml`.
+  Config,
+
   /// Generic error kind.
   Error,
 }
@@ -65,6 +79,12 @@ impl Error {
   pub fn message(&self) -> &str {
     &self.err.msg
   }
+
+  /// Every failure this error aggregates, e.g. the files which failed to
+  /// generate with `--keep-going`. Empty for other errors.
+  pub fn failures(&self) -> &[(PathBuf, Error)] {
+    &self.err.failures
+  }
 }
 
 impl FromStr for Error {
@@ -97,6 +117,12 @@ impl From<io::Error> for Error {
   }
 }
 
+impl From<walkdir::Error> for Error {
+  fn from(err: walkdir::Error) -> Self {
+    Error::new(ErrorKind::Io, &err.to_string())
+  }
+}
+
 impl From<toml::de::Error> for Error {
   fn from(err: toml::de::Error) -> Self {
     Error::new(ErrorKind::StripPrefix, &err.to_string())
@@ -133,6 +159,12 @@ impl From<handlebars::TemplateRenderError> for Error {
   }
 }
 
+impl From<notify::Error> for Error {
+  fn from(err: notify::Error) -> Self {
+    Error::new(ErrorKind::Io, &err.to_string())
+  }
+}
+
 impl From<liquid::Error> for Error {
   fn from(err: liquid::Error) -> Self {
     Error::new(ErrorKind::ParseError, &format!("{}", err))
@@ -141,9 +173,50 @@ impl From<liquid::Error> for Error {
 
 impl std::error::Error for Error {}
 
+/// Failures of every path, aggregated rather than stopping at the first one,
+/// e.g. the files which failed to generate with `--keep-going`.
+#[derive(Debug)]
+pub struct MultiError(pub Vec<(PathBuf, Error)>);
+
+impl fmt::Display for MultiError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} failure(s):", self.0.len())?;
+    for (path, err) in &self.0 {
+      write!(f, "\n  {}: {}", path.display(), err)?;
+    }
+
+    Ok(())
+  }
+}
+
+impl std::error::Error for MultiError {}
+
+impl From<MultiError> for Error {
+  fn from(err: MultiError) -> Self {
+    let mut error = Error::new(ErrorKind::Error, &err.to_string());
+    error.err.failures = err.0;
+    error
+  }
+}
+
+/// Report a warning `message` to the user, or return it as an `Error`
+/// if `abort_on_warning` is set.
+pub(crate) fn warn(message: &str, abort_on_warning: bool) -> Result<()> {
+  if abort_on_warning {
+    return Err(Error::new(ErrorKind::Warning, message));
+  }
+
+  log::warn!("{}", message);
+  if !logger::is_json() {
+    eprintln!("{} {}", emoji::WARN, style(message).bold().yellow());
+  }
+  Ok(())
+}
+
 struct ErrorImpl {
   kind: ErrorKind,
   msg: String,
+  failures: Vec<(PathBuf, Error)>,
 }
 
 impl ErrorImpl {
@@ -151,6 +224,7 @@ impl ErrorImpl {
     ErrorImpl {
       kind,
       msg: msg.to_string(),
+      failures: Vec::new(),
     }
   }
 }
@@ -1,24 +1,64 @@
-use crate::{emoji, error::Result, util};
+use crate::{
+  authors, emoji,
+  error::{Error, ErrorKind, Result},
+  info::RemoteSource,
+  util,
+};
 
 use cargo::core::GitReference;
 use console::style;
 
 use git2::{
-  Cred, RemoteCallbacks, Repository as GitRepository, RepositoryInitOptions,
+  build::CheckoutBuilder, BranchType, Cred, CredentialType, Delta,
+  IndexAddOption, RemoteCallbacks, Repository as GitRepository,
+  RepositoryInitOptions, Signature, StatusOptions,
 };
 use url::Url;
+use walkdir::WalkDir;
 
 use std::{
+  cell::Cell,
+  collections::HashSet,
   env, fs,
   path::{Path, PathBuf},
+  time::{Duration, SystemTime},
 };
 
+/// `cache` subcommand.
+#[derive(Debug, PartialEq)]
+pub enum CacheCommand {
+  /// Remove the cached templates, or only list them with `dry_run`. Caches
+  /// other than the default [`cache_dir`] are only cleaned with `force`.
+  ///
+  /// [`cache_dir`]: fn.cache_dir.html
+  Clean { dry_run: bool, force: bool },
+}
+
+/// How a template previously cloned into the cache is treated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CachePolicy {
+  /// Ask whether to use the cached template or clone a fresh copy.
+  Ask,
+  /// Use the cached template without asking.
+  Use,
+  /// Ignore the cached template and clone a fresh copy.
+  Refresh,
+}
+
 #[derive(Debug, Clone)]
 pub struct GitOptions {
   /// Remote or local git URI.
   remote: Url,
   /// Git branch to select. Defaults to the `HEAD` branch.
   branch: GitReference,
+  /// How a cached clone of the template is treated.
+  cache: CachePolicy,
+  /// Directory where the template is cloned.
+  cache_dir: PathBuf,
+  /// Where the template is hosted, if known.
+  source: Option<RemoteSource>,
+  /// Bytes the clone may receive before it's aborted.
+  max_clone_size: Option<u64>,
 }
 
 impl GitOptions {
@@ -28,15 +68,81 @@ impl GitOptions {
       branch: branch
         .map(GitReference::Branch)
         .unwrap_or(GitReference::DefaultBranch),
+      cache: CachePolicy::Refresh,
+      cache_dir: cache_dir(),
+      source: None,
+      max_clone_size: None,
     }
   }
 
-  /// Returns a `tempdir` where template will be cloned locally.
+  /// Returns the directory in the cache where template will be cloned locally.
+  ///
+  /// e.g `https://github.com/owner/repo.git` is cloned into
+  /// `<cache-dir>/github.com/owner/repo` and its `develop` branch into
+  /// `<cache-dir>/github.com/owner/repo@develop`.
   #[inline]
   pub fn path(&self) -> PathBuf {
-    // self.remote.path().trim_start_matches('/')
-    // util::basename(self.remote.path()).into()
-    env::temp_dir().join(util::basename(self.remote.path()))
+    let mut repo = self
+      .remote
+      .path()
+      .trim_start_matches('/')
+      .trim_end_matches(".git")
+      .to_string();
+    if let GitReference::Branch(branch) = &self.branch {
+      repo = format!("{}@{}", repo, branch);
+    }
+
+    self
+      .cache_dir
+      .join(self.remote.host_str().unwrap_or("local"))
+      .join(repo)
+  }
+
+  /// Clone the template into `dir` instead of the default [`cache_dir`].
+  ///
+  /// [`cache_dir`]: fn.cache_dir.html
+  pub fn set_cache_dir(&mut self, dir: &Path) {
+    self.cache_dir = dir.to_path_buf();
+  }
+
+  /// Remote or local git URI.
+  pub fn remote(&self) -> &Url {
+    &self.remote
+  }
+
+  /// Where the template is hosted, if known.
+  pub fn source(&self) -> Option<&RemoteSource> {
+    self.source.as_ref()
+  }
+
+  pub fn set_source(&mut self, source: RemoteSource) {
+    self.source = Some(source);
+  }
+
+  /// Abort the clone once it received more than `bytes`, i.e.
+  /// `--max-clone-size`.
+  pub fn set_max_clone_size(&mut self, bytes: u64) {
+    self.max_clone_size = Some(bytes);
+  }
+
+  /// How a cached clone of the template is treated.
+  pub fn cache_policy(&self) -> CachePolicy {
+    self.cache
+  }
+
+  pub fn set_cache_policy(&mut self, cache: CachePolicy) {
+    self.cache = cache;
+  }
+
+  /// Returns how long ago the template was cloned into the cache, or `None`
+  /// if it hasn't been cloned yet.
+  pub fn cache_age(&self) -> Option<Duration> {
+    let path = self.path();
+    // An empty directory is not a cached template.
+    fs::read_dir(&path).ok()?.next()?.ok()?;
+
+    let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+    SystemTime::now().duration_since(modified).ok()
   }
 
   pub fn clone_repo(&self) -> Result<()> {
@@ -51,14 +157,46 @@ impl GitOptions {
 
     // Prepare callbacks.
     let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(|_url, username_from_url, _allowed_types| {
-      Cred::ssh_key(
-        // TODO: If `username_from_url` is None - Project is likely a private repo.
-        username_from_url.unwrap(),
-        None,
-        Path::new(&format!("{}/.ssh/id_rsa", env::var("HOME").unwrap())),
-        None,
-      )
+    let scheme = self.remote.scheme().to_string();
+    let mut attempts = 0;
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+      let username = username_from_url.unwrap_or("git");
+      let credential = select_credential(&scheme, allowed_types, attempts);
+      if credential != Some(Credential::Username) {
+        attempts += 1;
+      }
+
+      match credential {
+        Some(Credential::Username) => Cred::username(username),
+        Some(Credential::SshAgent) => Cred::ssh_key_from_agent(username),
+        Some(Credential::SshKey) => {
+          let home = dirs::home_dir().unwrap_or_default();
+          Cred::ssh_key(username, None, &home.join(".ssh/id_rsa"), None)
+        }
+        Some(Credential::Helper) => git2::Config::open_default()
+          .and_then(|config| {
+            Cred::credential_helper(&config, url, username_from_url)
+          })
+          .or_else(|_| {
+            // Without the helper's credentials, the netrc's are tried right
+            // away, and only once.
+            attempts += 1;
+            netrc_credential(url)
+          }),
+        Some(Credential::Netrc) => netrc_credential(url),
+        Some(Credential::Default) => Cred::default(),
+        None => Err(git2::Error::from_str(&format!(
+          "No more credentials to try for {}://",
+          scheme
+        ))),
+      }
+    });
+    let oversized = Cell::new(false);
+    let max_clone_size = self.max_clone_size;
+    callbacks.transfer_progress(|progress| {
+      let within = within_clone_size(progress.received_bytes(), max_clone_size);
+      oversized.set(!within);
+      within
     });
 
     // Prepare fetch options.
@@ -68,6 +206,9 @@ impl GitOptions {
     // Prepare builder.
     let mut builder = git2::build::RepoBuilder::new();
     builder.fetch_options(fo);
+    if let GitReference::Branch(branch) = &self.branch {
+      builder.branch(branch);
+    }
 
     // Create clone directory if it doesn't exist.
     if !path.exists() {
@@ -79,25 +220,82 @@ impl GitOptions {
     }
 
     // Clone the project.
-    builder.clone(self.remote.as_str(), &path)?;
-
-    // Remove ".git" folder in cloned repo.
-    self.remove_git_history(&path);
+    if let Err(err) = builder.clone(self.remote.as_str(), &path) {
+      // Don't leave a partial clone in the cache.
+      let _ = fs::remove_dir_all(&path);
+      if oversized.get() {
+        return Err(Error::new(
+          ErrorKind::GitError,
+          &format!(
+            "clone of {} aborted: it exceeds --max-clone-size of {} bytes",
+            self.remote,
+            max_clone_size.unwrap_or_default()
+          ),
+        ));
+      }
+      return Err(Error::from(err));
+    }
 
     // Successfully cloned.
     Ok(())
   }
 
-  #[inline]
-  fn remove_git_history(&self, dir: &Path) {
-    fs::remove_dir_all(dir.join(".git")).unwrap_or_else(|err| {
-      eprintln!(
-        "{} {} {}",
-        emoji::WARN,
-        style("Could not clean up git history: {}").bold().yellow(),
-        style(err).bold().yellow()
-      )
-    });
+  /// Returns the SHA of the commit the cloned template resolved to.
+  pub fn resolved_commit(&self) -> Result<String> {
+    let repo = GitRepository::open(self.path())?;
+    let commit = repo.head()?.peel_to_commit()?;
+    Ok(commit.id().to_string())
+  }
+
+  /// Remove ".git" folder in cloned repo, along with the ones of the
+  /// repositories nested in it, e.g. submodules.
+  pub fn remove_git_history(&self) -> Result<()> {
+    let git_dirs: Vec<PathBuf> = WalkDir::new(self.path())
+      .into_iter()
+      .filter_entry(|entry| entry.depth() == 0 || entry.file_name() != ".git")
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.file_type().is_dir())
+      .map(|entry| entry.path().join(".git"))
+      .filter(|git_dir| fs::symlink_metadata(git_dir).is_ok())
+      .collect();
+    for git_dir in git_dirs {
+      if git_dir.is_dir() {
+        fs::remove_dir_all(git_dir)?;
+      } else {
+        fs::remove_file(git_dir)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Whether a branch was given, e.g. with `--branch`, rather than the
+  /// remote's default branch.
+  pub fn has_branch(&self) -> bool {
+    matches!(self.branch, GitReference::Branch(_))
+  }
+
+  /// Check out the `branch` of the cloned template, whose remote branches
+  /// are all fetched by the clone, so it isn't cloned again.
+  ///
+  /// Returns false if `branch` is already checked out.
+  pub fn checkout_branch(&self, branch: &str) -> Result<bool> {
+    let repo = GitRepository::open(self.path())?;
+    if repo.head()?.shorthand() == Some(branch) {
+      return Ok(false);
+    }
+
+    let remote =
+      repo.find_branch(&format!("origin/{}", branch), BranchType::Remote)?;
+    let commit = remote.get().peel_to_commit()?;
+    let local = repo.branch(branch, &commit, true)?;
+    let refname = local.get().name().unwrap_or_default().to_string();
+    repo.checkout_tree(
+      commit.as_object(),
+      Some(CheckoutBuilder::new().force()),
+    )?;
+    repo.set_head(&refname)?;
+
+    Ok(true)
   }
 
   pub fn branch(&self) -> String {
@@ -131,6 +329,318 @@ impl GitOptions {
   }
 }
 
+/// Credentials used to authenticate with a remote.
+#[derive(Debug, PartialEq)]
+enum Credential {
+  /// Username only, asked for before an SSH key if missing from the URL.
+  Username,
+  /// SSH key from the running `ssh-agent`.
+  SshAgent,
+  /// SSH key from `~/.ssh/id_rsa`.
+  SshKey,
+  /// Username & password from the git credential helper.
+  Helper,
+  /// Username & password of the URL's host in `~/.netrc`.
+  Netrc,
+  /// Default credentials e.g NTLM or Kerberos.
+  Default,
+}
+
+/// Select the next credential to try for a remote with the given URL `scheme`,
+/// based on the `allowed` credential types & number of failed `attempts`.
+///
+/// - `git://` is unauthenticated, so no credential is ever returned.
+/// - `ssh://` tries the `ssh-agent` first, then `~/.ssh/id_rsa`. It never falls
+///   back to username & password.
+/// - `http(s)://` asks the git credential helper for a username & password,
+///   then looks them up in `~/.netrc`.
+fn select_credential(
+  scheme: &str,
+  allowed: CredentialType,
+  attempts: usize,
+) -> Option<Credential> {
+  match scheme {
+    "git" => None,
+    "ssh" if allowed.is_username() => Some(Credential::Username),
+    "ssh" if allowed.is_ssh_key() => match attempts {
+      0 => Some(Credential::SshAgent),
+      1 => Some(Credential::SshKey),
+      _ => None,
+    },
+    "ssh" => None,
+    "http" | "https" if allowed.is_user_pass_plaintext() => match attempts {
+      0 => Some(Credential::Helper),
+      1 => Some(Credential::Netrc),
+      _ => None,
+    },
+    "http" | "https" => None,
+    _ if allowed.is_default() && attempts == 0 => Some(Credential::Default),
+    _ => None,
+  }
+}
+
+/// Username & password for the host of `url` from the netrc file, i.e.
+/// `$NETRC` or `~/.netrc`.
+fn netrc_credential(url: &str) -> std::result::Result<Cred, git2::Error> {
+  let path = env::var_os("NETRC")
+    .map(PathBuf::from)
+    .or_else(|| dirs::home_dir().map(|home| home.join(".netrc")));
+  let host = Url::parse(url)
+    .ok()
+    .and_then(|url| url.host_str().map(String::from));
+  match (path, host) {
+    (Some(path), Some(host)) => match netrc_login(&path, &host) {
+      Some((login, password)) => Cred::userpass_plaintext(&login, &password),
+      None => Err(git2::Error::from_str(&format!(
+        "No credentials for {} in {}",
+        host,
+        path.display()
+      ))),
+    },
+    _ => Err(git2::Error::from_str(
+      "No netrc file to read credentials from",
+    )),
+  }
+}
+
+/// Login & password of `host` in the netrc file at `path`.
+fn netrc_login(path: &Path, host: &str) -> Option<(String, String)> {
+  netrc_lookup(&fs::read_to_string(path).ok()?, host)
+}
+
+/// Login & password of `host` in the `content` of a netrc file, falling back
+/// to its `default` entry.
+fn netrc_lookup(content: &str, host: &str) -> Option<(String, String)> {
+  let mut tokens = content.split_whitespace();
+  let mut matched = None;
+  let mut default = None;
+  // Login & password of the entry being read, if it's for `host`.
+  let mut entry: Option<(Option<String>, Option<String>)> = None;
+  let mut is_default = false;
+
+  let mut finish = |entry: &mut Option<(Option<String>, Option<String>)>,
+                    is_default: bool| {
+    if let Some((Some(login), Some(password))) = entry.take() {
+      if is_default {
+        default.get_or_insert((login, password));
+      } else {
+        matched.get_or_insert((login, password));
+      }
+    }
+  };
+
+  while let Some(token) = tokens.next() {
+    match token {
+      "machine" => {
+        finish(&mut entry, is_default);
+        is_default = false;
+        entry = match tokens.next() {
+          Some(machine) if machine == host => Some((None, None)),
+          _ => None,
+        };
+      }
+      "default" => {
+        finish(&mut entry, is_default);
+        is_default = true;
+        entry = Some((None, None));
+      }
+      "login" => {
+        let login = tokens.next().map(String::from);
+        if let Some(entry) = &mut entry {
+          entry.0 = login;
+        }
+      }
+      "password" => {
+        let password = tokens.next().map(String::from);
+        if let Some(entry) = &mut entry {
+          entry.1 = password;
+        }
+      }
+      "account" => {
+        tokens.next();
+      }
+      _ => {}
+    }
+  }
+  finish(&mut entry, is_default);
+
+  matched.or(default)
+}
+
+/// Directory where remote templates are cloned, i.e. `<cache-dir>/project`.
+pub fn cache_dir() -> PathBuf {
+  dirs::cache_dir()
+    .unwrap_or_else(env::temp_dir)
+    .join(env!("CARGO_PKG_NAME"))
+}
+
+/// Remove the templates cloned into `cache_dir`, returning their paths. With
+/// `dry_run`, they're only listed.
+///
+/// Only the clones of the cache are removed, i.e. the repositories under
+/// `<host>/<path>`, with their directories once they're empty. Anything else
+/// is left as-is, symlinks aren't followed, and the root, home & system
+/// directories are never cleaned. Directories other than the default
+/// [`cache_dir`] are only cleaned with `force`.
+///
+/// [`cache_dir`]: fn.cache_dir.html
+pub fn clean_cache(
+  cache_dir: &Path,
+  dry_run: bool,
+  force: bool,
+) -> Result<Vec<PathBuf>> {
+  if !cache_dir.exists() {
+    return Ok(Vec::new());
+  }
+
+  let root = cache_dir.canonicalize()?;
+  let refuse = |reason: &str| {
+    Err(Error::new(
+      ErrorKind::Error,
+      &format!("refusing to clean {:?}, {}", root, reason),
+    ))
+  };
+  if util::is_sensitive_dir(&root) {
+    return refuse("it isn't a cache directory");
+  }
+  if !force && self::cache_dir().canonicalize().ok().as_ref() != Some(&root) {
+    return refuse("it isn't the default cache, pass --force to clean it");
+  }
+
+  // Clones are repositories below a host's directory.
+  let mut removed = Vec::new();
+  let mut entries = WalkDir::new(&root).min_depth(2).into_iter();
+  while let Some(entry) = entries.next() {
+    let entry = entry?;
+    if !entry.file_type().is_dir() || !entry.path().join(".git").is_dir() {
+      continue;
+    }
+    entries.skip_current_dir();
+    removed.push(entry.into_path());
+  }
+  removed.sort();
+
+  if !dry_run {
+    for path in &removed {
+      fs::remove_dir_all(path)?;
+      // Directories of the host & owner, once they have no other clone.
+      for dir in path.ancestors().skip(1).take_while(|dir| *dir != root) {
+        if fs::remove_dir(dir).is_err() {
+          break;
+        }
+      }
+    }
+  }
+
+  Ok(removed)
+}
+
+/// SHAs of the tree of the template in `template_dir` & of the commit it's at,
+/// i.e. `HEAD` of its repository.
+pub fn head_shas(template_dir: &Path) -> Result<(String, String)> {
+  let repo = GitRepository::discover(template_dir)?;
+  let commit = repo.head()?.peel_to_commit()?;
+
+  // Templates can be a sub-directory of the repository.
+  let workdir = repo
+    .workdir()
+    .unwrap_or_else(|| repo.path())
+    .canonicalize()?;
+  let template_dir = template_dir.canonicalize()?;
+  let tree = match template_dir.strip_prefix(&workdir) {
+    Ok(prefix) if prefix != Path::new("") => {
+      commit.tree()?.get_path(prefix)?.id()
+    }
+    _ => commit.tree_id(),
+  };
+
+  Ok((tree.to_string(), commit.id().to_string()))
+}
+
+/// Whether the template in `template_dir` has changes which aren't committed,
+/// including untracked files, so it doesn't match the tree of its `HEAD`.
+pub fn is_dirty(template_dir: &Path) -> Result<bool> {
+  let repo = GitRepository::discover(template_dir)?;
+  let workdir = repo
+    .workdir()
+    .unwrap_or_else(|| repo.path())
+    .canonicalize()?;
+
+  let mut options = StatusOptions::new();
+  options.include_untracked(true).recurse_untracked_dirs(true);
+  // Only the template's own sub-directory of the repository counts.
+  let template_dir = template_dir.canonicalize()?;
+  if let Ok(prefix) = template_dir.strip_prefix(&workdir) {
+    if prefix != Path::new("") {
+      options.pathspec(prefix);
+    }
+  }
+
+  let dirty = !repo.statuses(Some(&mut options))?.is_empty();
+  Ok(dirty)
+}
+
+/// Name of the repository the `origin` remote of the repository containing
+/// `dir` points to, e.g. `repo` for `git@github.com:owner/repo.git`.
+///
+/// Returns `None` if `dir` isn't in a repository, or if it has no `origin`
+/// remote.
+pub fn origin_repo_name(dir: &Path) -> Option<String> {
+  let repo = GitRepository::discover(dir).ok()?;
+  let remote = repo.find_remote("origin").ok()?;
+  repo_name(remote.url()?)
+}
+
+/// Last component of the repository `url`, without its `.git` extension.
+fn repo_name(url: &str) -> Option<String> {
+  let name = url.trim_end_matches('/').rsplit(&['/', ':'][..]).next()?;
+  let name = name.strip_suffix(".git").unwrap_or(name);
+  if name.is_empty() {
+    None
+  } else {
+    Some(name.to_string())
+  }
+}
+
+/// Files of the template in `template_dir` which were added or modified
+/// between the commit `since` & its `HEAD`, relative to `template_dir`.
+pub fn changed_files(
+  template_dir: &Path,
+  since: &str,
+) -> Result<HashSet<PathBuf>> {
+  let repo = GitRepository::discover(template_dir)?;
+  let old = repo.revparse_single(since)?.peel_to_tree()?;
+  let new = repo.head()?.peel_to_tree()?;
+  let diff = repo.diff_tree_to_tree(Some(&old), Some(&new), None)?;
+
+  // Templates can be a sub-directory of the repository.
+  let workdir = repo
+    .workdir()
+    .unwrap_or_else(|| repo.path())
+    .canonicalize()?;
+  let template_dir = template_dir.canonicalize()?;
+  let prefix = template_dir.strip_prefix(&workdir).unwrap_or(Path::new(""));
+
+  Ok(
+    diff
+      .deltas()
+      .filter(|delta| delta.status() != Delta::Deleted)
+      .filter_map(|delta| delta.new_file().path())
+      .filter_map(|path| path.strip_prefix(prefix).ok())
+      .map(Path::to_path_buf)
+      .collect(),
+  )
+}
+
+/// Whether a clone which `received` bytes so far may go on, given its
+/// `max` size, i.e. `--max-clone-size`.
+fn within_clone_size(received: usize, max: Option<u64>) -> bool {
+  match max {
+    Some(max) => received as u64 <= max,
+    None => true,
+  }
+}
+
 /// Initializes a new repository from a given git `branch` into a `project_dir`.
 pub fn init(project_dir: &Path, branch: &str) -> Result<GitRepository> {
   let mut opt = RepositoryInitOptions::new();
@@ -148,6 +658,40 @@ pub fn init(project_dir: &Path, branch: &str) -> Result<GitRepository> {
   )
 }
 
+/// Initializes a repository in `project_dir` if needed, and commits all of its
+/// files with the given `message`, i.e. `--git-init`.
+///
+/// The commit is authored by the configured git user, falling back to the
+/// project's author.
+pub fn init_commit(project_dir: &Path, message: &str) -> Result<()> {
+  let repo = GitRepository::init(project_dir)?;
+
+  let mut index = repo.index()?;
+  index.add_all(["*"].iter(), IndexAddOption::DEFAULT, None)?;
+  index.write()?;
+  let tree = repo.find_tree(index.write_tree()?)?;
+
+  let signature = match repo.signature() {
+    Ok(signature) => signature,
+    Err(_) => {
+      let (name, email) = authors::discover_author()?;
+      Signature::now(&name, &email.unwrap_or_default())?
+    }
+  };
+  let parent = repo.head().and_then(|head| head.peel_to_commit()).ok();
+  let parents: Vec<_> = parent.iter().collect();
+  repo.commit(
+    Some("HEAD"),
+    &signature,
+    &signature,
+    message,
+    &tree,
+    &parents,
+  )?;
+
+  Ok(())
+}
+
 /// Delete temporary template repo from base `template_dir`.
 #[inline]
 pub fn delete_local_repo(template_dir: &dyn AsRef<Path>) -> Result<()> {
@@ -161,3 +705,180 @@ pub fn delete_local_repo(template_dir: &dyn AsRef<Path>) -> Result<()> {
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_select_credential() {
+    let any = CredentialType::all();
+
+    // `git://` is never authenticated.
+    assert_eq!(select_credential("git", any, 0), None);
+
+    // `ssh://` uses the agent first, then the default key & never a password.
+    let ssh = CredentialType::SSH_KEY;
+    assert_eq!(
+      select_credential("ssh", CredentialType::USERNAME, 0),
+      Some(Credential::Username)
+    );
+    assert_eq!(select_credential("ssh", ssh, 0), Some(Credential::SshAgent));
+    assert_eq!(select_credential("ssh", ssh, 1), Some(Credential::SshKey));
+    assert_eq!(select_credential("ssh", ssh, 2), None);
+    assert_eq!(
+      select_credential("ssh", CredentialType::USER_PASS_PLAINTEXT, 0),
+      None
+    );
+
+    // `https://` asks the credential helper once.
+    let userpass = CredentialType::USER_PASS_PLAINTEXT;
+    assert_eq!(
+      select_credential("https", userpass, 0),
+      Some(Credential::Helper)
+    );
+    assert_eq!(
+      select_credential("https", userpass, 1),
+      Some(Credential::Netrc)
+    );
+    assert_eq!(select_credential("https", userpass, 2), None);
+    assert_eq!(select_credential("https", ssh, 0), None);
+  }
+
+  #[test]
+  fn test_clean_cache() {
+    let cache = tempfile::tempdir().unwrap();
+    let clone = cache.path().join("github.com/owner/repo");
+    fs::create_dir_all(clone.join(".git")).unwrap();
+    fs::write(clone.join("README.md"), "").unwrap();
+    let branch = cache.path().join("gitlab.com/group/sub/repo@dev");
+    fs::create_dir_all(branch.join(".git")).unwrap();
+
+    // Anything but clones is kept, e.g. a user's files.
+    fs::write(cache.path().join("template.tar"), "").unwrap();
+    fs::create_dir_all(cache.path().join("github.com/notes")).unwrap();
+
+    // Directories outside of the cache are never removed, even if linked.
+    let outside = tempfile::tempdir().unwrap();
+    fs::create_dir(outside.path().join(".git")).unwrap();
+    fs::write(outside.path().join("keep.txt"), "").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(
+      outside.path(),
+      cache.path().join("github.com/link"),
+    )
+    .unwrap();
+
+    // Only the default cache is cleaned without `force`.
+    let err = clean_cache(cache.path(), true, false).unwrap_err();
+    assert!(err.to_string().contains("pass --force"), "{}", err);
+
+    let listed = clean_cache(cache.path(), true, true).unwrap();
+    assert_eq!(listed, vec![clone.clone(), branch.clone()]);
+    assert!(clone.exists());
+
+    let removed = clean_cache(cache.path(), false, true).unwrap();
+    assert_eq!(removed, listed);
+    assert!(!clone.exists());
+    assert!(!cache.path().join("gitlab.com").exists());
+    assert!(cache.path().join("template.tar").exists());
+    assert!(cache.path().join("github.com/notes").exists());
+    assert!(outside.path().join("keep.txt").exists());
+
+    assert!(clean_cache(Path::new("/"), true, true).is_err());
+    if let Some(home) = dirs::home_dir() {
+      assert!(clean_cache(&home, true, true).is_err());
+    }
+    assert!(clean_cache(&cache.path().join("missing"), false, true)
+      .unwrap()
+      .is_empty());
+  }
+
+  #[test]
+  fn test_within_clone_size() {
+    assert!(within_clone_size(usize::MAX, None));
+    assert!(within_clone_size(0, Some(1024)));
+    assert!(within_clone_size(1024, Some(1024)));
+    assert!(!within_clone_size(1025, Some(1024)));
+    assert!(!within_clone_size(1, Some(0)));
+
+    // The partial clone is removed once aborted.
+    let origin = tempfile::tempdir().unwrap();
+    let repo = GitRepository::init(origin.path()).unwrap();
+    fs::write(origin.path().join("README.md"), "# Template\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("README.md")).unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = Signature::now("tester", "tester@example.com").unwrap();
+    repo
+      .commit(Some("HEAD"), &sig, &sig, "commit", &tree, &[])
+      .unwrap();
+
+    let cache = tempfile::tempdir().unwrap();
+    let url = Url::from_file_path(origin.path()).unwrap();
+    let mut opts = GitOptions::new(url, None);
+    opts.set_cache_dir(cache.path());
+    opts.set_max_clone_size(0);
+    let err = opts.clone_repo().unwrap_err();
+    assert!(err.to_string().contains("--max-clone-size"), "{}", err);
+    assert!(!opts.path().exists());
+  }
+
+  #[test]
+  fn test_origin_repo_name() {
+    for url in &[
+      "https://github.com/owner/my-service.git",
+      "https://gitlab.com/group/owner/my-service/",
+      "git@github.com:owner/my-service.git",
+      "ssh://git@host:22/my-service",
+      "/srv/git/my-service.git",
+    ] {
+      assert_eq!(repo_name(url).as_deref(), Some("my-service"), "{}", url);
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let repo = GitRepository::init(dir.path()).unwrap();
+    assert_eq!(origin_repo_name(dir.path()), None);
+
+    // Found from a sub-directory of the repository too.
+    repo
+      .remote("origin", "git@github.com:owner/my-service.git")
+      .unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    assert_eq!(
+      origin_repo_name(&dir.path().join("src")).as_deref(),
+      Some("my-service")
+    );
+  }
+
+  #[test]
+  fn test_netrc_lookup() {
+    let dir = tempfile::tempdir().unwrap();
+    let netrc = dir.path().join(".netrc");
+    fs::write(
+      &netrc,
+      "machine github.com login octocat password gh-token\n\
+       machine git.example.com\n  login me\n  account ops\n  password s3cret\n\
+       default login anonymous password guest\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+      netrc_login(&netrc, "git.example.com"),
+      Some(("me".to_string(), "s3cret".to_string()))
+    );
+    assert_eq!(
+      netrc_login(&netrc, "github.com"),
+      Some(("octocat".to_string(), "gh-token".to_string()))
+    );
+    assert_eq!(
+      netrc_login(&netrc, "gitlab.com"),
+      Some(("anonymous".to_string(), "guest".to_string()))
+    );
+    assert_eq!(
+      netrc_lookup("machine github.com login me\n", "github.com"),
+      None
+    );
+  }
+}
@@ -1,17 +1,24 @@
 use crate::{
   emoji,
-  error::{Error, Result},
+  error::{Error, ErrorKind, Result},
   git::GitOptions,
   util,
 };
 
 use console::style;
-use heck::{KebabCase, SnakeCase};
+use heck::{
+  CamelCase, KebabCase, MixedCase, ShoutySnakeCase, SnakeCase, TitleCase,
+};
+use regex::Regex;
+use tempfile::TempDir;
 use url::{ParseError, Url};
 
 use std::{
+  convert::TryFrom,
   env, fs,
+  io::{self, Read},
   path::{Path, PathBuf},
+  sync::Arc,
 };
 
 /// Information about the new project to be created.
@@ -21,6 +28,9 @@ pub struct ProjectInfo {
   pub name: String,
   /// Base directory of the target project.
   pub path: PathBuf,
+  /// Whether the base directory was created for the project, rather than
+  /// already existing.
+  pub created: bool,
 }
 
 impl ProjectInfo {
@@ -29,7 +39,8 @@ impl ProjectInfo {
     let path = PathBuf::from(p);
 
     // Create project directory.
-    if !path.exists() {
+    let created = !path.exists();
+    if created {
       fs::create_dir_all(&path).unwrap();
     }
 
@@ -51,14 +62,11 @@ impl ProjectInfo {
       name = name.to_snake_case();
     }
 
-    println!(
-      "{} {} {}",
-      emoji::WRENCH,
-      style("Creating project: ").bold().white(),
-      style(&name).bold().yellow()
-    );
-
-    ProjectInfo { name, path }
+    ProjectInfo {
+      name,
+      path,
+      created,
+    }
   }
 }
 
@@ -78,6 +86,26 @@ impl ProjectInfo {
     self.name.to_snake_case()
   }
 
+  /// Get the project name in Title Case.
+  pub fn name_title_case(&self) -> String {
+    self.name.to_title_case()
+  }
+
+  /// Get the project name in UpperCamelCase.
+  pub fn name_upper_camel_case(&self) -> String {
+    self.name.to_camel_case()
+  }
+
+  /// Get the project name in lowerCamelCase.
+  pub fn name_lower_camel_case(&self) -> String {
+    self.name.to_mixed_case()
+  }
+
+  /// Get the project name in SCREAMING_SNAKE_CASE.
+  pub fn name_screaming_snake_case(&self) -> String {
+    self.name.to_shouty_snake_case()
+  }
+
   /// Get owned project path.
   pub fn path(&self) -> PathBuf {
     self.path.clone()
@@ -130,14 +158,20 @@ pub enum TemplateOptions {
   /// git options (e.g) branch stating how we want to fetch the remote
   /// template.
   Remote(GitOptions),
+
+  /// A template read from a tar archive, extracted into a temporary directory
+  /// which is removed once the options are dropped.
+  Archive(Arc<TempDir>),
 }
 
-#[allow(dead_code)]
-#[derive(Debug)]
+#[allow(dead_code, clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RemoteSource {
   GitHub,
   GitLab,
   BitBucket,
+  /// A bare SSH remote with its login e.g. `git@example.com`.
+  SSH(String),
 }
 
 #[allow(dead_code)]
@@ -147,6 +181,7 @@ impl RemoteSource {
       RemoteSource::GitHub => "github",
       RemoteSource::GitLab => "gitlab",
       RemoteSource::BitBucket => "bitbucket",
+      RemoteSource::SSH(_) => "ssh",
     }
   }
 
@@ -164,6 +199,16 @@ impl RemoteSource {
     }
   }
 
+  /// Source hosted on `host` e.g `gitlab.com`, if it's a known host.
+  pub fn from_host(host: &str) -> Option<Self> {
+    match host {
+      "github.com" => Some(Self::GitHub),
+      "gitlab.com" => Some(Self::GitLab),
+      "bitbucket.org" => Some(Self::BitBucket),
+      _ => None,
+    }
+  }
+
   pub fn get_remote(&self, username: &str, repo: &str) -> String {
     match self {
       RemoteSource::GitHub => {
@@ -175,6 +220,9 @@ impl RemoteSource {
       RemoteSource::BitBucket => {
         format!("https://{0}@bitbucket.org/{0}/{1}", username, repo)
       }
+      RemoteSource::SSH(login) => {
+        format!("ssh://{}/{}/{}.git", login, username, repo)
+      }
     }
   }
 }
@@ -188,24 +236,18 @@ impl TemplateOptions {
   /// full absolute path.
   ///
   /// `branch` represents the branch to checkout if it's a git repo.
-  pub fn new(path: &str, branch: Option<&str>) -> TemplateOptions {
+  pub fn new(path: &str, branch: Option<&str>) -> Result<TemplateOptions> {
     // https://github.com/username/repo
     // username/repo
     // relative/path/to/template
-    match Self::parse_path(path, branch.map(|s| s.to_string())) {
-      Ok(opts) => opts,
-      Err(err) => panic!(
-        "{} {} {}",
-        emoji::ERROR,
-        style("ERROR: ").bold().red(),
-        style(err).bold().red()
-      ),
-    }
+    Self::parse_path(path, branch.map(|s| s.to_string()))
   }
 
-  pub fn set_source(&self, _source: &str) {
-    // TODO: Find a way to add source as part of the template's remote options.
-    // self.source = RemoteSource::from_str(source);
+  /// Set the source of a remote template. Local templates have no source.
+  pub fn set_source(&mut self, source: RemoteSource) {
+    if let TemplateOptions::Remote(git_opts) = self {
+      git_opts.set_source(source);
+    }
   }
 
   /// Parses a given path as URL or local file path.
@@ -213,16 +255,54 @@ impl TemplateOptions {
   /// Path can be one of:
   /// - A Full URL e.g. https://github.com/username/repo
   /// - A Shortened Git repo e.g. username/repo
+  /// - A bare SSH remote e.g. git@github.com:username/repo.git
   /// - A local file path.
+  /// - `-` for a tar archive read from stdin.
   fn parse_path(path: &str, branch: Option<String>) -> Result<Self> {
+    // cat template.tar | project new - my-project
+    if path == "-" {
+      return Self::from_tar(io::stdin());
+    }
+
+    // Bare SSH remote e.g. git@github.com:username/repo.git
+    let scp_like =
+      Regex::new(r"^([\w.-]+@[\w.-]+):([^/].*)/([^/]+?)(\.git)?$")?;
+    if let Some(caps) = scp_like.captures(path) {
+      let source = RemoteSource::SSH(caps[1].to_string());
+      let mut opts =
+        Self::parse_path(&source.get_remote(&caps[2], &caps[3]), branch)?;
+      // Known hosts keep their source.
+      if let Self::Remote(git_opts) = &mut opts {
+        if git_opts.source().is_none() {
+          git_opts.set_source(source);
+        }
+      }
+      return Ok(opts);
+    }
+
     let opts = match Url::parse(path) {
       // A valid URL. -- Remote
-      Ok(url) => Self::Remote(GitOptions::new(url, branch)),
+      Ok(url) => {
+        // Source inferred from the host e.g `gitlab.com`.
+        let source = url.host_str().and_then(RemoteSource::from_host);
+        let mut git_opts = GitOptions::new(url, branch);
+        if let Some(source) = source {
+          git_opts.set_source(source);
+        }
+        Self::Remote(git_opts)
+      }
       Err(ParseError::RelativeUrlWithoutBase) => {
         // Might be a relative path or a shortened Git URI.
         match fs::canonicalize(path) {
           // Relative local file path.
           Ok(p) => Self::Local(p),
+          // A mistyped local path, rather than a short Git URI.
+          Err(_err) if Self::looks_local(path) => {
+            return Err(Error::new(
+              ErrorKind::NotFound,
+              &format!("template path not found: {}", path),
+            ));
+          }
           Err(_err) => {
             // Short Git URI.
             // TODO: Add `--source` flag to cli.
@@ -239,6 +319,9 @@ impl TemplateOptions {
                 // https://username@bitbucket.org/username/repo.git
                 format!("https://username@bitbucket.org/{}.git", path)
               }
+              RemoteSource::SSH(login) => {
+                format!("ssh://{}/{}.git", login, path)
+              }
             };
             Self::parse_path(&path, branch)?
           }
@@ -254,17 +337,38 @@ impl TemplateOptions {
 }
 
 impl TemplateOptions {
+  /// Extracts the tar archive read from `reader` into a temporary directory,
+  /// which is the template's base directory.
+  pub fn from_tar<R: Read>(reader: R) -> Result<TemplateOptions> {
+    let dir = tempfile::tempdir()?;
+    tar::Archive::new(reader).unpack(dir.path())?;
+
+    Ok(Self::Archive(Arc::new(dir)))
+  }
+
+  /// Returns true if `path` looks like a local path rather than a short Git
+  /// URI, i.e. it starts with `.`, `/` or `~`, or has more separators than
+  /// `owner/repo`.
+  fn looks_local(path: &str) -> bool {
+    path.starts_with(&['.', '/', '~'][..])
+      || path.contains('\\')
+      || path.matches('/').count() > 1
+  }
+
   pub fn path(&self) -> PathBuf {
     match self {
       TemplateOptions::Local(p) => p.to_owned(),
       TemplateOptions::Remote(g) => g.path(),
+      TemplateOptions::Archive(dir) => dir.path().to_path_buf(),
     }
   }
 }
 
-impl From<&dyn AsRef<Path>> for TemplateOptions {
-  fn from(path: &dyn AsRef<Path>) -> TemplateOptions {
-    TemplateOptions::new(path.as_ref().to_str().unwrap(), None)
+impl TryFrom<&dyn AsRef<Path>> for TemplateOptions {
+  type Error = Error;
+
+  fn try_from(path: &dyn AsRef<Path>) -> Result<TemplateOptions> {
+    TemplateOptions::new(&path.as_ref().to_string_lossy(), None)
   }
 }
 
@@ -275,3 +379,96 @@ impl Default for TemplateOptions {
     Self::Local(curr_dir)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_name_cases() {
+    let project = ProjectInfo {
+      name: "my cool project".to_string(),
+      path: PathBuf::from("my cool project"),
+      created: false,
+    };
+    assert_eq!(project.name_title_case(), "My Cool Project");
+    assert_eq!(project.name_upper_camel_case(), "MyCoolProject");
+    assert_eq!(project.name_lower_camel_case(), "myCoolProject");
+    assert_eq!(project.name_screaming_snake_case(), "MY_COOL_PROJECT");
+    assert_eq!(project.name_kebab_case(), "my-cool-project");
+    assert_eq!(project.name_snake_case(), "my_cool_project");
+  }
+
+  fn remote(path: &str) -> String {
+    match TemplateOptions::parse_path(path, None).unwrap() {
+      TemplateOptions::Remote(opts) => opts.remote().to_string(),
+      other => panic!("Expected a remote: {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_parse_remote_schemes() {
+    assert_eq!(
+      remote("git://example.com/owner/repo.git"),
+      "git://example.com/owner/repo.git"
+    );
+    assert_eq!(
+      remote("ssh://git@example.com/owner/repo.git"),
+      "ssh://git@example.com/owner/repo.git"
+    );
+    assert_eq!(
+      remote("git@github.com:owner/repo.git"),
+      "ssh://git@github.com/owner/repo.git"
+    );
+    assert_eq!(
+      remote("git@gitlab.com:group/sub/repo"),
+      "ssh://git@gitlab.com/group/sub/repo.git"
+    );
+  }
+
+  #[test]
+  fn test_missing_local_path() {
+    for path in &["./does-not-exist", "../does/not/exist", "does/not/exist"] {
+      let err = TemplateOptions::parse_path(path, None).unwrap_err();
+      assert_eq!(err.kind(), &ErrorKind::NotFound);
+    }
+
+    // Library users get the error instead of a panic.
+    assert!(TemplateOptions::new("./does-not-exist", None).is_err());
+
+    // Short Git URIs are still remote templates.
+    assert_eq!(remote("owner/repo"), "https://github.com/owner/repo.git");
+  }
+
+  #[test]
+  fn test_remote_source_from_host() {
+    let source = |path: &str| match TemplateOptions::parse_path(path, None) {
+      Ok(TemplateOptions::Remote(opts)) => opts.source().cloned(),
+      _ => panic!("Expected a remote: {:?}", path),
+    };
+
+    assert_eq!(
+      source("https://github.com/owner/repo.git"),
+      Some(RemoteSource::GitHub)
+    );
+    assert_eq!(
+      source("https://gitlab.com/owner/repo.git"),
+      Some(RemoteSource::GitLab)
+    );
+    assert_eq!(
+      source("https://bitbucket.org/owner/repo"),
+      Some(RemoteSource::BitBucket)
+    );
+    assert_eq!(source("owner/repo"), Some(RemoteSource::GitHub));
+    assert_eq!(
+      source("git@gitlab.com:owner/repo.git"),
+      Some(RemoteSource::GitLab)
+    );
+    assert_eq!(
+      source("git@example.com:owner/repo.git"),
+      Some(RemoteSource::SSH("git@example.com".to_string()))
+    );
+    assert_eq!(source("https://example.com/owner/repo.git"), None);
+  }
+}
@@ -37,16 +37,25 @@
 //!     project [FLAGS] [SUBCOMMAND]
 //!
 //! FLAGS:
-//!     -h, --help       Prints help information
-//!     -q, --quiet      Supress all output. Progress is not reported to the standard error stream.
-//!         --version    Prints version information
-//!     -V, --verbose    Run verbosely.
+//!     -h, --help           Prints help information
+//!     -i, --interactive    Prompt for the template's `[prompts]` variables, and confirm the project's summary like `--confirm`.
+//!     -q, --quiet          Supress all output. Progress is not reported to the standard error stream.
+//!         --silent         Supress all output, even errors. Only the exit code reports a failure.
+//!         --summary-only   Only print a one-line summary of the generated project.
+//!         --version        Prints version information
+//!     -V, --verbose        Run verbosely.
 //!
 //! SUBCOMMANDS:
-//!     git     Initalize project from a GitHub template
-//!     help    Prints this message or the help of the given subcommand(s)
-//!     init    Initialize new project from current dir.
-//!     new     Creates a new project from a local template.
+//!     batch             Generates every project of a TOML manifest, reporting which failed.
+//!     check             Compare a generated project with its template, failing if any generated file differs or is missing.
+//!     config            Read or write user defaults.
+//!     diff-templates    Compare the projects generated by two templates.
+//!     git               Initalize project from a GitHub template
+//!     help              Prints this message or the help of the given subcommand(s)
+//!     init              Initialize new project from current dir.
+//!     new               Creates a new project from a local template.
+//!     render-only       Re-renders the template files matching a glob into an existing project, leaving the others untouched.
+//!     validate-vars     Check a vars file against a template's `[prompts]` & `[validate]`, without generating a project.
 //! ```
 //!
 //!
@@ -74,8 +83,33 @@
 //!
 //! ```sh
 //! $ project init <repo>
+//! $ project init <repo> --name <name>  # Name it other than the current directory.
+//! $ project init <repo> --name-from-remote  # Name it after the repository of its `origin` remote.
 //! $ project new <template> <name>
 //! $ project git <remote> <name> --branch master
+//! $ project helpers  # List the built-in Handlebars helpers.
+//! $ project validate-vars <template> --vars-file vars.toml  # Report every invalid variable, generating nothing.
+//! $ project check <template> <path>  # Exit non-zero, listing the drifted files, if the project drifted from its template.
+//! $ project render-only <template> "src/*.py" <path>  # Re-render only these template files into the project.
+//! $ project batch services.toml --jobs 4  # Generate every `[[project]]` of the manifest, 4 at once.
+//! ```
+//!
+//! To provision several projects at once, `batch` generates every `[[project]]` of a TOML manifest, in order or
+//! `--jobs` at once. Its top-level `template`, `branch` & `[vars]` are shared by the projects, which can override them.
+//! Each project's outcome is reported, and the exit code is non-zero if any failed:
+//!
+//! ```toml
+//! template = "owner/service-template"
+//!
+//! [vars]
+//! license = "MIT"
+//!
+//! [[project]]
+//! name = "billing"
+//!
+//! [[project]]
+//! name = "auth"
+//! vars = { port = "8081" }
 //! ```
 //!
 //! To start a new project from a local template:
@@ -83,7 +117,7 @@
 //! ```sh
 //! $ project new ../relative/path/to/template my-project
 //!
-//! ProjectInfo: ProjectInfo { name: "my-project", path: "/Users/user/project/my-project" }
+//! ProjectInfo: ProjectInfo { name: "my-project", path: "/Users/user/project/my-project", created: true }
 //! TemplateOptions: Local("/Users/user/hbs-template")
 //!
 //! Done generating template into /Users/user/project/my-project
@@ -102,6 +136,10 @@
 //! - `{{project-name}}` - This is supplied by either passing the `name` argument to the CLI or automatically inferred from the base project
 //!   directory.
 //!
+//! - `{{project-name-title}}`, `{{project-name-upper-camel}}`, `{{project-name-lower-camel}}` & `{{project-name-screaming-snake}}` - The
+//!   project name in Title Case, UpperCamelCase, lowerCamelCase & SCREAMING_SNAKE_CASE, e.g. `My Project`, `MyProject`, `myProject` &
+//!   `MY_PROJECT`.
+//!
 //! - `{{author-name}}` - Author's name is deteremed from your `cargo` or `git` configuration or a fallback to environment variables. You can also manually set the `$NAME` or `$USERNAME` environment variable.
 //!
 //! - `{{author-email}}` - Author's email, like `author-name`, it's gotten from your `cargo` or `git` configuration and a fallback to environment variables. You can also manually set `$EMAIL` environment variable.
@@ -115,7 +153,7 @@
 //! # #[clippy::needless_doctest_main]
 //! fn main() {
 //!   let project = ProjectInfo::from("./my-project");
-//!   let options = TemplateOptions::new("victor-iyi/project", None);
+//!   let options = TemplateOptions::new("victor-iyi/project", None).unwrap();
 //!
 //!   let template = Template::new(&project, &options);
 //!   match &template.generate() {
@@ -135,6 +173,32 @@
 //! ```toml
 //! # Available built-in placholder variables are project-name, author-name, author-email.
 //!
+//! # Generate this template on top of a base template, local or remote. Its variables
+//! # & filters are inherited, and its files are overridden by this template's. A local path
+//! # is relative to this file.
+//! extends = "org/base-template"
+//! # Copy this file into the generated project, with its placeholders unresolved, but without its
+//! # `extends`, `[git]`, `[validate]` & `[scripts]`.
+//! keep_template_toml = false
+//! # Message of the commit made with `--git-init`. Defaults to "Initial commit".
+//! initial_commit_message = "Initial commit for {{project-name}}"
+//! # Steps printed once the project is generated, or alone with `--print-next-steps`, as a list
+//! # or one step per line.
+//! next_steps = ["cd {{project-name}}", "make setup"]
+//! # Drop the lines holding nothing but a block tag, e.g. `{{#if license}}` or `{% endif %}`,
+//! # instead of leaving blank lines in rendered files. Handlebars' `{{~` & `~}}` and Liquid's
+//! # `{%-` & `-%}` also trim whitespace, up to the next non-blank character.
+//! trim_blocks = true
+//! # Files from this size in bytes, 8 MiB by default, are copied in chunks into a temporary file
+//! # which is renamed once complete, with their progress shown with `--verbose`.
+//! large_file_size = 8388608
+//! # Engine of the files rendered without a template extension, and of the rendered paths e.g.
+//! # `[rename]` values: "handlebars" by default, or "liquid".
+//! default_engine = "handlebars"
+//! # Lines of rendered files matching this regex are dropped, e.g. notes for the template's authors.
+//! # Files can override it with their own `strip_lines_matching` in their front matter.
+//! strip_lines_matching = "^#\\s*template-only"
+//!
 //! [variables]
 //! project = "{{project-name}}"
 //! author = "{{author-name}}"
@@ -142,17 +206,76 @@
 //! description = "A template project"
 //! py_version = "3.7"
 //!
+//! # Values of an environment selected with `--env`, e.g. `--env prod`, merged over `[variables]`.
+//! [variables.prod]
+//! py_version = "3.9"
+//!
 //! # Replace these directory with the value.
 //! # e.g  path/to/template/file is renamed to path/to/my_project/file
+//! # The values can be converted to a case with `--rename-case`, e.g `kebab` for my-project.
+//! # Values rendered empty, or referencing an unset variable, keep the original name.
 //! [rename]
 //! template = "{{project-name}}"
 //! bin = "scripts"
 //!
-//! # Files or directories present in the templates can also be filtered out of the target project.
+//! # Move files to another directory of the project, creating it if needed.
+//! [move]
+//! "setup.sh" = "tools/setup.sh"
+//!
+//! # Directories generated once per item of a comma separated variable, e.g.
+//! # services = "auth, billing", with the item bound to {{item}}.
+//! # Each copy's name must be a single directory name, e.g. neither `..` nor `a/b`.
+//! [[fanout]]
+//! src = "service_template"
+//! over = "services"
+//! name = "{{item}}-service"
+//!
+//! # Branch of a remote template checked out unless `--branch` is given, instead of the remote's
+//! # default branch. The default branch is cloned first, with every other branch, to read it.
+//! [git]
+//! default_branch = "main"
+//!
+//! # Files copied over as-is, even if they have a template extension.
+//! [raw]
+//! files = ["assets/styles.css.hbs", "dist/**"]
+//!
+//! # Files matching `include` are rendered with the `default_engine` without a template extension.
+//! # Files matching `keep_extension` are rendered, but keep their template extension,
+//! # e.g. to ship Handlebars templates rendered with the project's variables.
+//! [render]
+//! include = ["config/*.conf"]
+//! keep_extension = ["templates/*.hbs"]
+//!
+//! # Files or directories present in the templates can also be filtered out of the target project,
+//! # by name or by glob pattern. Patterns with a `/` match the path in the template, and
+//! # placeholders are rendered first, with their values matched literally.
+//! # Wildcards never match the leading dot of dotfiles: `*.example` keeps `.env.example`, unlike `.env*`.
+//! # `.git` directories & files, e.g. of nested repositories or submodules, are always left out.
 //! [filters]
-//! exclude = ["venv", ".vscode", ".DS_Store"]
+//! exclude = ["venv", ".vscode", ".DS_Store", "*.log", "{{project-name}}-secrets/*"]
+//!
+//! # Variables asked for when running with `--interactive`. Defaults may reference
+//! # environment variables as `${VAR}` or `${VAR:-fallback}`.
+//! [prompts]
+//! license = "${PROJECT_LICENSE:-MIT}"
+//!
+//! # Built-in Handlebars helpers to register, e.g. to avoid clashing with your own partials.
+//! [helpers]
+//! disable = ["replace"]
+//!
+//! # Patterns the values of variables must match.
+//! [validate]
+//! project = "^[a-z][a-z0-9_]*$"
+//!
+//! # Commands run in the generated project when named with `--run`, e.g. `--run install`.
+//! [scripts]
+//! install = "pip install -e ."
+//! lint = "flake8 {{project-name}}"
 //! ```
 //!
+//! Unknown keys of `template.toml`, e.g. a misspelled `[filtres]`, are ignored with a warning, or rejected with
+//! `--template-toml-strict`.
+//!
 //! > **NOTE**: *Every files that ends with either `".hbs"` or `".liquid"` is rendered. Since it is a templating engine, logics, conditionals,*
 //! > *loops are also evaluated and rendered. After the render, the files are saved without the template extensions. E.g. `setup.cfg.hbs` is*
 //! > *rendered and saved as `setup.cfg`.*
@@ -160,10 +283,35 @@
 //! > **WARNING**: *For files without extensions, the template extensions are still required to treat it as a candidate*
 //! > *for template rendering, otherwise the files are just copied over as-is into the target project.*
 //!
+//! Rendered files can start with a TOML front matter block between `---` lines, dropped from the
+//! rendered file, to override their rendered path, their engine, the lines stripped from them, or to
+//! leave them out of the project when a Handlebars condition holds:
+//!
+//! ```txt
+//! ---
+//! path = "config/{{project-name}}.toml"
+//! engine = "liquid"
+//! skip_if = "(eq database \"none\")"
+//! strip_lines_matching = "^#\\s*note:"
+//! ---
+//! [database]
+//! url = "{{ database_url }}"
+//! ```
+//!
+//! Other front matter, e.g. the YAML front matter of a Jekyll site, is rendered as-is.
+//!
 //! Additionally, all `filters` and `tags` of the [`Handlebars`] and [`Liquid`] templating language are
 //! supported.
 //! For more information, checkout the [`Handlebars`] and [`Liquid`] documentation on `Tags` and `Filters`.
 //!
+//! Handlebars templates can branch on variables with the `if_eq`, `if_ne`, `if_gt` & `if_lt` block helpers, which
+//! compare numbers as numbers and anything else as text. Numbers are compared as floats, so versions such as `3.10`
+//! & `3.8` aren't compared as versions:
+//!
+//! ```txt
+//! {{#if_eq lang "python"}}pip install -e .{{else}}npm install{{/if_eq}}
+//! ```
+//!
 //! More [handlebars helpers] are supported in [`handlebars.rs`] and you can also add more helpers to the default ones already provided.
 //! See [`handlebars.rs`] and [handlebars helpers docs] for more information.
 //!
@@ -173,16 +321,26 @@
 //! [handlebars helpers docs]: https://docs.rs/handlebars/3.5.2/handlebars/struct.Handlebars.html#method.register_helper
 
 mod authors;
+pub mod batch;
 mod cli;
+pub mod defaults;
+pub mod diff;
 mod emoji;
 mod error;
 pub mod git;
 mod info;
+pub mod logger;
 mod template;
 pub mod util;
+pub mod version;
+pub mod watch;
 
 // Exported public API.
-pub use self::cli::{Arguments, Cli};
+pub use self::cli::{Arguments, Cli, InputRequest};
 pub use self::error::{Error, ErrorKind, Result};
-pub use self::info::{ProjectInfo, TemplateOptions};
-pub use self::template::Template;
+pub use self::info::{ProjectInfo, RemoteSource, TemplateOptions};
+pub use self::template::{
+  write_helpers, ActionKind, BoxedEngine, Case, GenerationPlan,
+  GenerationStats, PlannedAction, SkipReason, Template, TemplateEngine,
+  Verbosity,
+};
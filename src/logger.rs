@@ -0,0 +1,193 @@
+//! Log file written with `--log-file`, e.g. to debug a failed generation.
+//!
+//! Every record up to the debug level is written on its own line, with its
+//! time, level & module, regardless of the console's verbosity:
+//!
+//! ```txt
+//! 2021-03-01T12:00:00.123Z INFO  project::template: generating "my_project" from Local("template")
+//! ```
+//!
+//! With `--json-logs`, stderr gets one JSON object per line instead, e.g. for
+//! orchestration. Every object has an `event`, either one of the
+//! [`GenerationEvent`]s, or `log` for the other records:
+//!
+//! ```txt
+//! {"event":"file_rendered","path":"my-project/README.md"}
+//! {"event":"log","level":"warn","message":"...","target":"project::error"}
+//! ```
+//!
+//! [`GenerationEvent`]: enum.GenerationEvent.html
+//!
+use crate::template::SkipReason;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+
+use std::{
+  fs::File,
+  io::{self, Write},
+  path::Path,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+  },
+  time::SystemTime,
+};
+
+/// Target of the records of [`GenerationEvent`]s.
+///
+/// [`GenerationEvent`]: enum.GenerationEvent.html
+const EVENTS: &str = "project::events";
+
+/// Whether stderr is NDJSON, i.e. `--json-logs`.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// An event of the generation, logged at the info level.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GenerationEvent<'a> {
+  /// The project is about to be generated from the template.
+  Started {
+    template: &'a Path,
+    project_dir: &'a Path,
+  },
+  /// A file was rendered from the template.
+  FileRendered { path: &'a Path },
+  /// A file was copied from the template as-is.
+  FileCopied { path: &'a Path },
+  /// A directory was created.
+  DirCreated { path: &'a Path },
+  /// A symbolic link was created.
+  LinkCreated { path: &'a Path },
+  /// A template file was left out of the project.
+  FileSkipped {
+    path: &'a Path,
+    reason: &'a SkipReason,
+  },
+  /// A template file failed to generate, with `--keep-going`.
+  FileFailed { path: &'a Path, error: String },
+  /// The project was generated.
+  Finished {
+    generated: usize,
+    skipped: usize,
+    elapsed_ms: u128,
+  },
+}
+
+impl GenerationEvent<'_> {
+  /// Log the event, written as-is with `--json-logs`.
+  pub(crate) fn log(&self) {
+    if let Ok(json) = serde_json::to_string(self) {
+      log::info!(target: EVENTS, "{}", json);
+    }
+  }
+}
+
+/// Whether stderr is NDJSON, i.e. `--json-logs`, so nothing else may be
+/// printed into it.
+pub fn is_json() -> bool {
+  JSON.load(Ordering::Relaxed)
+}
+
+/// A `log` event of the record at `level`, as a line of `--json-logs`.
+pub fn json_record(level: Level, target: &str, message: &str) -> String {
+  serde_json::json!({
+    "event": "log",
+    "level": level.as_str().to_lowercase(),
+    "target": target,
+    "message": message,
+  })
+  .to_string()
+}
+
+/// Logger writing the records up to `level` into a file, or stderr.
+struct FileLogger {
+  file: Mutex<Box<dyn Write + Send>>,
+  level: LevelFilter,
+  json: bool,
+}
+
+impl Log for FileLogger {
+  fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+    // Events are only logged as JSON, as the other records already tell them.
+    metadata.level() <= self.level && (self.json || metadata.target() != EVENTS)
+  }
+
+  fn log(&self, record: &Record<'_>) {
+    if !self.enabled(record.metadata()) {
+      return;
+    }
+
+    if let Ok(mut file) = self.file.lock() {
+      // Logging mustn't fail the generation.
+      let _ = if !self.json {
+        writeln!(
+          file,
+          "{} {:<5} {}: {}",
+          humantime::format_rfc3339_millis(SystemTime::now()),
+          record.level(),
+          record.target(),
+          record.args()
+        )
+      } else if record.target() == EVENTS {
+        writeln!(file, "{}", record.args())
+      } else {
+        let message = record.args().to_string();
+        writeln!(
+          file,
+          "{}",
+          json_record(record.level(), record.target(), &message)
+        )
+      };
+    }
+  }
+
+  fn flush(&self) {
+    if let Ok(mut file) = self.file.lock() {
+      let _ = file.flush();
+    }
+  }
+}
+
+/// Write the log into the file at `path`, truncated first, i.e.
+/// `--log-file`.
+///
+/// Returns an error if the file can't be created, or if a logger was already
+/// set.
+pub fn init(path: &Path) -> io::Result<()> {
+  set_logger(Box::new(File::create(path)?), LevelFilter::Debug, false)
+}
+
+/// Write the records up to the info level into stderr instead, e.g. the
+/// trace of `--trace-renders` without `--log-file`.
+///
+/// Returns an error if a logger was already set.
+pub fn init_stderr() -> io::Result<()> {
+  set_logger(Box::new(io::stderr()), LevelFilter::Info, false)
+}
+
+/// Write the records up to the info level into stderr as NDJSON, i.e.
+/// `--json-logs`, with the generation's events.
+///
+/// Returns an error if a logger was already set.
+pub fn init_json() -> io::Result<()> {
+  set_logger(Box::new(io::stderr()), LevelFilter::Info, true)
+}
+
+fn set_logger(
+  file: Box<dyn Write + Send>,
+  level: LevelFilter,
+  json: bool,
+) -> io::Result<()> {
+  let logger = FileLogger {
+    file: Mutex::new(file),
+    level,
+    json,
+  };
+  log::set_boxed_logger(Box::new(logger))
+    .map_err(|err| io::Error::new(io::ErrorKind::AlreadyExists, err))?;
+  log::set_max_level(level);
+  JSON.store(json, Ordering::Relaxed);
+
+  Ok(())
+}
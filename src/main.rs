@@ -1,25 +1,261 @@
-use project::{Cli, Template};
+use project::{
+  batch,
+  defaults::{self, Defaults},
+  diff,
+  git::{self, CacheCommand},
+  logger, watch, write_helpers, Cli, Template, Verbosity,
+};
 
 use console::style;
 
+use std::{fmt::Display, io, panic, process};
+
 fn main() {
   let cli = Cli::new();
+  if let Some(path) = &cli.args.log_file {
+    if let Err(err) = logger::init(path) {
+      let message = format!("couldn't write the log into {:?}: {}", path, err);
+      exit_with_error(message, cli.args.verbosity);
+    }
+  } else if cli.args.json_logs {
+    // Nothing's logged if a logger was already set.
+    let _ = logger::init_json();
+    // Templates which fail to load panic, which is logged as JSON too.
+    panic::set_hook(Box::new(|info| {
+      let payload = info.payload();
+      let message = payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("panicked");
+      log::error!("{}", console::strip_ansi_codes(message).trim());
+    }));
+  } else if cli.args.trace_renders {
+    // Nothing's traced if a logger was already set.
+    let _ = logger::init_stderr();
+  }
+
+  // Only generating the project needs the defaults.
+  if cli.args.generates() && !cli.args.watch && !cli.args.no_first_run {
+    let dir = defaults::config_dir();
+    match Defaults::first_run(&dir) {
+      Ok(Some(_)) if cli.args.verbosity.shows_progress() => eprintln!(
+        "{} {}",
+        style("Created your defaults in").bold(),
+        style(dir.join(defaults::DEFAULTS_FILE).display())
+          .bold()
+          .white()
+      ),
+      Ok(_) => {}
+      // Defaults are optional, so the project is generated anyway.
+      Err(err) => warn(
+        format!("couldn't create the defaults: {}", err),
+        cli.args.verbosity,
+      ),
+    }
+  }
+  if let Some(command) = &cli.args.config {
+    match Defaults::run(command, &defaults::config_dir()) {
+      Ok(Some(value)) => println!("{}", value),
+      Ok(None) => (),
+      Err(err) => exit_with_error(err, cli.args.verbosity),
+    }
+    return;
+  }
+
+  if let Some(CacheCommand::Clean { dry_run, force }) = &cli.args.cache {
+    let dir = cli.args.cache_dir.clone().unwrap_or_else(git::cache_dir);
+    match git::clean_cache(&dir, *dry_run, *force) {
+      Ok(paths) => {
+        let action = if *dry_run { "Would remove" } else { "Removed" };
+        for path in &paths {
+          println!("{} {}", action, path.display());
+        }
+      }
+      Err(err) => exit_with_error(err, cli.args.verbosity),
+    }
+    return;
+  }
+
+  if cli.args.helpers {
+    // Nothing to do if stdout is closed.
+    let _ = write_helpers(&mut io::stdout());
+    return;
+  }
+
+  if let Some(other) = &cli.args.diff {
+    let name = cli.args.project.raw();
+    match diff::diff_templates(&cli.args.template, other, &name) {
+      Ok(changes) if changes.is_empty() => {
+        println!("{}", style("No differences.").bold());
+      }
+      Ok(changes) => {
+        for change in &changes {
+          println!("{}", change);
+          if let (true, diff::Change::Changed(_, diff)) =
+            (cli.args.verbosity.shows_details(), change)
+          {
+            print!("{}", diff);
+          }
+        }
+      }
+      Err(err) => exit_with_error(err, cli.args.verbosity),
+    }
+    return;
+  }
+
+  if let Some(manifest) = &cli.args.batch {
+    match batch::run(manifest, &cli.args, cli.args.jobs) {
+      Ok(results) => {
+        // Nothing to do if stdout is closed.
+        let _ = batch::write_report(&results, &mut io::stdout());
+        if results.iter().any(|result| result.result.is_err()) {
+          process::exit(1);
+        }
+      }
+      Err(err) => exit_with_error(err, cli.args.verbosity),
+    }
+    return;
+  }
+
+  if cli.args.watch {
+    if let Err(err) = watch::watch(&cli.args) {
+      exit_with_error(err, cli.args.verbosity);
+    }
+    return;
+  }
+
   let template = Template::from(&cli.args);
+  if cli.args.check {
+    match diff::check_project(&template, &cli.args.project.path()) {
+      Ok(changes) if changes.is_empty() => {
+        if cli.args.verbosity.shows_progress() {
+          println!("{}", style("No drift from the template.").bold());
+        }
+      }
+      Ok(changes) => {
+        for change in &changes {
+          println!("{}", change);
+          if let (true, diff::Change::Changed(_, diff)) =
+            (cli.args.verbosity.shows_details(), change)
+          {
+            print!("{}", diff);
+          }
+        }
+        process::exit(1);
+      }
+      Err(err) => exit_with_error(err, cli.args.verbosity),
+    }
+    return;
+  }
+  if let Some(vars_file) = &cli.args.vars_file {
+    match template.validate_vars(vars_file) {
+      Ok(()) if cli.args.verbosity.shows_progress() => {
+        println!("{}", style("Variables are valid.").bold());
+      }
+      Ok(()) => {}
+      Err(err) => exit_with_error(err, cli.args.verbosity),
+    }
+    return;
+  }
+
+  if cli.args.dump_vars {
+    println!("{}", template.variables_json());
+    if cli.args.dump_vars_only {
+      return;
+    }
+  }
+
+  if cli.args.print_next_steps {
+    match template.next_steps() {
+      Ok(steps) => {
+        for step in steps {
+          println!("{}", step);
+        }
+      }
+      Err(err) => exit_with_error(err, cli.args.verbosity),
+    }
+    return;
+  }
+
+  if cli.args.dry_run {
+    match template.plan() {
+      Ok(plan) if cli.args.json => println!("{}", plan.to_json()),
+      Ok(plan) => print!("{}", plan),
+      Err(err) => exit_with_error(err, cli.args.verbosity),
+    }
+    return;
+  }
+
+  if let Some(archive) = &cli.args.archive {
+    if let Err(err) = template.archive(archive) {
+      exit_with_error(err, cli.args.verbosity);
+    }
+    return;
+  }
+
+  // The project's directory is snapshotted before anything's generated into it.
+  let snapshot = if cli.args.print_diff_after {
+    match diff::Snapshot::take(&cli.args.project.path()) {
+      Ok(snapshot) => Some(snapshot),
+      Err(err) => exit_with_error(err, cli.args.verbosity),
+    }
+  } else {
+    None
+  };
+
   match template.generate() {
+    // The JSON summary is all that's printed.
+    Ok(_) if cli.args.json => {}
+
+    // The project already existed.
+    Ok(_) if cli.args.render_only.is_some() => {}
     Ok(_) => {
-      println!("{}", style("Go to project's directory:").bold());
-      if cfg!(unix) {
-        println!("\t$ cd {}", cli.args.project.rel_path().display());
-        println!("\t$ ls");
-      } else {
-        println!("\t> cd {}", cli.args.project.rel_path().display());
-        println!("\t> dir")
+      if let Some(snapshot) = &snapshot {
+        match snapshot.compare() {
+          Ok(report) => print!("{}", report),
+          Err(err) => exit_with_error(err, cli.args.verbosity),
+        }
+      }
+
+      // The project is generated either way.
+      let steps = template.next_steps().unwrap_or_else(|err| {
+        let message = format!("couldn't render the next steps: {}", err);
+        warn(message, cli.args.verbosity);
+        Vec::new()
+      });
+      // Nothing to do if stdout is closed.
+      let _ = cli.args.write_next_steps(&steps, &mut io::stdout());
+      if cli.args.open {
+        // The project is generated either way.
+        if let Err(err) = cli.args.open_project() {
+          let message = format!("couldn't open the project: {}", err);
+          warn(message, cli.args.verbosity);
+        }
       }
     }
-    Err(err) => eprintln!(
+    Err(err) => exit_with_error(err, cli.args.verbosity),
+  }
+}
+
+/// Report the warning `message`, unless running with `--silent`. It's only
+/// logged if a logger was set up, e.g. with `--log-file`.
+fn warn(message: impl Display, verbosity: Verbosity) {
+  log::warn!("{}", message);
+  if verbosity.shows_errors() && !logger::is_json() {
+    eprintln!("{}", style(message).bold().yellow());
+  }
+}
+
+/// Report `err`, unless running with `--silent`, and exit with a failure.
+fn exit_with_error(err: impl Display, verbosity: Verbosity) -> ! {
+  log::error!("{}", err);
+  if verbosity.shows_errors() {
+    eprintln!(
       "{} {}",
       style("ERROR:").bold().red(),
       style(err).bold().red()
-    ),
+    );
   }
+  process::exit(1);
 }
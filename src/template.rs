@@ -1,29 +1,326 @@
 use console::style;
+use filetime::FileTime;
+use glob::{MatchOptions, Pattern as GlobPattern};
+use heck::{CamelCase, KebabCase, MixedCase, SnakeCase};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use walkdir::{DirEntry, WalkDir};
 
 use crate::{
+  authors,
   cli::{Arguments, Cli},
+  defaults::{self, Defaults},
   emoji,
-  error::Result,
-  git::{self, GitOptions},
+  error::{self, Error, ErrorKind, MultiError, Result},
+  git::{self, CachePolicy, GitOptions},
   info::{ProjectInfo, TemplateOptions},
-  template::{
-    config::TemplateConfig,
-    engine::{Engine, TemplateEngine},
-  },
+  logger::{self, GenerationEvent},
+  template::{config::TemplateConfig, engine::Engine, lock::TemplateLock},
+  util,
 };
 
 use std::{
-  collections::HashMap,
+  collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+  env,
+  ffi::OsStr,
   fmt, fs,
+  io::{self, BufRead, IsTerminal, Read, Write},
   ops::Deref,
-  path::{Path, PathBuf},
+  path::{Component, Path, PathBuf},
+  process,
+  rc::Rc,
+  str::FromStr,
+  time::{Duration, Instant},
 };
 
+pub(crate) mod archive;
+pub(crate) mod compat;
 pub(crate) mod config;
+pub(crate) mod copy;
 pub(crate) mod engine;
+pub(crate) mod front_matter;
 pub(crate) mod helpers;
+pub(crate) mod inherit;
+pub(crate) mod lock;
 pub(crate) mod parser;
+pub(crate) mod prompt;
+
+pub use self::engine::TemplateEngine;
+pub use self::helpers::write_helpers;
+
+/// Message of the `--git-init` commit, unless the template or
+/// `--commit-message` provides one.
+const DEFAULT_COMMIT_MESSAGE: &str = "Initial commit";
+
+/// A custom templating engine, see [`Template::register_engine`].
+///
+/// [`Template::register_engine`]: struct.Template.html#method.register_engine
+pub type BoxedEngine = Box<dyn TemplateEngine<Data = HashMap<String, String>>>;
+
+/// A custom templating engine shared by a template & the templates it
+/// `extends`.
+type SharedEngine = Rc<dyn TemplateEngine<Data = HashMap<String, String>>>;
+
+/// Directory of a copy of a `[[fanout]]` directory, relative to the template's
+/// base directory, with the variables it's rendered with.
+type FanoutCopy = (PathBuf, HashMap<String, String>);
+
+/// Summary of a generated project, returned by [`Template::generate`].
+///
+/// It's printed as JSON with `--format json`, e.g:
+///
+/// ```json
+/// {
+///   "schema_version": 1,
+///   "project_dir": "/home/user/my-project",
+///   "generated": ["/home/user/my-project/README.md"],
+///   "skipped": [["template.toml", "TemplateFile"], ["debug.log", {"ConfigExclude": "*.log"}]],
+///   "elapsed": {"secs": 0, "nanos": 1200000}
+/// }
+/// ```
+///
+/// [`Template::generate`]: struct.Template.html#method.generate
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenerationStats {
+  /// Version of this schema, i.e. [`SCHEMA_VERSION`], bumped whenever a field
+  /// is changed or removed.
+  ///
+  /// [`SCHEMA_VERSION`]: #associatedconstant.SCHEMA_VERSION
+  pub schema_version: u32,
+  /// Absolute path of the generated project.
+  pub project_dir: PathBuf,
+  /// Target paths of the generated files, in order.
+  pub generated: BTreeSet<PathBuf>,
+  /// Template files & directories left out of the project, relative to their
+  /// template's directory, and why.
+  pub skipped: Vec<(PathBuf, SkipReason)>,
+  /// Time taken to generate the files, as `secs` & `nanos`.
+  pub elapsed: Duration,
+}
+
+impl GenerationStats {
+  /// Current version of the JSON schema.
+  pub const SCHEMA_VERSION: u32 = 1;
+
+  /// Summary as a JSON object, i.e. `--format json`.
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).expect("stats are serializable")
+  }
+}
+
+impl Default for GenerationStats {
+  fn default() -> GenerationStats {
+    GenerationStats {
+      schema_version: GenerationStats::SCHEMA_VERSION,
+      project_dir: PathBuf::new(),
+      generated: BTreeSet::new(),
+      skipped: Vec::new(),
+      elapsed: Duration::default(),
+    }
+  }
+}
+
+/// Files a template would generate, returned by [`Template::plan`].
+///
+/// It's printed with `--dry-run`, or as JSON with `--format json`.
+///
+/// [`Template::plan`]: struct.Template.html#method.plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationPlan {
+  /// Absolute path of the project to generate.
+  pub project_dir: PathBuf,
+  /// What's done with every template file & directory, in order. The files
+  /// of the templates it `extends` come first.
+  pub actions: Vec<PlannedAction>,
+}
+
+impl GenerationPlan {
+  /// Plan as a JSON object, i.e. `--dry-run --format json`.
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).expect("plan is serializable")
+  }
+}
+
+impl fmt::Display for GenerationPlan {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for action in &self.actions {
+      match (&action.kind, &action.target) {
+        (ActionKind::Skip(reason), _) => writeln!(
+          f,
+          "{:<6} {} ({:?})",
+          "Skip",
+          action.source.display(),
+          reason
+        )?,
+        (kind, Some(target)) => writeln!(
+          f,
+          "{:<6} {} -> {}",
+          format!("{:?}", kind),
+          action.source.display(),
+          target.display()
+        )?,
+        (kind, None) => writeln!(
+          f,
+          "{:<6} {}",
+          format!("{:?}", kind),
+          action.source.display()
+        )?,
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// A template file or directory, and what generating it does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedAction {
+  /// Path relative to its template's directory.
+  pub source: PathBuf,
+  /// Absolute path generated in the project, without its template extension
+  /// if it's rendered. Skipped files have none.
+  pub target: Option<PathBuf>,
+  /// What's done with the `source`.
+  pub kind: ActionKind,
+}
+
+/// What generating a template file or directory does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ActionKind {
+  /// An empty directory is created.
+  Create,
+  /// The file is copied as-is.
+  Copy,
+  /// The file is rendered with a templating engine.
+  Render,
+  /// The symlink is recreated, with its target rendered.
+  Link,
+  /// The file or directory is left out of the project.
+  Skip(SkipReason),
+}
+
+/// Drop the lines of the rendered `content` matching the regex `pattern`,
+/// i.e. `strip_lines_matching`, e.g. notes for the template's authors.
+fn strip_lines(content: &str, pattern: &Regex) -> String {
+  content
+    .split_inclusive('\n')
+    .filter(|line| !pattern.is_match(line.trim_end_matches(&['\r', '\n'][..])))
+    .collect()
+}
+
+/// Called with every action planned by a template, see `plan_files`.
+type Visit<'a> = dyn FnMut(
+    &Template,
+    &PlannedAction,
+    &Path,
+    Option<&HashMap<String, String>>,
+  ) -> Result<()>
+  + 'a;
+
+/// Why a template file or directory was left out of the generated project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SkipReason {
+  /// Git history of a cloned template, or of a repository nested in it e.g.
+  /// a submodule, i.e. `.git`.
+  Git,
+  /// The `template.toml` file, unless it's kept.
+  TemplateFile,
+  /// Matched a default filter pattern, for templates without `template.toml`.
+  DefaultExclude(String),
+  /// Matched a pattern of the `[filters]` clause.
+  ConfigExclude(String),
+  /// The `skip_if` condition of the file's front matter held.
+  Condition(String),
+}
+
+/// Case of the components renamed by `[rename]`, i.e. `--rename-case`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+  /// `snake_case`, e.g. Python packages.
+  Snake,
+  /// `kebab-case`, e.g. Rust crates.
+  Kebab,
+  /// `camelCase`.
+  Camel,
+  /// `PascalCase`.
+  Pascal,
+}
+
+impl Case {
+  /// Names of the cases, as passed to `--rename-case`.
+  pub const NAMES: &'static [&'static str] =
+    &["snake", "kebab", "camel", "pascal"];
+
+  /// Convert a file or directory `name` to this case, keeping its leading
+  /// dots & extension e.g `.{{project-name}}.toml`.
+  pub fn apply(&self, name: &str) -> String {
+    let stripped = name.trim_start_matches('.');
+    let dots = &name[..name.len() - stripped.len()];
+    let (stem, ext) = match stripped.find('.') {
+      Some(index) => stripped.split_at(index),
+      None => (stripped, ""),
+    };
+
+    let stem = match self {
+      Case::Snake => stem.to_snake_case(),
+      Case::Kebab => stem.to_kebab_case(),
+      Case::Camel => stem.to_mixed_case(),
+      Case::Pascal => stem.to_camel_case(),
+    };
+    format!("{}{}{}", dots, stem, ext)
+  }
+}
+
+impl FromStr for Case {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Case> {
+    match s {
+      "snake" => Ok(Case::Snake),
+      "kebab" => Ok(Case::Kebab),
+      "camel" => Ok(Case::Camel),
+      "pascal" => Ok(Case::Pascal),
+      _ => Err(Error::new(
+        ErrorKind::Error,
+        &format!("unknown case {:?}, expected one of {:?}", s, Case::NAMES),
+      )),
+    }
+  }
+}
+
+/// How much is reported while generating a project, from `--silent`,
+/// `--quiet`, `--summary-only` & `--verbose`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub enum Verbosity {
+  /// Nothing is reported, not even errors.
+  Silent,
+  /// Nothing but errors is reported.
+  Quiet,
+  /// Only a one-line summary of the generated project.
+  SummaryOnly,
+  /// Progress & where the project was created.
+  #[default]
+  Normal,
+  /// Also how many files were generated & how long it took.
+  Verbose,
+}
+
+impl Verbosity {
+  /// Whether errors are reported.
+  pub fn shows_errors(&self) -> bool {
+    *self > Verbosity::Silent
+  }
+
+  /// Whether progress & the next steps are reported.
+  pub fn shows_progress(&self) -> bool {
+    *self >= Verbosity::Normal
+  }
+
+  /// Whether details, e.g timings or diffs, are reported.
+  pub fn shows_details(&self) -> bool {
+    *self == Verbosity::Verbose
+  }
+}
 
 /// Template builds and generates the project from a given template.
 ///
@@ -35,7 +332,7 @@ pub(crate) mod parser;
 /// # #[allow(clippy::needless_doctest_main)]
 /// fn main() {
 ///   let project = ProjectInfo::from("my-project");
-///   let options = TemplateOptions::new("victor-iyi/project", None);
+///   let options = TemplateOptions::new("victor-iyi/project", None).unwrap();
 ///
 ///   let template = Template::new(&project, &options);
 /// # std::fs::remove_dir_all(&project.path()).unwrap();
@@ -52,12 +349,84 @@ impl Template {
     template_options: &TemplateOptions,
   ) -> Template {
     Template {
-      template: TemplateMeta::new(project_info, template_options),
+      template: TemplateMeta::new(
+        project_info,
+        template_options,
+        config::TEMPLATE_FILE,
+        false,
+        Verbosity::Normal,
+      ),
     }
   }
 }
 
 impl Template {
+  /// Render files with extension `ext` using a custom `engine`, instead of
+  /// copying them over. The extension is dropped from the generated file e.g
+  /// `README.md.foo` is rendered into `README.md`.
+  ///
+  /// Custom engines take precedence over the built-in ones, including in the
+  /// templates it `extends`.
+  pub fn register_engine(&mut self, ext: &str, engine: BoxedEngine) {
+    self.template.register_engine(ext, Rc::from(engine));
+  }
+
+  /// Set the variable `key` to `value`, overriding its value from the
+  /// template's config, including the built-in placeholders.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use project::{ProjectInfo, TemplateOptions, Template};
+  ///
+  /// # fn main() -> std::io::Result<()> {
+  /// let template_dir = tempfile::tempdir()?;
+  /// let license = "{{project-name}} is {{license}} licensed.";
+  /// std::fs::write(template_dir.path().join("LICENSE.hbs"), license)?;
+  /// let project_dir = tempfile::tempdir()?;
+  ///
+  /// let project = ProjectInfo::new(&project_dir.path().join("my-project"));
+  /// let options = TemplateOptions::Local(template_dir.path().to_path_buf());
+  /// let mut template = Template::new(&project, &options);
+  /// template.set_variable("license", "MIT");
+  /// template.generate().unwrap();
+  ///
+  /// let license = std::fs::read_to_string(project.path().join("LICENSE"))?;
+  /// assert_eq!(license, "my_project is MIT licensed.");
+  /// # Ok(())
+  /// # }
+  /// ```
+  pub fn set_variable(&mut self, key: &str, value: &str) {
+    let mut template = Some(self);
+    while let Some(current) = template {
+      current
+        .variables_mut()
+        .insert(key.to_string(), value.to_string());
+      template = current.template.base.as_deref_mut();
+    }
+  }
+
+  /// The template's variables, to be overridden before it's generated.
+  /// Unlike [`set_variable`], the templates it `extends` keep theirs.
+  ///
+  /// [`set_variable`]: #method.set_variable
+  pub fn variables_mut(&mut self) -> &mut HashMap<String, String> {
+    self
+      .template
+      .config
+      .variables
+      .get_or_insert_with(HashMap::new)
+  }
+
+  /// The resolved variables as a JSON object ordered by name, i.e.
+  /// `--dump-vars json`.
+  pub fn variables_json(&self) -> String {
+    let variables: BTreeMap<String, String> =
+      self.variables().into_iter().collect();
+    serde_json::to_string_pretty(&variables)
+      .expect("variables are serializable")
+  }
+
   /// Most important function for this entire library is this method.
   ///
   /// This is where all the parts come together to build new project from a template source,
@@ -69,6 +438,11 @@ impl Template {
   /// It also applies necessary configurations: like filtering excluded files & directories,
   /// renaming target files and directories and many more.
   ///
+  /// Returns the generated files along with the ones which were skipped,
+  /// see [`GenerationStats`]. A project directory which isn't empty is only
+  /// overwritten once confirmed by the user on the command line, so it's an
+  /// error otherwise.
+  ///
   /// # Example
   ///
   /// ```rust, no_run
@@ -77,289 +451,4433 @@ impl Template {
   /// # #[allow(clippy::needless_doctest_main)]
   /// # fn main() {
   ///   let project = ProjectInfo::from("my-project");
-  ///   let options = TemplateOptions::new("victor-iyi/project", None);
+  ///   let options = TemplateOptions::new("victor-iyi/project", None).unwrap();
   ///
   ///   let template = Template::new(&project, &options);
-  ///   assert!(&template.generate().is_ok());
+  ///   let stats = template.generate().unwrap();
+  ///   for (path, reason) in &stats.skipped {
+  ///     println!("Skipped {:?}: {:?}", path, reason);
+  ///   }
   /// # std::fs::remove_dir_all(&project.path()).unwrap();
   /// # }
   /// ```
-  pub fn generate(&self) -> Result<()> {
-    // Project path.
-    let project_dir = &self.project_info.path;
-    // Template path.
-    let template_dir = &self.template_options.path();
-
-    // Walk the `template_dir`.
-    for entry in WalkDir::new(template_dir)
-      .into_iter()
-      .filter_entry(|e| !self.filter_ignore(e))
-      .filter_map(|e| e.ok())
-    {
-      // Strip `template_dir` from entry.
-      let relative_path = entry.path().strip_prefix(template_dir)?;
-      // Append stripped path to `project_dir`.
-      let target = self.rename_path(relative_path, project_dir);
-
-      if entry.path().is_dir() {
-        fs::create_dir_all(&target)?;
-      } else {
-        self.substitute(entry.path(), &target)?;
-      }
-    }
-
-    println!("{} {}", emoji::SPARKLE, style("Finished!").bold().green(),);
-    println!(
-      "{} \"{}\"",
-      style("Project created in: ").bold().white(),
-      style(&self.project_info.path().display()).bold().yellow()
-    );
-
-    Ok(())
+  ///
+  /// [`GenerationStats`]: struct.GenerationStats.html
+  pub fn generate(&self) -> Result<GenerationStats> {
+    self.generate_into(&self.project_info.path)
   }
 
-  /// Rename path based on the config file i.e. `"template.toml"` file.
-  /// If there's no `[rename]` clause in the template file, the template
-  /// filename is used instead.
+  /// Generate the project into the `target` directory instead of the
+  /// project's, see [`generate`], e.g. to preview it before generating it
+  /// for good.
+  ///
+  /// The project's name, e.g. `{{project-name}}`, still comes from its
+  /// [`ProjectInfo`], whatever the `target`.
   ///
   /// # Example
   ///
-  /// ```toml
-  /// # template.toml
-  /// [rename]
-  /// template = {{ project-name }}
-  /// bin = "scripts"
-  /// ```
+  /// ```rust
+  /// use project::{ProjectInfo, TemplateOptions, Template};
   ///
-  /// `{{ project-name }}` will be resolved to whatever the project's name is
-  /// e.g `"my_project"`. Therefore, `path/to/template/file` will be renamed
-  /// to `path/to/my_project/file`. Same with `bin` which will be renamed
-  /// to `scripts`.
-  fn rename_path(&self, relative_path: &Path, project_dir: &Path) -> PathBuf {
-    let maps = self.rename_maps();
-    if maps.is_empty() {
-      // Append stripped path to `project_dir`.
-      project_dir.join(relative_path)
-    } else {
-      // Go through the `maps` & rename paths accordingly.
-      let mut rel_path = relative_path.to_path_buf();
-      for (key, value) in &maps {
-        // If `key` occurs in `rel_path`, replace the occurrenc with `value`.
-        let renamed: PathBuf = rel_path
-          .iter()
-          .map(|path| -> &str {
-            if key == path.to_str().unwrap() {
-              value
-            } else {
-              path.to_str().unwrap()
-            }
-          })
-          .collect();
-
-        if renamed != rel_path {
-          rel_path = renamed;
-        }
-      }
-      // Append `rel_path` to `project_dir`.
-      project_dir.join(rel_path)
-    }
-  }
-
-  /// Template substitution is done here, based on the `src` file.
+  /// # fn main() -> std::io::Result<()> {
+  /// let template_dir = tempfile::tempdir()?;
+  /// let readme = "# {{project-name}}\n";
+  /// std::fs::write(template_dir.path().join("README.md.hbs"), readme)?;
+  /// let dir = tempfile::tempdir()?;
   ///
-  /// If the `src` file or the template file has extensions supported by [`Engine`],
-  /// template substitution will be done for such file, otherwise the file is just
-  /// copied over to the `dest` or target/project file.
+  /// let project = ProjectInfo::new(&dir.path().join("my-project"));
+  /// let options = TemplateOptions::Local(template_dir.path().to_path_buf());
+  /// let template = Template::new(&project, &options);
   ///
-  /// **NOTE:** For files without extension; if you want it to be templated, append
-  /// any extension supported by [`Engine`] e.g `".hbs"` or `".liquid"` as it's extension.
-  /// It will be parsed and the extension will be dropped before writing to the target
-  /// file.
+  /// let preview = dir.path().join("preview");
+  /// template.generate_into(&preview).unwrap();
+  /// template.generate().unwrap();
   ///
-  /// See [`Engine`] for more details.
+  /// for target in &[preview, project.path()] {
+  ///   let readme = std::fs::read_to_string(target.join("README.md"))?;
+  ///   assert_eq!(readme, "# my_project\n");
+  /// }
+  /// # Ok(())
+  /// # }
+  /// ```
   ///
-  /// [`Engine`]: struct.Engine
-  fn substitute(&self, src: &Path, dest: &Path) -> Result<()> {
-    if let Some(ext) = src.extension() {
-      let engine = Engine::new(ext);
+  /// [`generate`]: #method.generate
+  /// [`ProjectInfo`]: struct.ProjectInfo.html
+  pub fn generate_into(&self, target: &Path) -> Result<GenerationStats> {
+    let stdin = io::stdin();
+    let terminal = stdin.is_terminal();
+    self.generate_with(target, &mut stdin.lock(), &mut io::stdout(), terminal)
+  }
 
-      engine.render(src, &dest, &self.variables())?;
-    } else {
-      // Copy over file without extension. If you want it to be
-      // templated, append ".hbs" or ".liquid" as extension.
-      fs::copy(src, dest)?;
+  /// Generate the project into `project_dir`, see [`generate_into`], reading
+  /// the answers to its questions from `input` & asking them on `output`.
+  /// With `--confirm`, `input` must be a `terminal`.
+  ///
+  /// [`generate_into`]: #method.generate_into
+  fn generate_with<R: BufRead, W: Write>(
+    &self,
+    project_dir: &Path,
+    input: &mut R,
+    output: &mut W,
+    terminal: bool,
+  ) -> Result<GenerationStats> {
+    self.validate()?;
+    self.validate_target(project_dir)?;
+
+    // Updates regenerate the files of an existing project.
+    let in_place = self.update || self.render_only.is_some();
+    if !in_place && !self.confirm_overwrite(project_dir, input, output)? {
+      return Err(Error::new(
+        ErrorKind::Error,
+        &format!("{:?} is not empty, not overwriting it.", project_dir),
+      ));
     }
 
-    Ok(())
-  }
+    // Nothing is written unless the summary is confirmed, which can't be
+    // waited for without a terminal.
+    if self.confirm && !self.assume_yes {
+      if !terminal {
+        return Err(Error::new(
+          ErrorKind::Error,
+          "--confirm needs a terminal to answer on, pass --assume-yes to generate without confirming",
+        ));
+      }
+      if !self.confirm_generation(project_dir, input, output)? {
+        return Err(Error::new(
+          ErrorKind::Error,
+          &format!(
+            "Generation aborted, nothing was written into {:?}.",
+            project_dir
+          ),
+        ));
+      }
+    }
 
-  fn filter_ignore(&self, entry: &DirEntry) -> bool {
-    // Filterignored/included files here...
-    let (should_ignore, files) = self.get_ignored();
+    // Warnings raised while loading the template.
+    for warning in &self.warnings {
+      error::warn(warning, self.abort_on_warning)?;
+    }
 
-    if should_ignore {
-      entry
-        .file_name()
-        .to_str()
-        .map(|s| files.contains(&s.to_string()))
-        .unwrap_or(false)
+    // Exact commit the remote template resolved to.
+    let lock = if self.locked || self.write_lock {
+      Some(self.resolve_lock()?)
+    } else if self.update {
+      // Local templates aren't locked.
+      self.resolve_lock().ok()
     } else {
-      !entry
-        .file_name()
-        .to_str()
-        .map(|s| files.contains(&s.to_string()))
-        .unwrap_or(false)
+      None
+    };
+
+    // Template files changed since the project was generated.
+    let changed = if self.update {
+      Some(self.changed_files(project_dir)?)
+    } else if let Some(glob) = &self.render_only {
+      Some(self.render_only_files(glob)?)
+    } else {
+      None
+    };
+    if let (true, Some(lock)) = (self.locked, &lock) {
+      TemplateLock::read(project_dir)?.verify(lock)?;
+    }
+    if let Some(expected) = &self.expect_tree_sha {
+      self.verify_tree_sha(expected)?;
     }
-  }
-}
 
-impl From<&Arguments> for Template {
-  fn from(args: &Arguments) -> Template {
-    Template {
-      template: TemplateMeta::new(&args.project, &args.template),
+    log::info!(
+      "generating {:?} from {:?} into {:?}",
+      self.project_info.name,
+      self.template_options,
+      project_dir
+    );
+    GenerationEvent::Started {
+      template: &self.template_options.path(),
+      project_dir,
     }
-  }
-}
+    .log();
+    let start = Instant::now();
+    let mut stats = GenerationStats {
+      project_dir: project_dir.to_path_buf(),
+      ..GenerationStats::default()
+    };
+    self.generate_files(project_dir, &mut stats, changed.as_ref())?;
+    stats.elapsed = start.elapsed();
+    log::info!(
+      "generated {} files ({} skipped) in {:?}",
+      stats.generated.len(),
+      stats.skipped.len(),
+      stats.elapsed
+    );
+    GenerationEvent::Finished {
+      generated: stats.generated.len(),
+      skipped: stats.skipped.len(),
+      elapsed_ms: stats.elapsed.as_millis(),
+    }
+    .log();
 
-impl From<&Cli<'_>> for Template {
-  fn from(cli: &Cli) -> Template {
-    Self::from(&cli.args)
-  }
-}
+    // A template without any file but its configuration is likely the wrong
+    // template path.
+    let config_file = OsStr::new(&self.config.file_name);
+    if !in_place
+      && stats
+        .generated
+        .iter()
+        .all(|path| path.file_name() == Some(config_file))
+    {
+      error::warn(
+        &format!(
+          "template {:?} has no files to generate, is it the right path?",
+          self.template_options.path()
+        ),
+        self.abort_on_warning,
+      )?;
+    }
 
-impl Default for Template {
-  fn default() -> Template {
-    Template {
-      template: TemplateMeta::default(),
+    if let (true, Some(lock)) = (self.write_lock || self.update, &lock) {
+      lock.write(project_dir)?;
     }
-  }
-}
 
-impl Deref for Template {
-  type Target = TemplateMeta;
-  fn deref(&self) -> &Self::Target {
-    &self.template
+    for name in &self.run {
+      self.run_script(name, project_dir)?;
+    }
+
+    if self.git_init {
+      git::init_commit(project_dir, &self.commit_message()?)?;
+    }
+
+    // Nothing to do if stdout is closed.
+    let _ = self.write_summary_of(&stats, project_dir, &mut io::stdout());
+
+    Ok(stats)
   }
-}
 
-impl fmt::Debug for Template {
-  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    f.debug_struct("Template")
-      .field("option", &self.template_options)
-      .field("project", &self.project_info)
-      .field("config", &self.config)
-      .finish()
+  /// Regenerate the project in place, without asking before overwriting its
+  /// files, i.e. `--watch`. Only the template files in `changed`, relative
+  /// to its directory, are regenerated if given, like with `render-only`.
+  pub(crate) fn regenerate(
+    &self,
+    changed: Option<&HashSet<PathBuf>>,
+  ) -> Result<GenerationStats> {
+    let project_dir = &self.project_info.path;
+    self.validate()?;
+
+    let start = Instant::now();
+    let mut stats = GenerationStats {
+      project_dir: project_dir.to_path_buf(),
+      ..GenerationStats::default()
+    };
+    self.generate_files(project_dir, &mut stats, changed)?;
+    stats.elapsed = start.elapsed();
+
+    Ok(stats)
   }
-}
 
-/// Template & project comes together to load the template from remote or local
-/// path, loads the `"template.toml"` config file, and initializes git for the
-/// new project.
-pub struct TemplateMeta {
-  #[doc(hidden)]
-  template_options: TemplateOptions,
+  /// Plan the files the template would generate, without writing anything,
+  /// i.e. `--dry-run`.
+  ///
+  /// The template's filters, `[rename]`, `[move]` & `[[fanout]]` clauses
+  /// apply as they do when generating, and [`generate`] executes the same
+  /// actions.
+  ///
+  /// [`generate`]: #method.generate
+  pub fn plan(&self) -> Result<GenerationPlan> {
+    let project_dir = &self.project_info.path;
+    self.validate()?;
 
-  #[doc(hidden)]
-  config: TemplateConfig,
+    let changed = if self.update {
+      Some(self.changed_files(project_dir)?)
+    } else if let Some(glob) = &self.render_only {
+      Some(self.render_only_files(glob)?)
+    } else {
+      None
+    };
 
-  #[doc(hidden)]
-  project_info: ProjectInfo,
-}
+    let mut plan = GenerationPlan {
+      project_dir: project_dir.to_path_buf(),
+      actions: Vec::new(),
+    };
+    self.plan_files(
+      project_dir,
+      changed.as_ref(),
+      &mut |_, action, _, _| {
+        plan.actions.push(action.clone());
+        Ok(())
+      },
+    )?;
 
-impl TemplateMeta {
-  fn new(
-    project_info: &ProjectInfo,
-    template_options: &TemplateOptions,
-  ) -> Self {
-    if let TemplateOptions::Remote(opts) = template_options {
-      // Download template if it's a remote template.
-      TemplateMeta::load_remote(opts).unwrap();
+    Ok(plan)
+  }
+
+  /// Write what was generated into `out`, according to the verbosity.
+  ///
+  /// With `--summary-only`, it's a single line with the project's path and
+  /// how many files were generated & skipped. With `--format json`, it's the
+  /// `stats` as JSON, whatever the verbosity.
+  pub fn write_summary<W: Write>(
+    &self,
+    stats: &GenerationStats,
+    out: &mut W,
+  ) -> io::Result<()> {
+    self.write_summary_of(stats, &self.project_info.path, out)
+  }
+
+  /// Same as [`write_summary`], for a project generated into `location`, e.g.
+  /// an archive.
+  ///
+  /// [`write_summary`]: #method.write_summary
+  fn write_summary_of<W: Write>(
+    &self,
+    stats: &GenerationStats,
+    location: &Path,
+    out: &mut W,
+  ) -> io::Result<()> {
+    if self.json {
+      return writeln!(out, "{}", stats.to_json());
     }
 
-    TemplateMeta {
-      config: TemplateConfig::new(
-        &template_options.path(),
-        &project_info.name_snake_case(),
-      ),
-      template_options: template_options.clone(),
-      project_info: project_info.clone(),
+    match self.verbosity {
+      Verbosity::Silent | Verbosity::Quiet => {}
+      Verbosity::SummaryOnly => writeln!(
+        out,
+        "Generated {} files ({} skipped) in \"{}\"",
+        stats.generated.len(),
+        stats.skipped.len(),
+        location.display()
+      )?,
+      Verbosity::Normal | Verbosity::Verbose => {
+        writeln!(
+          out,
+          "{} {}",
+          emoji::SPARKLE,
+          style("Finished!").bold().green()
+        )?;
+        if self.verbosity.shows_details() {
+          writeln!(
+            out,
+            "Generated {} files in {:.1}s",
+            stats.generated.len(),
+            stats.elapsed.as_secs_f64()
+          )?;
+        }
+        writeln!(
+          out,
+          "{} \"{}\"",
+          style("Project created in: ").bold().white(),
+          style(location.display()).bold().yellow()
+        )?;
+      }
     }
+
+    Ok(())
   }
 
-  /// Clone remote repo into local path.
-  fn load_remote(git_opts: &GitOptions) -> Result<()> {
-    println!(
-      "{} {} {}",
-      emoji::WRENCH,
-      style("Cloning remote repo into ").bold(),
-      style(&git_opts.path().display()).bold().white()
-    );
+  /// Generate the files of the template into `project_dir`, by executing the
+  /// actions planned by [`plan_files`] as they're planned.
+  ///
+  /// The generated & skipped files are recorded in `stats`.
+  ///
+  /// [`plan_files`]: #method.plan_files
+  fn generate_files(
+    &self,
+    project_dir: &Path,
+    stats: &mut GenerationStats,
+    changed: Option<&HashSet<PathBuf>>,
+  ) -> Result<()> {
+    // Files which failed to render or copy with `--keep-going`.
+    let mut failures: Vec<(PathBuf, Error)> = Vec::new();
 
-    match git_opts.clone_repo() {
-      Ok(_) => {}
-      Err(err) => panic!(
-        "{} {} {}",
-        emoji::ERROR,
-        style("Could not create template:").bold().red(),
-        style(err).bold().red()
-      ),
+    self.plan_files(
+      project_dir,
+      changed,
+      &mut |template, action, dest, variables| {
+        template.execute(
+          action,
+          dest,
+          project_dir,
+          variables,
+          stats,
+          &mut failures,
+        )
+      },
+    )?;
+
+    if !failures.is_empty() {
+      return Err(MultiError(failures).into());
     }
+
     Ok(())
   }
-}
 
-impl TemplateMeta {
-  pub(crate) fn variables(&self) -> HashMap<String, String> {
-    match &self.config.variables {
-      Some(var) => var.clone(),
-      None => HashMap::new(),
+  /// Plan the files of the template to generate into `project_dir`, after
+  /// the files of the template it `extends`, which are overridden by this
+  /// template. Nothing is written.
+  ///
+  /// Only the files in `changed`, relative to the template's directory, are
+  /// planned if given, i.e. with `--update`. The templates it `extends`
+  /// aren't planned then, since they weren't diffed, nor with `--only`.
+  ///
+  /// Every action is passed to `visit` along with the template it's planned
+  /// by, its target before any template extension is dropped, and the
+  /// variables of its `[[fanout]]` copy, if any.
+  fn plan_files(
+    &self,
+    project_dir: &Path,
+    changed: Option<&HashSet<PathBuf>>,
+    visit: &mut Visit<'_>,
+  ) -> Result<()> {
+    if let (Some(base), None, None) = (&self.base, changed, &self.only) {
+      base.validate()?;
+      for warning in &base.warnings {
+        error::warn(warning, self.abort_on_warning)?;
+      }
+      base.plan_files(project_dir, None, visit)?;
+    }
+
+    // Template path.
+    let template_dir = &self.template_options.path();
+    // Directory the project is generated from, see `root_dir`.
+    let root_dir = &self.root_dir();
+    let walk_dir = match &self.only {
+      Some(only) => template_dir.join(only),
+      None => template_dir.to_path_buf(),
+    };
+
+    // Files whose extensions look like a mistyped engine extension.
+    let mut suspicious: Vec<(PathBuf, String, &str)> = Vec::new();
+    // Target paths already planned.
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+
+    // Entries which couldn't be read while walking the `template_dir`.
+    let mut walk_errors: Vec<walkdir::Error> = Vec::new();
+
+    // Files & directories left out by the filters.
+    let mut skipped: Vec<(PathBuf, SkipReason)> = Vec::new();
+
+    // Directories generated once per item, instead of as-is.
+    let fanouts = self.fanout_maps()?;
+    let variables = self.variables();
+
+    // Walk the `template_dir`, or its `--only` subdirectory.
+    for entry in WalkDir::new(walk_dir)
+      .into_iter()
+      .filter_entry(|e| match self.skip_reason(e) {
+        Some(reason) => {
+          let path = e.path().strip_prefix(template_dir).unwrap_or(e.path());
+          skipped.push((path.to_path_buf(), reason));
+          false
+        }
+        None => true,
+      })
+    {
+      let entry = match entry {
+        Ok(entry) => entry,
+        Err(err) if self.strict_walk => return Err(Error::from(err)),
+        Err(err) => {
+          log::warn!("{}", err);
+          walk_errors.push(err);
+          continue;
+        }
+      };
+
+      // Strip `template_dir` from entry.
+      let relative_path = entry.path().strip_prefix(template_dir)?;
+      // Path of the entry in the project, before it's renamed.
+      let project_path = entry.path().strip_prefix(root_dir)?;
+
+      // Unchanged files are left as they are when updating.
+      if let Some(changed) = changed {
+        if !entry.path().is_dir() && !changed.contains(relative_path) {
+          continue;
+        }
+      }
+
+      if let Some((src, copies)) = fanouts
+        .iter()
+        .find(|(src, _)| project_path.starts_with(src))
+      {
+        let rest = project_path.strip_prefix(src)?;
+        for (dir, variables) in copies {
+          let dest =
+            self.rename_path(&dir.join(rest), project_dir, &mut seen)?;
+          let kind = if entry.path().is_dir() {
+            // Directories are created along with their files.
+            if fs::read_dir(entry.path())?.next().is_some() {
+              continue;
+            }
+            ActionKind::Create
+          } else {
+            self.file_kind(entry.path())
+          };
+          let (dest, skipped) = self.plan_front_matter(
+            entry.path(),
+            dest,
+            &kind,
+            variables,
+            project_dir,
+            &mut seen,
+          )?;
+          let action = match skipped {
+            Some(reason) => PlannedAction {
+              source: relative_path.to_path_buf(),
+              target: None,
+              kind: ActionKind::Skip(reason),
+            },
+            None => self.action(relative_path, &dest, kind),
+          };
+          visit(self, &action, &dest, Some(variables))?;
+        }
+        continue;
+      }
+      // Append stripped path to `project_dir`.
+      let dest = self.rename_path(project_path, project_dir, &mut seen)?;
+
+      // Symlinks are recreated rather than followed.
+      let symlink = cfg!(unix) && entry.path_is_symlink();
+
+      let kind = if entry.path().is_dir() && !symlink {
+        // Directories are created along with their files, so a directory
+        // whose files were all moved elsewhere isn't created.
+        if fs::read_dir(entry.path())?.next().is_some() {
+          continue;
+        }
+        ActionKind::Create
+      } else if symlink {
+        ActionKind::Link
+      } else {
+        if let Some(ext) = entry.path().extension() {
+          if let Some(known) = Engine::suggest(ext) {
+            let ext = ext.to_string_lossy().to_string();
+            suspicious.push((relative_path.to_path_buf(), ext, known));
+          }
+        }
+        self.file_kind(entry.path())
+      };
+      let (dest, skipped) = self.plan_front_matter(
+        entry.path(),
+        dest,
+        &kind,
+        &variables,
+        project_dir,
+        &mut seen,
+      )?;
+      let action = match skipped {
+        Some(reason) => PlannedAction {
+          source: relative_path.to_path_buf(),
+          target: None,
+          kind: ActionKind::Skip(reason),
+        },
+        None => self.action(relative_path, &dest, kind),
+      };
+      visit(self, &action, &dest, None)?;
     }
+
+    // Files with mistyped template extensions are copied as-is, which is
+    // likely an authoring mistake. Warn about them regardless of verbosity.
+    for (path, ext, known) in &suspicious {
+      error::warn(
+        &format!(
+          "file {:?} has extension {:?} — did you mean .{}?",
+          path, ext, known
+        ),
+        self.abort_on_warning,
+      )?;
+    }
+
+    // Unreadable files & directories are missing from the generated project.
+    for err in &walk_errors {
+      let denied = err
+        .io_error()
+        .map(|e| e.kind() == io::ErrorKind::PermissionDenied)
+        .unwrap_or(false);
+      if denied {
+        let path = err.path().unwrap_or(template_dir);
+        error::warn(
+          &format!(
+            "{:?} was skipped: permission denied. The project is incomplete.",
+            path
+          ),
+          self.abort_on_warning,
+        )?;
+      }
+    }
+
+    for (path, reason) in skipped {
+      let action = PlannedAction {
+        source: path,
+        target: None,
+        kind: ActionKind::Skip(reason),
+      };
+      visit(self, &action, project_dir, None)?;
+    }
+
+    Ok(())
   }
 
-  pub(crate) fn rename_maps(&self) -> HashMap<String, String> {
-    match &self.config.rename {
-      Some(rename) => rename.clone(),
-      None => HashMap::new(),
+  /// Action of kind `kind` generating `relative_path` into `dest`, whose
+  /// template extension is dropped if it's rendered, unless it's kept by the
+  /// `[render]` clause.
+  fn action(
+    &self,
+    relative_path: &Path,
+    dest: &Path,
+    kind: ActionKind,
+  ) -> PlannedAction {
+    let custom = self.has_custom_engine(relative_path);
+    let target = match kind {
+      ActionKind::Render if self.keeps_extension(relative_path) => {
+        dest.to_path_buf()
+      }
+      ActionKind::Render if custom => dest.with_extension(""),
+      ActionKind::Render => Engine::rendered_path(dest),
+      _ => dest.to_path_buf(),
+    };
+
+    PlannedAction {
+      source: relative_path.to_path_buf(),
+      target: Some(target),
+      kind,
     }
   }
 
-  pub(crate) fn get_ignored(&self) -> (bool, Vec<String>) {
-    let filters = match &self.config.filters {
-      Some(f) => f,
-      None => panic!("No Filters."),
+  /// Directory the paths of the filters, renames & generated files are
+  /// relative to: the `--only` subdirectory with `--only-as-root`, else the
+  /// template's directory.
+  fn root_dir(&self) -> PathBuf {
+    let template_dir = self.template_options.path();
+    match &self.only {
+      Some(only) if self.only_as_root => template_dir.join(only),
+      _ => template_dir,
+    }
+  }
+
+  /// Apply the front matter of the file `src` of kind `kind`, planned into
+  /// `dest`, if it's rendered by a built-in engine & has one: its `path`
+  /// replaces `dest`, and its `skip_if` condition skips it.
+  ///
+  /// Returns the file's destination, and why it's skipped if it is.
+  fn plan_front_matter(
+    &self,
+    src: &Path,
+    dest: PathBuf,
+    kind: &ActionKind,
+    variables: &HashMap<String, String>,
+    project_dir: &Path,
+    seen: &mut HashSet<PathBuf>,
+  ) -> Result<(PathBuf, Option<SkipReason>)> {
+    let rendered = *kind == ActionKind::Render
+      && !self.has_custom_engine(src)
+      && !self.cargo_generate;
+    // Only the front matter is read, not the whole file.
+    let front_matter = match front_matter::read_block(src) {
+      Ok(Some(block)) if rendered => front_matter::split(&block).0,
+      _ => None,
     };
-    if filters.include.is_some() {
-      (true, filters.include.clone().unwrap())
+    let front_matter = match front_matter {
+      Some(front_matter) => front_matter,
+      None => return Ok((dest, None)),
+    };
+
+    if let Some(condition) = front_matter.skip_if {
+      let test = format!("{{{{#if {}}}}}true{{{{/if}}}}", condition);
+      let holds =
+        Engine::Handlebars.parse(&test, variables, &self.config.helpers)?;
+      if holds == "true" {
+        return Ok((dest, Some(SkipReason::Condition(condition))));
+      }
+    }
+
+    let path = match front_matter.path {
+      Some(path) => path,
+      None => return Ok((dest, None)),
+    };
+    let path =
+      self
+        .name_engine()
+        .parse(&path, variables, &self.config.helpers)?;
+    // Templates, e.g. remote ones, mustn't write outside of the project.
+    let target = util::normalize(&project_dir.join(&path));
+    if !target.starts_with(project_dir) {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!(
+          "front matter path {:?} of {:?} is outside of the project",
+          path, src
+        ),
+      ));
+    }
+    seen.remove(&dest);
+    if !seen.insert(target.clone()) {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!("rename collision: two source paths map to {:?}", target),
+      ));
+    }
+
+    Ok((target, None))
+  }
+
+  /// Render the file `src` with `engine` without its front matter, if it
+  /// has one, whose `engine` then overrides `engine`.
+  fn render_front_matter(
+    &self,
+    src: &Path,
+    engine: Engine,
+    variables: &HashMap<String, String>,
+  ) -> Result<Option<String>> {
+    // Only files starting with the delimiter are read whole.
+    let mut start = [0; 3];
+    let read =
+      fs::File::open(src).and_then(|mut file| file.read_exact(&mut start));
+    if read.is_err() || &start != b"---" {
+      return Ok(None);
+    }
+    let content = match fs::read_to_string(src) {
+      Ok(content) => content,
+      Err(_) => return Ok(None),
+    };
+    let (front_matter, body) = match front_matter::split(&content) {
+      (Some(front_matter), body) => (front_matter, body),
+      (None, _) => return Ok(None),
+    };
+
+    let engine = front_matter.engine.unwrap_or(engine);
+    let body = if self.config.trim_blocks {
+      engine.trim_blocks(body)
     } else {
-      (true, filters.exclude.clone().unwrap())
+      body.to_string()
+    };
+    let content = engine.parse(&body, variables, &self.config.helpers)?;
+
+    // The file's own pattern overrides the template's.
+    let pattern = match &front_matter.strip_lines_matching {
+      Some(pattern) => Some(Regex::new(pattern)?),
+      None => self.config.strip_lines.clone(),
+    };
+    match pattern {
+      Some(pattern) => Ok(Some(strip_lines(&content, &pattern))),
+      None => Ok(Some(content)),
     }
   }
-}
 
-impl Default for TemplateMeta {
-  fn default() -> TemplateMeta {
-    TemplateMeta {
-      template_options: TemplateOptions::default(),
-      config: TemplateConfig::default(),
-      project_info: ProjectInfo::default(),
+  /// Whether a custom engine is registered for the extension of `path`.
+  fn has_custom_engine(&self, path: &Path) -> bool {
+    path
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .filter(|ext| self.engines.contains_key(*ext))
+      .is_some()
+  }
+
+  /// Whether the file `src` is rendered or copied as-is, see [`substitute`].
+  ///
+  /// [`substitute`]: #method.substitute
+  fn file_kind(&self, src: &Path) -> ActionKind {
+    let custom = self.has_custom_engine(src);
+    let template = !matches!(self.engine_for(src), Engine::None);
+
+    // Every text file of `cargo-generate` templates is rendered.
+    if !self.is_raw(src) && (custom || template || self.cargo_generate) {
+      ActionKind::Render
+    } else {
+      ActionKind::Copy
     }
   }
-}
 
-impl Drop for TemplateMeta {
-  fn drop(&mut self) {
-    // Delete cloned template, if `template_option` is `TemplateOptions::Remote`.
-    match &self.template_options {
-      TemplateOptions::Remote(git_opts) => {
-        // Delete cloned repo.
-        println!(
-          "{} {}",
-          emoji::WRENCH,
-          style("Cleaning up cloned templates...").bold().yellow()
-        );
-        git::delete_local_repo(&git_opts.path()).unwrap();
+  /// Execute a planned `action`, generating its file into `dest` with the
+  /// given `variables`, or the template's.
+  ///
+  /// Generated & skipped files are recorded in `stats`. With `--keep-going`,
+  /// files which fail to generate are recorded in `failures` instead, along
+  /// with their error.
+  fn execute(
+    &self,
+    action: &PlannedAction,
+    dest: &Path,
+    project_dir: &Path,
+    variables: Option<&HashMap<String, String>>,
+    stats: &mut GenerationStats,
+    failures: &mut Vec<(PathBuf, Error)>,
+  ) -> Result<()> {
+    log::debug!(
+      "{:?} {:?} -> {:?}",
+      action.kind,
+      action.source,
+      action.target
+    );
+    let src = self.template_options.path().join(&action.source);
+    let create_parent = || match dest.parent() {
+      Some(parent) => fs::create_dir_all(parent),
+      None => Ok(()),
+    };
+
+    let result = match &action.kind {
+      ActionKind::Skip(reason) => {
+        GenerationEvent::FileSkipped {
+          path: &action.source,
+          reason,
+        }
+        .log();
+        stats.skipped.push((action.source.clone(), reason.clone()));
+        return Ok(());
+      }
+      ActionKind::Create => {
+        fs::create_dir_all(dest)?;
+        GenerationEvent::DirCreated { path: dest }.log();
+        return Ok(());
+      }
+      ActionKind::Link => {
+        create_parent()?;
+        self
+          .symlink(&src, dest, project_dir)
+          .map(|()| dest.to_path_buf())
+      }
+      ActionKind::Copy | ActionKind::Render => {
+        create_parent()?;
+        match variables {
+          Some(variables) => self.substitute_with(&src, dest, variables),
+          None => self.substitute(&src, dest),
+        }
+      }
+    };
+
+    match result {
+      Ok(written) => {
+        if self.preserve_timestamps && action.kind != ActionKind::Link {
+          self.preserve_timestamp(&src, &written)?;
+        }
+        let path = written.as_path();
+        match action.kind {
+          ActionKind::Link => GenerationEvent::LinkCreated { path },
+          ActionKind::Copy => GenerationEvent::FileCopied { path },
+          _ => GenerationEvent::FileRendered { path },
+        }
+        .log();
+        stats.generated.insert(written);
+      }
+      Err(err) if !self.keep_going => return Err(err),
+      // Every failure is reported once the other files are generated.
+      Err(err) => {
+        GenerationEvent::FileFailed {
+          path: &action.source,
+          error: err.to_string(),
+        }
+        .log();
+        failures.push((action.source.clone(), err))
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Ask whether to overwrite the files of `project_dir`, unless it's empty.
+  /// Always overwrite with `--assume-yes` or `--force`, and never without
+  /// being asked to on the command line.
+  fn confirm_overwrite<R: BufRead, W: Write>(
+    &self,
+    project_dir: &Path,
+    input: &mut R,
+    output: &mut W,
+  ) -> Result<bool> {
+    let empty = match fs::read_dir(project_dir) {
+      Ok(mut entries) => entries.next().is_none(),
+      Err(_) => true,
+    };
+    if empty || self.assume_yes || self.force {
+      return Ok(true);
+    }
+    if !self.ask_overwrite {
+      return Ok(false);
+    }
+
+    prompt::confirm(
+      input,
+      output,
+      &format!(
+        "{} is not empty. Overwrite existing files?",
+        project_dir.display()
+      ),
+      false,
+    )
+  }
+
+  /// Show the template, the `project_dir`, the template's variables & how
+  /// many files are about to be generated on `output`, i.e. `--confirm`,
+  /// then ask whether to generate them, reading the answer from `input`.
+  fn confirm_generation<R: BufRead, W: Write>(
+    &self,
+    project_dir: &Path,
+    input: &mut R,
+    output: &mut W,
+  ) -> Result<bool> {
+    let files = self
+      .plan()?
+      .actions
+      .iter()
+      .filter(|action| match action.kind {
+        ActionKind::Copy | ActionKind::Render | ActionKind::Link => true,
+        ActionKind::Create | ActionKind::Skip(_) => false,
+      })
+      .count();
+    let source = match &self.template_options {
+      TemplateOptions::Remote(git) => {
+        format!("{} ({})", git.remote(), git.branch())
+      }
+      options => options.path().display().to_string(),
+    };
+
+    writeln!(output, "{} {}", style("Template:").bold(), source)?;
+    writeln!(
+      output,
+      "{} {}",
+      style("Target:").bold(),
+      project_dir.display()
+    )?;
+    let variables: BTreeMap<_, _> =
+      self.config.variables.iter().flatten().collect();
+    if !variables.is_empty() {
+      writeln!(output, "{}", style("Variables:").bold())?;
+      for (name, value) in variables {
+        writeln!(output, "  {} = {:?}", name, value)?;
+      }
+    }
+    writeln!(output, "{} {}", style("Files:").bold(), files)?;
+
+    prompt::confirm(input, output, "Generate the project?", false)
+  }
+
+  /// Rename path based on the config file i.e. `"template.toml"` file.
+  /// If there's no `[rename]` clause in the template file, the template
+  /// filename is used instead.
+  ///
+  /// # Example
+  ///
+  /// ```toml
+  /// # template.toml
+  /// [rename]
+  /// template = {{ project-name }}
+  /// bin = "scripts"
+  /// ```
+  ///
+  /// `{{ project-name }}` will be resolved to whatever the project's name is
+  /// e.g `"my_project"`. Therefore, `path/to/template/file` will be renamed
+  /// to `path/to/my_project/file`. Same with `bin` which will be renamed
+  /// to `scripts`.
+  ///
+  /// Keys can also be glob patterns e.g `"*.py" = "{{ name }}.py"`, which are
+  /// matched against every component of the path. The first matching entry
+  /// is used, and values are rendered with the template's variables.
+  ///
+  /// Values rendered empty, or referencing an unset variable, e.g.
+  /// `app = "{{app_name}}"` without `app_name`, keep the original name.
+  ///
+  /// Files can also be moved to another directory with the `[move]` clause,
+  /// which maps a file's path in the template to its path in the project.
+  /// Moved files take precedence, i.e. `[rename]` doesn't apply to them.
+  ///
+  /// ```toml
+  /// # template.toml
+  /// [move]
+  /// "scripts/setup.sh" = "bin/setup.sh"
+  /// ```
+  ///
+  /// Renamed paths are recorded in `seen`, so that two source paths renamed
+  /// to the same target path are reported instead of overwriting each other.
+  fn rename_path(
+    &self,
+    relative_path: &Path,
+    project_dir: &Path,
+    seen: &mut HashSet<PathBuf>,
+  ) -> Result<PathBuf> {
+    let target = self.renamed(relative_path, project_dir)?;
+    if !seen.insert(target.clone()) {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!("rename collision: two source paths map to {:?}", target),
+      ));
+    }
+
+    Ok(target)
+  }
+
+  fn renamed(
+    &self,
+    relative_path: &Path,
+    project_dir: &Path,
+  ) -> Result<PathBuf> {
+    let moves = self.move_maps();
+    if let Some((_, target)) = moves
+      .iter()
+      .find(|(src, _)| Path::new(src) == relative_path)
+    {
+      // Templates, e.g. remote ones, mustn't write outside of the project.
+      let target = util::normalize(&project_dir.join(target));
+      if !target.starts_with(project_dir) {
+        return Err(Error::new(
+          ErrorKind::Config,
+          &format!(
+            "move target of {:?} is outside of the project",
+            relative_path
+          ),
+        ));
+      }
+      return Ok(target);
+    }
+
+    let maps = self.rename_maps()?;
+    // Rename every component of `relative_path` with the first matching
+    // pattern in `maps`.
+    let rel_path: PathBuf = relative_path
+      .components()
+      .map(|component| match component {
+        Component::Normal(name) => name
+          .to_str()
+          .and_then(|name| {
+            maps.iter().find(|(pattern, _)| pattern.matches(name))
+          })
+          .map(|(_, value)| OsStr::new(value))
+          .unwrap_or(name),
+        component => component.as_os_str(),
+      })
+      .collect();
+
+    // Append `rel_path` to `project_dir`.
+    Ok(project_dir.join(rel_path))
+  }
+
+  /// Validate the template configuration before generating the project.
+  ///
+  /// Returns an error if more than one `"template.toml"` file is found, e.g
+  /// both `template.toml` & `.project/template.toml`, or if two entries in the
+  /// `[rename]` clause map to the same name given the current variables, e.g:
+  ///
+  /// ```toml
+  /// # template.toml
+  /// [rename]
+  /// src = "{{ project-name }}"
+  /// lib = "{{ project-name }}"
+  /// ```
+  pub fn validate(&self) -> Result<()> {
+    self.validate_target(&self.project_info.path)?;
+
+    // Only one `template.toml` is used, so more than one is ambiguous.
+    let candidates = TemplateConfig::candidates(
+      &self.template_options.path(),
+      &self.config.file_name,
+    );
+    if candidates.len() > 1 {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!(
+          "multiple {} files found in {:?}",
+          self.config.file_name, candidates
+        ),
+      ));
+    }
+
+    // The `--env` must have an overlay of `[variables]`.
+    if let Some(env) = &self.env {
+      if !self.config.overlays.contains_key(env) {
+        let mut envs: Vec<&String> = self.config.overlays.keys().collect();
+        envs.sort();
+        return Err(Error::new(
+          ErrorKind::Config,
+          &format!(
+            "unknown --env {:?}, available environments are {:?}",
+            env, envs
+          ),
+        ));
+      }
+    }
+
+    // Unknown keys of the config file are most likely typos.
+    if self.template_toml_strict && !self.config.unknown_keys.is_empty() {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!(
+          "unknown keys in {}: {}",
+          self.config.file_name,
+          self.config.unknown_keys.join(", ")
+        ),
+      ));
+    }
+
+    // Lines are stripped after rendering, so the pattern is checked first.
+    if let Some(pattern) = &self.config.strip_lines_matching {
+      Regex::new(pattern).map_err(|err| {
+        Error::new(
+          ErrorKind::Config,
+          &format!("invalid strip_lines_matching {:?}: {}", pattern, err),
+        )
+      })?;
+    }
+
+    // `[move]` targets must be relative paths within the project.
+    for (src, target) in self.move_maps() {
+      let inside = Path::new(&target)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+      if !inside {
+        return Err(Error::new(
+          ErrorKind::Config,
+          &format!(
+            "move target {:?} of {:?} is outside of the project",
+            target, src
+          ),
+        ));
+      }
+    }
+
+    // Values of variables must match their `[validate]` pattern.
+    self.config.validate_variables()?;
+
+    // Scripts to `--run` must be in the `[scripts]` clause.
+    let scripts: Vec<&String> =
+      self.config.scripts.iter().flat_map(|s| s.keys()).collect();
+    if let Some(name) = self.run.iter().find(|name| !scripts.contains(name)) {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!(
+          "unknown script {:?}, available scripts are {:?}",
+          name, scripts
+        ),
+      ));
+    }
+
+    // The `--only` subdirectory must be in the template.
+    if let Some(only) = &self.only {
+      let inside = only
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+      if !inside || !self.template_options.path().join(only).is_dir() {
+        return Err(Error::new(
+          ErrorKind::Config,
+          &format!("--only {:?} isn't a directory of the template", only),
+        ));
+      }
+    }
+
+    // Keys are matched against a single file or directory name.
+    let separators: &[char] = &['/', '\\'];
+    let mut keys = self.config.rename.iter().flat_map(|r| r.keys());
+    if let Some(key) = keys.find(|k| k.contains(separators)) {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!(
+          "rename key {:?} contains a path separator, use [move] instead",
+          key
+        ),
+      ));
+    }
+
+    // Names renamed to the same name.
+    let mut renamed: HashMap<&str, &str> = HashMap::new();
+    let maps = self.rename_maps()?;
+    for (pattern, value) in maps.iter().filter(|(p, _)| !is_glob(p.as_str())) {
+      if let Some(other) = renamed.insert(value, pattern.as_str()) {
+        return Err(Error::new(
+          ErrorKind::Config,
+          &format!(
+            "rename collision: {:?} and {:?} both map to {:?}",
+            other,
+            pattern.as_str(),
+            value
+          ),
+        ));
+      }
+    }
+
+    // Files renamed to the same name by a glob pattern e.g `"*.py"`.
+    if maps.iter().any(|(p, _)| is_glob(p.as_str())) {
+      let template_dir = self.root_dir();
+      let mut sources: HashMap<PathBuf, PathBuf> = HashMap::new();
+      for entry in WalkDir::new(&template_dir)
+        .into_iter()
+        .filter_entry(|e| !self.filter_ignore(e))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+      {
+        let relative_path = entry.path().strip_prefix(&template_dir)?;
+        let target = self.renamed(relative_path, Path::new(""))?;
+        if let Some(other) =
+          sources.insert(target.clone(), relative_path.into())
+        {
+          error::warn(
+            &format!(
+              "rename collision: {:?} and {:?} both map to {:?}",
+              other, relative_path, target
+            ),
+            self.abort_on_warning,
+          )?;
+        }
       }
-      TemplateOptions::Local(_) => {}
     }
+
+    Ok(())
+  }
+
+  /// Refuse to generate into `project_dir` if it's the root, home or a system
+  /// directory, unless `--i-know-what-im-doing`.
+  fn validate_target(&self, project_dir: &Path) -> Result<()> {
+    // Overwriting the root or home directory by mistake, e.g. with a name
+    // resolving oddly, would be catastrophic.
+    if !self.i_know_what_im_doing && util::is_sensitive_dir(project_dir) {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!(
+          "refusing to generate into {:?}, pass --i-know-what-im-doing if it's intended",
+          project_dir
+        ),
+      ));
+    }
+
+    Ok(())
+  }
+
+  /// Template substitution is done here, based on the `src` file.
+  ///
+  /// If the `src` file or the template file has extensions supported by [`Engine`],
+  /// template substitution will be done for such file, otherwise the file is just
+  /// copied over to the `dest` or target/project file.
+  ///
+  /// **NOTE:** For files without extension; if you want it to be templated, append
+  /// any extension supported by [`Engine`] e.g `".hbs"` or `".liquid"` as it's extension.
+  /// It will be parsed and the extension will be dropped before writing to the target
+  /// file. The template extension may also come before the file's own extension, i.e.
+  /// both `README.md.hbs` and `README.hbs.md` are rendered into `README.md`.
+  ///
+  /// See [`Engine`] for more details.
+  ///
+  /// [`Engine`]: struct.Engine
+  fn substitute(&self, src: &Path, dest: &Path) -> Result<PathBuf> {
+    self.substitute_with(src, dest, &self.variables())
+  }
+
+  /// Same as [`substitute`], rendering with the given `variables`.
+  ///
+  /// Returns the path of the written file, i.e. `dest` without its template
+  /// extension if it was rendered.
+  ///
+  /// [`substitute`]: #method.substitute
+  fn substitute_with(
+    &self,
+    src: &Path,
+    dest: &Path,
+    variables: &HashMap<String, String>,
+  ) -> Result<PathBuf> {
+    // A kept `template.toml` is copied without its private sections.
+    if src == self.config_file() {
+      fs::write(dest, config::sanitize(&fs::read_to_string(src)?)?)?;
+      return Ok(dest.to_path_buf());
+    }
+
+    // Copy `[raw]` files as-is, keeping their template extension.
+    if self.is_raw(src) {
+      self.copy_file(src, dest)?;
+      return Ok(dest.to_path_buf());
+    }
+
+    // Custom engines registered for the file's extension.
+    let custom = src
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .and_then(|ext| self.engines.get(ext));
+    let keep_extension = self.keeps_extension(src);
+    if let Some(engine) = custom {
+      let rendered = if keep_extension {
+        dest.to_path_buf()
+      } else {
+        dest.with_extension("")
+      };
+      engine.render(src, &rendered, variables)?;
+      self.strip_rendered(&rendered)?;
+      return Ok(rendered);
+    }
+
+    self.trace_render(src, variables);
+    if self.cargo_generate {
+      let rendered = self.substitute_cargo_generate(src, dest, variables)?;
+      self.strip_rendered(&rendered)?;
+      return Ok(rendered);
+    }
+
+    // Files without a template extension are copied over. If you want it
+    // to be templated, append ".hbs" or ".liquid" as extension.
+    let engine = self.engine_for(src);
+    if let Engine::None = engine {
+      self.copy_file(src, dest)?;
+      return Ok(dest.to_path_buf());
+    }
+    if let Some(content) = self.render_front_matter(src, engine, variables)? {
+      let rendered = if keep_extension {
+        dest.to_path_buf()
+      } else {
+        Engine::rendered_path(dest)
+      };
+      fs::write(&rendered, content)?;
+      return Ok(rendered);
+    }
+    let rendered = if keep_extension {
+      engine.render_into(
+        src,
+        dest,
+        variables,
+        &self.config.helpers,
+        self.config.trim_blocks,
+      )?;
+      dest.to_path_buf()
+    } else {
+      engine.render_with(
+        src,
+        dest,
+        variables,
+        &self.config.helpers,
+        self.config.trim_blocks,
+      )?;
+      Engine::rendered_path(dest)
+    };
+
+    // Files are rendered as they're read, so lines are stripped once written.
+    self.strip_rendered(&rendered)?;
+
+    Ok(rendered)
+  }
+
+  /// Drop the lines of the rendered file `path` matching the template's
+  /// `strip_lines_matching`, unless it isn't a text file.
+  fn strip_rendered(&self, path: &Path) -> Result<()> {
+    let pattern = match &self.config.strip_lines {
+      Some(pattern) => pattern,
+      None => return Ok(()),
+    };
+    let content = match String::from_utf8(fs::read(path)?) {
+      Ok(content) => content,
+      Err(_) => return Ok(()),
+    };
+    let stripped = strip_lines(&content, pattern);
+    if stripped != content {
+      fs::write(path, stripped)?;
+    }
+
+    Ok(())
+  }
+
+  /// Log the variables referenced by the file `src` with their values, if
+  /// it's rendered with a built-in engine, i.e. `--trace-renders`.
+  fn trace_render(&self, src: &Path, variables: &HashMap<String, String>) {
+    let rendered =
+      self.cargo_generate || !matches!(self.engine_for(src), Engine::None);
+    if !self.trace_renders || !rendered {
+      return;
+    }
+    // Binary files aren't rendered.
+    let content = match fs::read_to_string(src) {
+      Ok(content) => content,
+      Err(_) => return,
+    };
+
+    let template_dir = self.template_options.path();
+    let path = src.strip_prefix(&template_dir).unwrap_or(src);
+    match engine::referenced(&content, variables) {
+      Ok(found) if found.is_empty() => {
+        log::info!("rendering {:?} without variables", path)
+      }
+      Ok(found) => {
+        let found: Vec<String> = found
+          .iter()
+          .map(|(name, value)| format!("{} = {:?}", name, value))
+          .collect();
+        log::info!("rendering {:?} with {}", path, found.join(", "));
+      }
+      Err(err) => log::warn!("couldn't trace {:?}: {}", path, err),
+    }
+  }
+
+  /// Copy the file `src` into `dest`. Files from the template's
+  /// `large_file_size` are copied in chunks through a temporary file, with
+  /// their progress shown in verbose mode.
+  fn copy_file(&self, src: &Path, dest: &Path) -> Result<()> {
+    let threshold =
+      self.config.large_file_size.unwrap_or(copy::LARGE_FILE_SIZE);
+    if fs::metadata(src)?.len() < threshold {
+      fs::copy(src, dest)?;
+      return Ok(());
+    }
+
+    let show = self.verbosity.shows_details();
+    copy::copy_large(src, dest, &mut |copied, len| {
+      if show {
+        let percent = (copied * 100).checked_div(len).unwrap_or(100);
+        eprint!("\r{} Copying {:?} {:>3}%", emoji::WRENCH, dest, percent);
+      }
+    })?;
+    if show {
+      eprintln!();
+    }
+
+    Ok(())
+  }
+
+  /// Set the modification time of the `written` file to the one of its `src`,
+  /// i.e. `--preserve-timestamps`. Rendered files get `$SOURCE_DATE_EPOCH`
+  /// instead, if it's set.
+  ///
+  /// Platforms whose timestamps can't be set only raise a warning.
+  fn preserve_timestamp(&self, src: &Path, written: &Path) -> Result<()> {
+    let rendered = written.file_name() != src.file_name();
+    let epoch = env::var("SOURCE_DATE_EPOCH")
+      .ok()
+      .and_then(|epoch| epoch.parse().ok());
+    let mtime = match epoch {
+      Some(epoch) if rendered => FileTime::from_unix_time(epoch, 0),
+      _ => FileTime::from_last_modification_time(&fs::metadata(src)?),
+    };
+
+    if let Err(err) = filetime::set_file_mtime(written, mtime) {
+      error::warn(
+        &format!("couldn't preserve the timestamp of {:?}: {}", written, err),
+        self.abort_on_warning,
+      )?;
+    }
+    Ok(())
+  }
+
+  /// Recreate the symlink `src` at `dest`, with its target rendered with the
+  /// template's variables e.g `{{project-name}}.md`.
+  ///
+  /// Targets whose placeholders resolve outside of `project_dir` are refused,
+  /// while targets without placeholders are kept as-is.
+  #[cfg(unix)]
+  fn symlink(&self, src: &Path, dest: &Path, project_dir: &Path) -> Result<()> {
+    let rendered = self.link_target(src, dest, project_dir)?;
+
+    // Replace existing files when overwriting a project.
+    if fs::symlink_metadata(dest).is_ok() {
+      fs::remove_file(dest)?;
+    }
+    std::os::unix::fs::symlink(rendered, dest)?;
+
+    Ok(())
+  }
+
+  /// Target of the symlink `src` recreated at `dest`, see [`symlink`].
+  ///
+  /// [`symlink`]: #method.symlink
+  fn link_target(
+    &self,
+    src: &Path,
+    dest: &Path,
+    project_dir: &Path,
+  ) -> Result<String> {
+    let link = fs::read_link(src)?;
+    let raw = link.to_string_lossy();
+    let rendered = self.name_engine().parse(
+      &raw,
+      &self.variables(),
+      &self.config.helpers,
+    )?;
+
+    if rendered != raw {
+      let parent = dest.parent().unwrap_or(project_dir);
+      if !util::normalize(&parent.join(&rendered)).starts_with(project_dir) {
+        return Err(Error::new(
+          ErrorKind::Error,
+          &format!(
+            "symlink {:?} points to {:?}, outside of the project directory",
+            src, rendered
+          ),
+        ));
+      }
+    }
+
+    Ok(rendered)
+  }
+
+  #[cfg(not(unix))]
+  fn symlink(&self, src: &Path, dest: &Path, _: &Path) -> Result<()> {
+    self.substitute(src, dest).map(|_| ())
+  }
+
+  /// Message of the `--git-init` commit, from `--commit-message` or the
+  /// template's `initial_commit_message`, rendered with its variables.
+  fn commit_message(&self) -> Result<String> {
+    let message = self
+      .commit_message
+      .as_ref()
+      .or_else(|| self.config.initial_commit_message.as_ref());
+
+    match message {
+      Some(message) => Engine::Handlebars.parse(
+        message,
+        &self.variables(),
+        &self.config.helpers,
+      ),
+      None => Ok(DEFAULT_COMMIT_MESSAGE.to_string()),
+    }
+  }
+
+  /// Returns true if `src` matches a pattern in the `[raw]` clause.
+  fn is_raw(&self, src: &Path) -> bool {
+    match &self.config.raw {
+      Some(raw) => self.matches_any(src, &raw.files),
+      None => false,
+    }
+  }
+
+  /// Engine `src` is rendered with: the one of its template extension, else
+  /// the `default_engine` if it matches an `include` pattern of the
+  /// `[render]` clause.
+  fn engine_for(&self, src: &Path) -> Engine {
+    let forced = match &self.config.render {
+      Some(render) => self.matches_any(src, &render.include),
+      None => false,
+    };
+    match Engine::from_path(src) {
+      Engine::None if forced => self.name_engine(),
+      engine => engine,
+    }
+  }
+
+  /// Returns true if `src` matches a `keep_extension` pattern in the
+  /// `[render]` clause.
+  fn keeps_extension(&self, src: &Path) -> bool {
+    match &self.config.render {
+      Some(render) => self.matches_any(src, &render.keep_extension),
+      None => false,
+    }
+  }
+
+  /// Returns true if `src`, relative to the template's base directory,
+  /// matches one of the glob `patterns`.
+  fn matches_any(&self, src: &Path, patterns: &[String]) -> bool {
+    let template_dir = self.template_options.path();
+    let relative_path = src.strip_prefix(&template_dir).unwrap_or(src);
+
+    patterns.iter().any(|pattern| {
+      GlobPattern::new(pattern)
+        .map(|p| p.matches_path(relative_path))
+        .unwrap_or(false)
+    })
+  }
+
+  /// Template substitution for [`cargo-generate`] templates.
+  ///
+  /// Every text file is rendered with Handlebars after its Tera-style filters
+  /// are converted (see [`compat`]). Files with a template extension keep
+  /// their usual behaviour, while binary files are copied over as-is.
+  ///
+  /// [`cargo-generate`]: https://github.com/cargo-generate/cargo-generate
+  /// [`compat`]: compat/index.html
+  fn substitute_cargo_generate(
+    &self,
+    src: &Path,
+    dest: &Path,
+    variables: &HashMap<String, String>,
+  ) -> Result<PathBuf> {
+    let content = match fs::read_to_string(src) {
+      Ok(content) => content,
+      Err(_) => {
+        // Not a text file.
+        fs::copy(src, dest)?;
+        return Ok(dest.to_path_buf());
+      }
+    };
+    let content = compat::cargo_generate(&content)?;
+
+    let (engine, dest) = match Engine::from_path(src) {
+      Engine::None => (Engine::Handlebars, dest.to_path_buf()),
+      engine if self.keeps_extension(src) => (engine, dest.to_path_buf()),
+      // Get rid of ".hbs" or ".liquid".
+      engine => (engine, Engine::rendered_path(dest)),
+    };
+    let content = if self.config.trim_blocks {
+      engine.trim_blocks(&content)
+    } else {
+      content
+    };
+
+    // `cargo-generate` built-in placeholders, e.g. its kebab-cased
+    // `project-name`, unless it's defined by the user.
+    let mut variables = variables.clone();
+    let builtin = self.project_info.name_snake_case();
+    let defined = variables
+      .get("project-name")
+      .filter(|name| **name != builtin);
+    if defined.is_none() {
+      variables.insert(
+        "project-name".to_string(),
+        self.project_info.name_kebab_case(),
+      );
+    }
+    variables
+      .entry("crate_name".to_string())
+      .or_insert_with(|| self.project_info.name_snake_case());
+    if !variables.contains_key("authors") {
+      variables.insert("authors".to_string(), authors::get_authors()?);
+    }
+
+    fs::write(
+      &dest,
+      engine.parse(&content, &variables, &self.config.helpers)?,
+    )?;
+    Ok(dest)
+  }
+
+  /// Run the script `name` of the `[scripts]` clause with the shell, in the
+  /// generated `project_dir`, once its placeholders are rendered.
+  ///
+  /// Values aren't HTML-escaped, but quoted for the shell, so that they're
+  /// passed as a single word whatever they contain. `{{args}}` is rendered
+  /// into the `--template-arg` values, each quoted for the shell.
+  fn run_script(&self, name: &str, project_dir: &Path) -> Result<()> {
+    let script = &self.config.scripts.as_ref().expect("validated")[name];
+    let mut variables: HashMap<String, String> = self
+      .variables()
+      .into_iter()
+      .map(|(key, value)| (key, util::shell_quote(&value)))
+      .collect();
+    let args: Vec<String> = self
+      .template_args
+      .iter()
+      .map(|arg| util::shell_quote(arg))
+      .collect();
+    variables.insert("args".to_string(), args.join(" "));
+    let script =
+      Engine::parse_unescaped(script, &variables, &self.config.helpers)?;
+
+    let (shell, flag) = if cfg!(windows) {
+      ("cmd", "/C")
+    } else {
+      ("sh", "-c")
+    };
+    let status = process::Command::new(shell)
+      .args([flag, &script])
+      .current_dir(project_dir)
+      .status()?;
+    if !status.success() {
+      return Err(Error::new(
+        ErrorKind::Error,
+        &format!("script {:?} failed: {}", name, status),
+      ));
+    }
+
+    Ok(())
+  }
+
+  /// The template's `next_steps`, rendered with its variables, printed once
+  /// the project is generated e.g. `make setup`.
+  ///
+  /// Values aren't HTML-escaped, as for `[scripts]`.
+  pub fn next_steps(&self) -> Result<Vec<String>> {
+    let steps = match &self.config.next_steps {
+      Some(steps) => steps.steps(),
+      None => return Ok(Vec::new()),
+    };
+    let variables = self.variables();
+    steps
+      .into_iter()
+      .map(|step| {
+        Engine::parse_unescaped(step, &variables, &self.config.helpers)
+      })
+      .collect()
+  }
+
+  /// Check the variables of the `vars_file` against the template's
+  /// `[prompts]` & `[validate]` sections, without generating the project,
+  /// i.e. `validate-vars`.
+  ///
+  /// The file is a TOML table of values, or a JSON object if its extension
+  /// is `.json`. Numbers & booleans are checked as strings, e.g. `8080` as
+  /// `"8080"`. Returns an error listing every violation, if any.
+  pub fn validate_vars(&self, vars_file: &Path) -> Result<()> {
+    let content = fs::read_to_string(vars_file)?;
+    let is_json = vars_file.extension() == Some("json".as_ref());
+    let invalid = |err: &dyn std::fmt::Display| {
+      Error::new(
+        ErrorKind::ParseError,
+        &format!("invalid vars file {:?}: {}", vars_file, err),
+      )
+    };
+    let values: HashMap<String, serde_json::Value> = if is_json {
+      serde_json::from_str(&content).map_err(|err| invalid(&err))?
+    } else {
+      toml::from_str(&content).map_err(|err| invalid(&err))?
+    };
+    let mut vars = HashMap::new();
+    for (name, value) in values {
+      let value = match value {
+        serde_json::Value::String(value) => value,
+        serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+          value.to_string()
+        }
+        _ => {
+          return Err(invalid(&format!(
+            "{:?} isn't a string, number or boolean",
+            name
+          )))
+        }
+      };
+      vars.insert(name, value);
+    }
+
+    let violations = self.config.check_variables(&vars)?;
+    if violations.is_empty() {
+      return Ok(());
+    }
+    Err(Error::new(
+      ErrorKind::Config,
+      &format!(
+        "{:?} has {} invalid variable(s):\n  {}",
+        vars_file,
+        violations.len(),
+        violations.join("\n  ")
+      ),
+    ))
+  }
+
+  /// Returns an error unless the template's git tree, or the commit it's at,
+  /// has the `expected` full 40-character SHA, and the template has no
+  /// uncommitted changes.
+  fn verify_tree_sha(&self, expected: &str) -> Result<()> {
+    // Abbreviated SHAs are short enough to be forged.
+    let expected = expected.to_lowercase();
+    if expected.len() != 40 || !expected.chars().all(|c| c.is_ascii_hexdigit())
+    {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!(
+          "--expect-tree-sha {:?} isn't a full 40-character SHA",
+          expected
+        ),
+      ));
+    }
+
+    let template_dir = self.template_options.path();
+    let (tree, commit) = git::head_shas(&template_dir)?;
+    if git::is_dirty(&template_dir)? {
+      return Err(Error::new(
+        ErrorKind::GitError,
+        &format!(
+          "Template has uncommitted changes, so it doesn't match its tree {}",
+          tree
+        ),
+      ));
+    }
+    if expected == tree || expected == commit {
+      return Ok(());
+    }
+
+    Err(Error::new(
+      ErrorKind::GitError,
+      &format!(
+        "Template tree is {} at commit {}, but {} was expected",
+        tree, commit, expected
+      ),
+    ))
+  }
+
+  /// Template files matching `glob`, relative to the template's directory,
+  /// i.e. `render-only`. Returns an error if there's none.
+  fn render_only_files(&self, glob: &str) -> Result<HashSet<PathBuf>> {
+    let template_dir = self.template_options.path();
+    let patterns = [glob.to_string()];
+    let mut files = HashSet::new();
+    for entry in WalkDir::new(&template_dir) {
+      let entry = entry?;
+      if entry.file_type().is_file()
+        && self.matches_any(entry.path(), &patterns)
+      {
+        files.insert(entry.path().strip_prefix(&template_dir)?.to_path_buf());
+      }
+    }
+
+    if files.is_empty() {
+      return Err(Error::new(
+        ErrorKind::NotFound,
+        &format!("render-only {:?} matches no file of the template", glob),
+      ));
+    }
+    Ok(files)
+  }
+
+  /// Template files changed between the commit the project was generated
+  /// from, i.e. `--since` or the one in its lock file, and the template's
+  /// current commit.
+  fn changed_files(&self, project_dir: &Path) -> Result<HashSet<PathBuf>> {
+    let since = match &self.since {
+      Some(since) => since.clone(),
+      None => TemplateLock::read(project_dir)?.commit,
+    };
+    git::changed_files(&self.template_options.path(), &since)
+  }
+
+  /// Returns the commit the remote template resolved to. Local templates
+  /// & archives can't be locked.
+  fn resolve_lock(&self) -> Result<TemplateLock> {
+    match &self.template_options {
+      TemplateOptions::Remote(git_opts) => Ok(TemplateLock {
+        remote: git_opts.remote().to_string(),
+        commit: git_opts.resolved_commit()?,
+      }),
+      TemplateOptions::Local(path) => Err(Error::new(
+        ErrorKind::Error,
+        &format!(
+          "Local template {} can't be locked, only remote templates can.",
+          path.display()
+        ),
+      )),
+      TemplateOptions::Archive(_) => Err(Error::new(
+        ErrorKind::Error,
+        "Template archives can't be locked, only remote templates can.",
+      )),
+    }
+  }
+
+  fn filter_ignore(&self, entry: &DirEntry) -> bool {
+    self.skip_reason(entry).is_some()
+  }
+
+  /// Why `entry` is left out of the generated project, if it is.
+  ///
+  /// Patterns of the `[filters]` clause are globs matched against the file
+  /// or directory name, e.g `"*.log"`, or against its path relative to the
+  /// template when they contain a `/`, e.g `"{{project-name}}-secrets/*"`.
+  fn skip_reason(&self, entry: &DirEntry) -> Option<SkipReason> {
+    // Git history of cloned templates & of the repositories nested in them,
+    // whose `.git` is a directory, or a file for submodules.
+    if entry.depth() > 0 && entry.file_name() == ".git" {
+      return Some(SkipReason::Git);
+    }
+
+    // The `template.toml` file drives the generation, unless it's kept.
+    if !self.keep_template_toml
+      && !self.config.keep_template_toml
+      && entry.path() == self.config_file()
+    {
+      return Some(SkipReason::TemplateFile);
+    }
+
+    // Filterignored/included files here...
+    let name = entry.file_name().to_str()?;
+    let path = entry.path().strip_prefix(self.root_dir()).ok()?.to_str()?;
+    let has_config = self.config_file().exists();
+    // Wildcards don't match the leading dot of dotfiles, e.g. `*.example`
+    // leaves `.env.example`, which must be excluded explicitly.
+    let options = MatchOptions {
+      require_literal_leading_dot: true,
+      ..MatchOptions::new()
+    };
+    let pattern = self.filter_patterns().into_iter().find(|pattern| {
+      if !has_config && config::DEFAULT_EXCLUDES.contains(&pattern.as_str()) {
+        return entry.file_type().is_dir() && pattern == name;
+      }
+      let candidate = if pattern.contains('/') { path } else { name };
+      // Invalid patterns are matched literally.
+      GlobPattern::new(pattern)
+        .map(|p| p.matches_with(candidate, options))
+        .unwrap_or_else(|_| pattern == candidate)
+    })?;
+
+    if has_config {
+      Some(SkipReason::ConfigExclude(pattern))
+    } else {
+      Some(SkipReason::DefaultExclude(pattern))
+    }
+  }
+}
+
+impl From<&Arguments> for Template {
+  fn from(args: &Arguments) -> Template {
+    let config_name = args.config_name.as_deref();
+    let mut template = TemplateMeta::new(
+      &args.project,
+      &args.template,
+      config_name.unwrap_or(config::TEMPLATE_FILE),
+      args.bare,
+      args.verbosity,
+    );
+    template.cargo_generate = args.cargo_generate;
+    template.abort_on_warning = args.abort_on_warning;
+    template.write_lock = args.write_lock;
+    template.locked = args.locked;
+    template.strict_walk = args.strict_walk;
+    template.keep_going = args.keep_going;
+    template.verbosity = args.verbosity;
+    template.json = args.json;
+    template.update = args.update;
+    template.since = args.since.clone();
+    template.render_only = args.render_only.clone();
+    template.env = args.env.clone();
+    template.preserve_timestamps = args.preserve_timestamps;
+    template.expect_tree_sha = args.expect_tree_sha.clone();
+    template.run = args.run.clone();
+    template.template_args = args.template_args.clone();
+    template.only = args.only.clone();
+    template.only_as_root = args.only_as_root;
+    template.trace_renders = args.trace_renders;
+    template.assume_yes = args.assume_yes;
+    // Answers piped into `--interactive` prompts aren't followed by one.
+    template.confirm =
+      args.confirm || (args.interactive && io::stdin().is_terminal());
+    template.keep_template_toml = args.keep_template_toml;
+    template.rename_case = args.rename_case;
+    template.git_init = args.git_init;
+    template.commit_message = args.commit_message.clone();
+    template.force = args.force;
+    template.ask_overwrite = true;
+    template.i_know_what_im_doing = args.i_know_what_im_doing;
+    template.template_toml_strict = args.template_toml_strict;
+
+    // The user's defaults override the template's.
+    let defaults = env::current_dir()
+      .map_err(Error::from)
+      .and_then(|dir| TemplateConfig::load_defaults(&dir))
+      .unwrap_or_else(|err| {
+        panic!(
+          "{} {} {}",
+          emoji::ERROR,
+          style("Could not load defaults:").bold().red(),
+          style(err).bold().red()
+        )
+      });
+    template.config.apply_defaults(defaults);
+
+    if let Some(env_file) = &args.env_file {
+      template
+        .config
+        .merge_env_file(env_file, args.env_lowercase)
+        .unwrap_or_else(|err| {
+          panic!(
+            "{} {} {}",
+            emoji::ERROR,
+            style("Could not load env file:").bold().red(),
+            style(err).bold().red()
+          )
+        });
+    }
+
+    if !args.inherited.is_empty() {
+      template.config.apply_defaults(args.inherited.clone());
+    }
+
+    // Environment variables are imported without enumerating each with `-D`,
+    // only with the user's prefix since they may hold secrets.
+    let env_prefix = args
+      .env_prefix
+      .clone()
+      .or_else(|| Defaults::load(&defaults::config_dir()).ok()?.env_prefix);
+    if let Some(prefix) = env_prefix.filter(|prefix| !prefix.is_empty()) {
+      let vars = env::vars_os().filter_map(|(key, value)| {
+        Some((key.into_string().ok()?, value.into_string().ok()?))
+      });
+      let imported = TemplateConfig::prefixed_env_vars(&prefix, vars);
+      template.config.apply_defaults(imported);
+    }
+
+    // The environment's overlay, picked explicitly, overrides the user's
+    // defaults. It's checked once validated.
+    if let Some(env) = &args.env {
+      template.config.apply_overlay(env);
+    }
+
+    // Variables given on the command line override every other value.
+    template.config.apply_defaults(args.defines.clone());
+
+    if let Some(filters) = &mut template.config.filters {
+      filters.merge(&config::Filters {
+        include: None,
+        exclude: Some(args.excludes.clone()),
+      });
+    }
+
+    // Prompts default to their value in `template.toml`.
+    if args.interactive && !args.assume_yes {
+      let stdin = io::stdin();
+      template
+        .config
+        .prompt(&mut stdin.lock(), &mut io::stdout())
+        .unwrap_or_else(|err| {
+          panic!(
+            "{} {} {}",
+            emoji::ERROR,
+            style("Could not read prompt:").bold().red(),
+            style(err).bold().red()
+          )
+        });
+    }
+    template.inherit();
+
+    Template { template }
+  }
+}
+
+impl From<&Cli<'_>> for Template {
+  fn from(cli: &Cli) -> Template {
+    Self::from(&cli.args)
+  }
+}
+
+impl Default for Template {
+  fn default() -> Template {
+    Template {
+      template: TemplateMeta::default(),
+    }
+  }
+}
+
+impl Deref for Template {
+  type Target = TemplateMeta;
+  fn deref(&self) -> &Self::Target {
+    &self.template
+  }
+}
+
+impl fmt::Debug for Template {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Template")
+      .field("option", &self.template_options)
+      .field("project", &self.project_info)
+      .field("config", &self.config)
+      .finish()
+  }
+}
+
+/// Template & project comes together to load the template from remote or local
+/// path, loads the `"template.toml"` config file, and initializes git for the
+/// new project.
+pub struct TemplateMeta {
+  #[doc(hidden)]
+  template_options: TemplateOptions,
+
+  #[doc(hidden)]
+  config: TemplateConfig,
+
+  #[doc(hidden)]
+  project_info: ProjectInfo,
+
+  /// Render the template as a `cargo-generate` template.
+  #[doc(hidden)]
+  cargo_generate: bool,
+
+  /// Treat warnings as errors.
+  #[doc(hidden)]
+  abort_on_warning: bool,
+
+  /// Warnings raised while loading the template, reported on `generate`.
+  #[doc(hidden)]
+  warnings: Vec<String>,
+
+  /// Write the commit a remote template resolved to into `project.lock`.
+  #[doc(hidden)]
+  write_lock: bool,
+
+  /// Refuse to generate unless the remote template matches `project.lock`.
+  #[doc(hidden)]
+  locked: bool,
+
+  /// Fail instead of skipping files & directories that can't be read.
+  #[doc(hidden)]
+  strict_walk: bool,
+
+  /// Skip files which fail to render or copy & report them once done.
+  #[doc(hidden)]
+  keep_going: bool,
+
+  /// How much is reported once the project is generated.
+  #[doc(hidden)]
+  verbosity: Verbosity,
+
+  /// Report the generated project as JSON.
+  #[doc(hidden)]
+  json: bool,
+
+  /// Only regenerate the template files changed since the project was
+  /// generated.
+  #[doc(hidden)]
+  update: bool,
+
+  /// Commit the project was generated from, instead of its lock file's.
+  #[doc(hidden)]
+  since: Option<String>,
+
+  /// Glob of the template files re-rendered into the existing project, the
+  /// only ones generated.
+  #[doc(hidden)]
+  render_only: Option<String>,
+
+  /// Environment whose overlay of `[variables]` is merged, e.g. `prod`.
+  #[doc(hidden)]
+  env: Option<String>,
+
+  /// Keep the modification time of the template's files.
+  #[doc(hidden)]
+  preserve_timestamps: bool,
+
+  /// SHA the template's tree, or commit, must have.
+  #[doc(hidden)]
+  expect_tree_sha: Option<String>,
+
+  /// Names of the `[scripts]` to run once the project is generated.
+  #[doc(hidden)]
+  run: Vec<String>,
+
+  /// Arguments passed to the `[scripts]` as `{{args}}`.
+  #[doc(hidden)]
+  template_args: Vec<String>,
+
+  /// Subdirectory of the template, the only one generated.
+  #[doc(hidden)]
+  only: Option<PathBuf>,
+
+  /// Generate the `only` subdirectory as the project's root.
+  #[doc(hidden)]
+  only_as_root: bool,
+
+  /// Log the variables referenced by each rendered file.
+  #[doc(hidden)]
+  trace_renders: bool,
+
+  /// Answer every prompt with its default & overwrite without asking.
+  #[doc(hidden)]
+  assume_yes: bool,
+
+  /// Ask to confirm a summary of the project before generating it.
+  #[doc(hidden)]
+  confirm: bool,
+
+  /// Copy the `template.toml` file into the generated project.
+  #[doc(hidden)]
+  keep_template_toml: bool,
+
+  /// Case of the components renamed by `[rename]`.
+  #[doc(hidden)]
+  rename_case: Option<Case>,
+
+  /// Initialize a git repository in the project & commit its files.
+  #[doc(hidden)]
+  git_init: bool,
+
+  /// Message of the `--git-init` commit, overriding the template's.
+  #[doc(hidden)]
+  commit_message: Option<String>,
+
+  /// Overwrite the project directory without asking.
+  #[doc(hidden)]
+  force: bool,
+
+  /// Ask on the command line whether to overwrite a project directory which
+  /// isn't empty, instead of refusing to.
+  #[doc(hidden)]
+  ask_overwrite: bool,
+
+  /// Generate into the root, home or a system directory.
+  #[doc(hidden)]
+  i_know_what_im_doing: bool,
+
+  /// Reject the unknown keys of the config file, instead of ignoring them.
+  #[doc(hidden)]
+  template_toml_strict: bool,
+
+  /// Custom templating engines by file extension, shared with the templates
+  /// it `extends`.
+  #[doc(hidden)]
+  engines: HashMap<String, SharedEngine>,
+
+  /// Template this template `extends`, generated before it.
+  #[doc(hidden)]
+  base: Option<Box<Template>>,
+}
+
+impl TemplateMeta {
+  fn new(
+    project_info: &ProjectInfo,
+    template_options: &TemplateOptions,
+    config_name: &str,
+    bare: bool,
+    verbosity: Verbosity,
+  ) -> Self {
+    let visited = &mut Vec::new();
+    Self::load(
+      project_info,
+      template_options,
+      config_name,
+      bare,
+      verbosity,
+      visited,
+    )
+    .unwrap_or_else(|err| {
+      panic!(
+        "{} {} {}",
+        emoji::ERROR,
+        style("Could not load template:").bold().red(),
+        style(err).bold().red()
+      )
+    })
+  }
+
+  /// Load the template along with the templates it `extends`, whose config
+  /// file is named `config_name`. `visited` holds the directories of the
+  /// templates extending it, to detect cycles.
+  ///
+  /// A `bare` template's config file is ignored, so only the default filters
+  /// & built-in variables apply. Progress is reported according to the
+  /// `verbosity`.
+  fn load(
+    project_info: &ProjectInfo,
+    template_options: &TemplateOptions,
+    config_name: &str,
+    bare: bool,
+    verbosity: Verbosity,
+    visited: &mut Vec<PathBuf>,
+  ) -> Result<Self> {
+    let mut warnings = Vec::new();
+
+    if let TemplateOptions::Remote(opts) = template_options {
+      // Download template if it's a remote template.
+      TemplateMeta::load_remote(opts, verbosity).unwrap();
+    }
+
+    let template_dir = template_options.path();
+    let canonical = template_dir
+      .canonicalize()
+      .unwrap_or_else(|_| template_dir.clone());
+    if visited.contains(&canonical) {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!("template inheritance cycle at {:?}", template_dir),
+      ));
+    }
+    visited.push(canonical);
+
+    let read_config = || {
+      if bare {
+        TemplateConfig {
+          dir: template_dir.clone(),
+          file_name: config_name.to_string(),
+          ..TemplateConfig::default()
+        }
+      } else {
+        TemplateConfig::new(
+          &template_dir,
+          &project_info.name_snake_case(),
+          config_name,
+          verbosity,
+        )
+      }
+    };
+    let mut config = read_config();
+
+    // The branch a remote template declares in its `[git]` clause is only
+    // known once its default branch is cloned. Every remote branch is
+    // fetched along, so the declared one is checked out from the clone, and
+    // its own config is read instead.
+    let default_branch = config
+      .git
+      .as_ref()
+      .and_then(|git| git.default_branch.as_ref());
+    if let (TemplateOptions::Remote(opts), Some(branch)) =
+      (template_options, default_branch)
+    {
+      if !opts.has_branch() && opts.checkout_branch(branch)? {
+        config = read_config();
+      }
+    }
+    warnings.append(&mut config.warnings);
+
+    // Built-in placeholders are available even without a `template.toml`.
+    let variables = config.variables.get_or_insert_with(HashMap::new);
+    for (key, value) in
+      parser::builtin_variables(&project_info.name_snake_case())
+    {
+      variables.entry(key).or_insert(value);
+    }
+
+    let base = match &config.extends {
+      Some(extends) => {
+        // Relative to the config file, which may be nested in the template.
+        let local = config.dir.join(extends);
+        let options = if local.exists() {
+          TemplateOptions::Local(local.canonicalize()?)
+        } else {
+          TemplateOptions::new(extends, None)?
+        };
+        Some(Box::new(Template {
+          template: Self::load(
+            project_info,
+            &options,
+            config_name,
+            bare,
+            verbosity,
+            visited,
+          )?,
+        }))
+      }
+      None => None,
+    };
+
+    // Variables & filters of the base template apply, unless overridden.
+    if let Some(base) = &base {
+      let variables = config.variables.get_or_insert_with(HashMap::new);
+      for (key, value) in base.variables() {
+        variables.entry(key).or_insert(value);
+      }
+      if let (Some(filters), Some(base)) =
+        (&mut config.filters, &base.config.filters)
+      {
+        filters.merge(base);
+      }
+    }
+
+    let mut template = TemplateMeta {
+      config,
+      template_options: template_options.clone(),
+      project_info: project_info.clone(),
+      cargo_generate: false,
+      abort_on_warning: false,
+      warnings,
+      write_lock: false,
+      locked: false,
+      strict_walk: false,
+      keep_going: false,
+      verbosity: Verbosity::Normal,
+      json: false,
+      update: false,
+      render_only: None,
+      env: None,
+      since: None,
+      preserve_timestamps: false,
+      expect_tree_sha: None,
+      run: Vec::new(),
+      template_args: Vec::new(),
+      only: None,
+      only_as_root: false,
+      trace_renders: false,
+      assume_yes: false,
+      confirm: false,
+      keep_template_toml: false,
+      rename_case: None,
+      git_init: false,
+      commit_message: None,
+      force: false,
+      ask_overwrite: false,
+      i_know_what_im_doing: false,
+      template_toml_strict: false,
+      engines: HashMap::new(),
+      base,
+    };
+    template.inherit();
+
+    Ok(template)
+  }
+
+  /// Pass the variables, filters, custom engines & run options of the
+  /// template down to the templates it `extends`, overriding theirs.
+  fn inherit(&mut self) {
+    let variables = self.variables();
+    let filters = self.config.filters.clone();
+    if let Some(base) = &mut self.base {
+      let base = &mut base.template;
+      base
+        .config
+        .variables
+        .get_or_insert_with(HashMap::new)
+        .extend(variables);
+      if let (Some(base), Some(filters)) = (&mut base.config.filters, &filters)
+      {
+        base.merge(filters);
+      }
+      base.engines.extend(self.engines.clone());
+      base.cargo_generate = self.cargo_generate;
+      base.abort_on_warning = self.abort_on_warning;
+      base.strict_walk = self.strict_walk;
+      base.keep_going = self.keep_going;
+      base.verbosity = self.verbosity;
+      base.preserve_timestamps = self.preserve_timestamps;
+      base.trace_renders = self.trace_renders;
+      base.assume_yes = self.assume_yes;
+      base.rename_case = self.rename_case;
+      base.force = self.force;
+      base.template_toml_strict = self.template_toml_strict;
+      base.inherit();
+    }
+  }
+
+  /// Register the custom `engine` for `ext`, see
+  /// [`Template::register_engine`].
+  ///
+  /// [`Template::register_engine`]: struct.Template.html#method.register_engine
+  fn register_engine(&mut self, ext: &str, engine: SharedEngine) {
+    if let Some(base) = &mut self.base {
+      base.template.register_engine(ext, Rc::clone(&engine));
+    }
+    self.engines.insert(ext.to_string(), engine);
+  }
+
+  /// Clone remote repo into local path, unless it's already in the cache
+  /// and the cached version should be used. Progress is reported according
+  /// to the `verbosity`.
+  pub(crate) fn load_remote(
+    git_opts: &GitOptions,
+    verbosity: Verbosity,
+  ) -> Result<()> {
+    let report = verbosity.shows_progress() && !logger::is_json();
+    let path = git_opts.path();
+
+    if let Some(age) = git_opts.cache_age() {
+      let use_cache = match git_opts.cache_policy() {
+        CachePolicy::Use => true,
+        CachePolicy::Refresh => false,
+        CachePolicy::Ask => {
+          let stdin = io::stdin();
+          prompt::confirm(
+            &mut stdin.lock(),
+            &mut io::stdout(),
+            &format!(
+              "Template found in cache (cloned {} ago). Use cached version?",
+              humantime::format_duration(coarse_duration(age))
+            ),
+            true,
+          )?
+        }
+      };
+
+      if use_cache {
+        if report {
+          eprintln!(
+            "{} {} {}",
+            emoji::WRENCH,
+            style("Using cached template in ").bold(),
+            style(&path.display()).bold().white()
+          );
+        }
+        return Ok(());
+      }
+
+      // Replace the cached template with a fresh clone.
+      fs::remove_dir_all(&path)?;
+    }
+
+    if report {
+      eprintln!(
+        "{} {} {}",
+        emoji::WRENCH,
+        style("Cloning remote repo into ").bold(),
+        style(&path.display()).bold().white()
+      );
+    }
+
+    match git_opts.clone_repo() {
+      Ok(_) => {}
+      Err(err) => panic!(
+        "{} {} {}",
+        emoji::ERROR,
+        style("Could not create template:").bold().red(),
+        style(err).bold().red()
+      ),
+    }
+    Ok(())
+  }
+}
+
+/// Truncate `duration` to its largest unit for display e.g `2h` instead of `2h 3m 4s`.
+fn coarse_duration(duration: Duration) -> Duration {
+  let secs = duration.as_secs();
+  let unit = match secs {
+    s if s >= 86_400 => 86_400,
+    s if s >= 3_600 => 3_600,
+    s if s >= 60 => 60,
+    _ => 1,
+  };
+  Duration::from_secs(secs / unit * unit)
+}
+
+impl TemplateMeta {
+  pub(crate) fn variables(&self) -> HashMap<String, String> {
+    match &self.config.variables {
+      Some(var) => var.clone(),
+      None => HashMap::new(),
+    }
+  }
+
+  /// Path of the template's config file, e.g. its `template.toml`.
+  pub(crate) fn config_file(&self) -> PathBuf {
+    self.config.dir.join(&self.config.file_name)
+  }
+
+  /// Engine rendering the paths of the template, e.g. its `[rename]` values,
+  /// i.e. its `default_engine`, Handlebars by default.
+  pub(crate) fn name_engine(&self) -> Engine {
+    self.config.default_engine.unwrap_or(Engine::Handlebars)
+  }
+
+  /// Patterns of the `[rename]` clause, in order, with their values rendered
+  /// with the template's variables and converted to the `--rename-case`.
+  ///
+  /// Patterns whose value is rendered empty, or references an unset
+  /// variable, are left out, so that names are kept as they are. Values that
+  /// can't be rendered otherwise, e.g. with an unclosed block, are reported.
+  pub(crate) fn rename_maps(&self) -> Result<Vec<(GlobPattern, String)>> {
+    let rename = match &self.config.rename {
+      Some(rename) => rename,
+      None => return Ok(Vec::new()),
+    };
+
+    let variables = self.variables();
+    let mut maps = Vec::new();
+    for (key, value) in rename {
+      // Invalid patterns are matched literally.
+      let pattern = GlobPattern::new(key)
+        .or_else(|_| GlobPattern::new(&GlobPattern::escape(key)))
+        .unwrap();
+      let engine = self.name_engine();
+      let helpers = &self.config.helpers;
+      let value = match engine.parse(value, &variables, helpers) {
+        Ok(value) => value,
+        // Rendered with its unset variables bound, the value is valid.
+        Err(_)
+          if engine
+            .parse(value, &engine::unbound(value, &variables)?, helpers)
+            .is_ok() =>
+        {
+          log::debug!("{:?} isn't renamed: its variables are unset", key);
+          continue;
+        }
+        Err(err) => {
+          return Err(Error::new(
+            ErrorKind::Config,
+            &format!("rename of {:?} can't be rendered: {}", key, err),
+          ))
+        }
+      };
+      if value.trim().is_empty() {
+        log::debug!("{:?} isn't renamed: its value is empty", key);
+        continue;
+      }
+      let value = match self.rename_case {
+        Some(case) => case.apply(&value),
+        None => value,
+      };
+      maps.push((pattern, value));
+    }
+
+    Ok(maps)
+  }
+
+  /// Directories of the `[[fanout]]` clause, relative to the template's base
+  /// directory, along with the directory of each of their copies & the
+  /// variables it's rendered with, i.e. with `{{item}}` bound.
+  ///
+  /// Each copy's name must be a single directory name, e.g. neither `..` nor
+  /// `a/b`, so that it's generated next to the directory it's copied from.
+  pub(crate) fn fanout_maps(&self) -> Result<Vec<(PathBuf, Vec<FanoutCopy>)>> {
+    let fanouts = match &self.config.fanout {
+      Some(fanouts) => fanouts,
+      None => return Ok(Vec::new()),
+    };
+
+    let variables = self.variables();
+    let mut maps = Vec::new();
+    for fanout in fanouts {
+      let value = variables.get(&fanout.over).ok_or_else(|| {
+        Error::new(
+          ErrorKind::Config,
+          &format!(
+            "fanout of {:?} is over an unknown variable {:?}",
+            fanout.src, fanout.over
+          ),
+        )
+      })?;
+
+      let src = PathBuf::from(&fanout.src);
+      let parent = src.parent().unwrap_or_else(|| Path::new(""));
+      let name = fanout.name.as_deref().unwrap_or("{{item}}");
+      let mut copies = Vec::new();
+      for item in config::Fanout::items(value) {
+        let mut variables = variables.clone();
+        variables.insert("item".to_string(), item.to_string());
+        let name =
+          self
+            .name_engine()
+            .parse(name, &variables, &self.config.helpers)?;
+        let mut components = Path::new(&name).components();
+        match (components.next(), components.next()) {
+          (Some(Component::Normal(_)), None) => {}
+          _ => {
+            return Err(Error::new(
+              ErrorKind::Config,
+              &format!(
+              "fanout of {:?} names a copy {:?}, which isn't a directory name",
+              fanout.src, name
+            ),
+            ))
+          }
+        }
+        copies.push((parent.join(name), variables));
+      }
+      maps.push((src, copies));
+    }
+
+    Ok(maps)
+  }
+
+  pub(crate) fn move_maps(&self) -> HashMap<String, String> {
+    match &self.config.moves {
+      Some(moves) => moves.clone(),
+      None => HashMap::new(),
+    }
+  }
+
+  /// Patterns of the `[filters]` clause, with their placeholders rendered.
+  ///
+  /// Glob metacharacters in the variables' values are escaped, so a project
+  /// named `app[1]` is matched literally rather than as a character class.
+  pub(crate) fn filter_patterns(&self) -> Vec<String> {
+    let (_, patterns) = self.get_ignored();
+    let variables: HashMap<String, String> = self
+      .variables()
+      .into_iter()
+      .map(|(key, value)| (key, GlobPattern::escape(&value)))
+      .collect();
+    patterns
+      .into_iter()
+      .map(|pattern| {
+        if !pattern.contains("{{") {
+          return pattern;
+        }
+        self
+          .name_engine()
+          .parse(&pattern, &variables, &self.config.helpers)
+          .unwrap_or(pattern)
+      })
+      .collect()
+  }
+
+  pub(crate) fn get_ignored(&self) -> (bool, Vec<String>) {
+    let filters = match &self.config.filters {
+      Some(f) => f,
+      None => panic!("No Filters."),
+    };
+    if filters.include.is_some() {
+      (true, filters.include.clone().unwrap())
+    } else {
+      (true, filters.exclude.clone().unwrap())
+    }
+  }
+}
+
+impl Default for TemplateMeta {
+  fn default() -> TemplateMeta {
+    TemplateMeta {
+      template_options: TemplateOptions::default(),
+      config: TemplateConfig::default(),
+      project_info: ProjectInfo::default(),
+      cargo_generate: false,
+      abort_on_warning: false,
+      warnings: Vec::new(),
+      write_lock: false,
+      locked: false,
+      strict_walk: false,
+      keep_going: false,
+      verbosity: Verbosity::Normal,
+      json: false,
+      update: false,
+      render_only: None,
+      env: None,
+      since: None,
+      preserve_timestamps: false,
+      expect_tree_sha: None,
+      run: Vec::new(),
+      template_args: Vec::new(),
+      only: None,
+      only_as_root: false,
+      trace_renders: false,
+      assume_yes: false,
+      confirm: false,
+      keep_template_toml: false,
+      rename_case: None,
+      git_init: false,
+      commit_message: None,
+      force: false,
+      ask_overwrite: false,
+      i_know_what_im_doing: false,
+      template_toml_strict: false,
+      engines: HashMap::new(),
+      base: None,
+    }
+  }
+}
+
+/// Returns true if a `[rename]` key is a glob pattern rather than a name.
+fn is_glob(key: &str) -> bool {
+  key.contains(&['*', '?', '['][..])
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  use tempfile::TempDir;
+
+  /// Creates a template directory containing the given `(path, content)` files.
+  pub(crate) fn template_dir(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, content) in files {
+      let path = dir.path().join(path);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, content).unwrap();
+    }
+    dir
+  }
+
+  /// Creates a `Template` generating from `template_dir` into `project_dir`.
+  pub(crate) fn local_template(
+    template_dir: &Path,
+    project_dir: &Path,
+  ) -> Template {
+    let project = ProjectInfo::new(project_dir);
+    let options = TemplateOptions::Local(template_dir.to_path_buf());
+    Template::new(&project, &options)
+  }
+
+  #[test]
+  fn test_cargo_generate_template() {
+    let src = template_dir(&[
+      (
+        "Cargo.toml",
+        "[package]\nname = \"{{project-name}}\"\nversion = \"0.1.0\"\n",
+      ),
+      (
+        "src/main.rs",
+        "// {{ crate_name }}\nfn main() {\n  println!(\"{{ project-name | title_case }}\");\n}\n",
+      ),
+      ("src/lib.rs.hbs", "pub struct {{ project-name | pascal_case }};\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.cargo_generate = true;
+    template.generate().unwrap();
+
+    assert_eq!(
+      fs::read_to_string(project_dir.join("Cargo.toml")).unwrap(),
+      "[package]\nname = \"my-project\"\nversion = \"0.1.0\"\n"
+    );
+    assert_eq!(
+      fs::read_to_string(project_dir.join("src/main.rs")).unwrap(),
+      "// my_project\nfn main() {\n  println!(\"My Project\");\n}\n"
+    );
+    assert_eq!(
+      fs::read_to_string(project_dir.join("src/lib.rs")).unwrap(),
+      "pub struct MyProject;\n"
+    );
+
+    // A user-defined `project-name` is kept.
+    let project_dir = dest.path().join("other-project");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.cargo_generate = true;
+    template.set_variable("project-name", "custom-name");
+    template.generate().unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("Cargo.toml")).unwrap(),
+      "[package]\nname = \"custom-name\"\nversion = \"0.1.0\"\n"
+    );
+  }
+
+  #[test]
+  fn test_abort_on_warning() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\ninclude = [\"src\"]\nexclude = [\".git\"]\n",
+      ),
+      ("src/main.rs", "fn main() {}\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+
+    // Warnings are reported, but generation succeeds.
+    let project_dir = dest.path().join("lenient");
+    let template = local_template(src.path(), &project_dir);
+    assert!(template.generate().is_ok());
+
+    // Warnings abort generation.
+    let project_dir = dest.path().join("strict");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.abort_on_warning = true;
+    let err = template.generate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Warning);
+  }
+
+  #[test]
+  fn test_empty_template() {
+    let src = template_dir(&[(
+      "template.toml",
+      "[filters]\nexclude = []\n\n[variables]\nname = \"app\"\n",
+    )]);
+    let dest = tempfile::tempdir().unwrap();
+
+    // Reported, but generated regardless.
+    let project_dir = dest.path().join("lenient");
+    let stats = local_template(src.path(), &project_dir).generate().unwrap();
+    assert!(stats.generated.is_empty());
+
+    // Also when `template.toml` is kept.
+    let project_dir = dest.path().join("kept");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.keep_template_toml = true;
+    template.template.abort_on_warning = true;
+    let err = template.generate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Warning);
+    assert!(err.message().contains("no files to generate"));
+  }
+
+  #[test]
+  fn test_rename_collision() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[rename]\nsrc = \"app\"\nlib = \"app\"\n",
+      ),
+      ("src/main.rs", "fn main() {}\n"),
+      ("lib/lib.rs", "\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+
+    // Statically detected before anything is generated.
+    let project_dir = dest.path().join("static");
+    let template = local_template(src.path(), &project_dir);
+    let err = template.validate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+    assert!(template.generate().is_err());
+    assert!(!project_dir.join("app").exists());
+
+    // `src` renamed onto the existing `lib` directory.
+    fs::write(
+      src.path().join("template.toml"),
+      "[filters]\nexclude = []\n\n[rename]\nsrc = \"lib\"\n",
+    )
+    .unwrap();
+    let project_dir = dest.path().join("runtime");
+    let template = local_template(src.path(), &project_dir);
+    assert!(template.validate().is_ok());
+    let err = template.generate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+  }
+
+  #[test]
+  fn test_env_file_variables() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[variables]\nservice_name = \"default\"\n\n[filters]\nexclude = []\n",
+      ),
+      ("README.md.hbs", "# {{service_name}}\n"),
+    ]);
+    let env = template_dir(&[(".env", "# Service.\nSERVICE_NAME=billing\n")]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let mut template = local_template(src.path(), &project_dir);
+    template
+      .template
+      .config
+      .merge_env_file(&env.path().join(".env"), true)
+      .unwrap();
+    template.generate().unwrap();
+
+    assert_eq!(
+      fs::read_to_string(project_dir.join("README.md")).unwrap(),
+      "# billing\n"
+    );
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn test_unreadable_directory() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let src = template_dir(&[
+      ("README.md", "# Project\n"),
+      ("secret/key.txt", "secret\n"),
+    ]);
+    // Permissions aren't enforced for root, which owns the files it creates.
+    if fs::metadata(src.path()).unwrap().uid() == 0 {
+      eprintln!("skipping test_unreadable_directory: running as root");
+      return;
+    }
+    let secret = src.path().join("secret");
+    fs::set_permissions(&secret, fs::Permissions::from_mode(0o000)).unwrap();
+    let dest = tempfile::tempdir().unwrap();
+
+    // Skipped with a warning.
+    let project_dir = dest.path().join("warning");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.abort_on_warning = true;
+    let err = template.generate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Warning);
+    assert!(project_dir.join("README.md").exists());
+
+    // Fatal with `--strict-walk`.
+    let project_dir = dest.path().join("strict");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.strict_walk = true;
+    let err = template.generate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Io);
+
+    fs::set_permissions(&secret, fs::Permissions::from_mode(0o755)).unwrap();
+  }
+
+  #[test]
+  fn test_move_file() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"template.toml\"]\n\n[rename]\nbin = \"tools\"\n\n[move]\n\"scripts/setup.sh\" = \"bin/setup.sh\"\n",
+      ),
+      ("scripts/setup.sh", "#!/bin/sh\n"),
+      ("src/main.rs", "fn main() {}\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let template = local_template(src.path(), &project_dir);
+    template.generate().unwrap();
+
+    // `[rename]` doesn't apply to moved files.
+    assert_eq!(
+      fs::read_to_string(project_dir.join("bin/setup.sh")).unwrap(),
+      "#!/bin/sh\n"
+    );
+    assert!(!project_dir.join("scripts").exists());
+    assert!(!project_dir.join("tools").exists());
+    assert!(project_dir.join("src/main.rs").exists());
+  }
+
+  #[test]
+  fn test_validate_vars_scalars() {
+    let src = template_dir(&[(
+      "template.toml",
+      "[filters]\nexclude = []\n\n[prompts]\nport = \"\"\n\n[validate]\nport = \"^[0-9]+$\"\n",
+    )]);
+    let dest = tempfile::tempdir().unwrap();
+    let template = local_template(src.path(), &dest.path().join("project"));
+
+    let vars = |file: &str, content: &str| {
+      let path = dest.path().join(file);
+      fs::write(&path, content).unwrap();
+      template.validate_vars(&path)
+    };
+    vars("vars.json", "{\"port\": 8080, \"debug\": true}").unwrap();
+    vars("vars.toml", "port = 8080\ndebug = true\n").unwrap();
+
+    let err = vars("vars.json", "{\"port\": [8080]}").unwrap_err();
+    assert!(err
+      .to_string()
+      .contains("\"port\" isn't a string, number or boolean"));
+  }
+
+  #[test]
+  fn test_move_outside_project() {
+    for target in &["../escape", "/tmp/x"] {
+      let src = template_dir(&[
+        (
+          "template.toml",
+          &format!("[filters]\nexclude = []\n\n[move]\n\"a\" = {:?}\n", target),
+        ),
+        ("a", "escaped\n"),
+      ]);
+      let dest = tempfile::tempdir().unwrap();
+      let project_dir = dest.path().join("project");
+
+      let err = local_template(src.path(), &project_dir)
+        .generate()
+        .unwrap_err();
+      assert!(
+        err.to_string().contains("outside of the project"),
+        "{}",
+        err
+      );
+      assert!(!dest.path().join("escape").exists());
+      assert_eq!(fs::read_dir(&project_dir).unwrap().count(), 0);
+    }
+  }
+
+  #[test]
+  fn test_conditional_rename() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[variables]\nlib_name = \"\"\n\n[rename]\napp = \"{{app_name}}\"\nlib = \"{{lib_name}}\"\n",
+      ),
+      ("app/main.rs", "fn main() {}\n"),
+      ("lib/lib.rs", "\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    // Unset or empty values keep the original names.
+    local_template(src.path(), &project_dir).generate().unwrap();
+    assert!(project_dir.join("app/main.rs").exists());
+    assert!(project_dir.join("lib/lib.rs").exists());
+
+    let project_dir = dest.path().join("renamed");
+    let mut template = local_template(src.path(), &project_dir);
+    let variables = template
+      .template
+      .config
+      .variables
+      .get_or_insert_with(HashMap::new);
+    variables.insert("app_name".to_string(), "server".to_string());
+    template.generate().unwrap();
+    assert!(project_dir.join("server/main.rs").exists());
+    assert!(project_dir.join("lib/lib.rs").exists());
+
+    // Values that can't be rendered are reported instead of dropped.
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[rename]\napp = \"{{#if app_name}}\"\n",
+      ),
+      ("app/main.rs", "fn main() {}\n"),
+    ]);
+    let project_dir = dest.path().join("invalid");
+    let err = local_template(src.path(), &project_dir)
+      .generate()
+      .unwrap_err();
+    assert!(err
+      .to_string()
+      .contains("rename of \"app\" can't be rendered"));
+  }
+
+  #[test]
+  fn test_rename_components() {
+    let src = template_dir(&[(
+      "template.toml",
+      "[filters]\nexclude = []\n\n[rename]\nsrc = \"app\"\n",
+    )]);
+    let dest = tempfile::tempdir().unwrap();
+    let template = local_template(src.path(), &dest.path().join("project"));
+
+    let renamed =
+      |path: &str| template.renamed(Path::new(path), Path::new("")).unwrap();
+    assert_eq!(renamed("src/lib.rs"), Path::new("app/lib.rs"));
+    assert_eq!(renamed("./src/lib.rs"), Path::new("./app/lib.rs"));
+    assert_eq!(renamed("lib/src.rs"), Path::new("lib/src.rs"));
+    #[cfg(windows)]
+    assert_eq!(renamed(r"src\lib.rs"), PathBuf::from("app").join("lib.rs"));
+
+    // Keys with a path separator never match a single name.
+    fs::write(
+      src.path().join("template.toml"),
+      "[filters]\nexclude = []\n\n[rename]\n\"src/bin\" = \"app\"\n",
+    )
+    .unwrap();
+    let template = local_template(src.path(), &dest.path().join("invalid"));
+    let err = template.validate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+  }
+
+  #[test]
+  fn test_rename_case() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[rename]\npackage = \"{{project-name}}\"\n\"*.cfg\" = \".{{project-name}}.cfg\"\n",
+      ),
+      ("package/__init__.py", ""),
+      ("setup.cfg", ""),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+
+    for (case, dir, cfg) in &[
+      (Case::Kebab, "my-project", ".my-project.cfg"),
+      (Case::Pascal, "MyProject", ".MyProject.cfg"),
+    ] {
+      let project_dir =
+        dest.path().join(format!("{:?}", case)).join("my-project");
+      let mut template = local_template(src.path(), &project_dir);
+      template.template.rename_case = Some(*case);
+      template.generate().unwrap();
+
+      assert!(project_dir.join(dir).join("__init__.py").exists());
+      assert!(project_dir.join(cfg).exists());
+    }
+  }
+
+  #[test]
+  fn test_skipped_files() {
+    let src = template_dir(&[
+      ("template.toml", "[filters]\nexclude = [\"*.log\"]\n"),
+      ("README.md.hbs", "# {{project-name}}\n"),
+      ("logs/debug.log", ""),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let mut stats =
+      local_template(src.path(), &project_dir).generate().unwrap();
+    stats.skipped.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    // Rendered files are recorded by the path they're written to.
+    assert_eq!(
+      stats.generated,
+      vec![project_dir.join("README.md")].into_iter().collect()
+    );
+
+    assert_eq!(
+      stats.skipped,
+      vec![
+        (
+          PathBuf::from("logs/debug.log"),
+          SkipReason::ConfigExclude("*.log".to_string())
+        ),
+        (PathBuf::from("template.toml"), SkipReason::TemplateFile),
+      ]
+    );
+    assert!(project_dir.join("README.md").exists());
+    assert!(!project_dir.join("logs/debug.log").exists());
+  }
+
+  #[test]
+  fn test_plan() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"*.log\"]\n\n[rename]\nsrc = \"{{project-name}}\"\n",
+      ),
+      ("README.md.hbs", "# {{project-name}}"),
+      ("src/main.py", ""),
+      ("debug.log", ""),
+    ]);
+    fs::create_dir(src.path().join("empty")).unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    let template = local_template(src.path(), &project_dir);
+    let mut plan = template.plan().unwrap();
+    plan.actions.sort_by(|a, b| a.source.cmp(&b.source));
+
+    let kinds: Vec<(&str, &ActionKind)> = plan
+      .actions
+      .iter()
+      .map(|action| (action.source.to_str().unwrap(), &action.kind))
+      .collect();
+    assert_eq!(
+      kinds,
+      vec![
+        ("README.md.hbs", &ActionKind::Render),
+        (
+          "debug.log",
+          &ActionKind::Skip(SkipReason::ConfigExclude("*.log".to_string()))
+        ),
+        ("empty", &ActionKind::Create),
+        ("src/main.py", &ActionKind::Copy),
+        ("template.toml", &ActionKind::Skip(SkipReason::TemplateFile)),
+      ]
+    );
+    assert_eq!(plan.actions[0].target, Some(project_dir.join("README.md")));
+    assert_eq!(
+      plan.actions[3].target,
+      Some(project_dir.join("my_project/main.py"))
+    );
+
+    // Nothing is written until the project is generated.
+    assert_eq!(fs::read_dir(&project_dir).unwrap().count(), 0);
+    let stats = template.generate().unwrap();
+    assert_eq!(
+      stats.generated,
+      vec![
+        project_dir.join("README.md"),
+        project_dir.join("my_project/main.py"),
+      ]
+      .into_iter()
+      .collect()
+    );
+    assert!(project_dir.join("empty").is_dir());
+  }
+
+  #[test]
+  fn test_trim_blocks() {
+    let content =
+      "[\n  {{#if license}}\n  license = \"{{license}}\"\n  {{/if}}\n]\n";
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "trim_blocks = true\n\n[filters]\nexclude = []\n\n[variables]\nlicense = \"MIT\"\n",
+      ),
+      ("trimmed.toml.hbs", content),
+      (
+        "trimmed.md.liquid",
+        "# Title\n{% if license %}\nLicensed under {{license}}.\n{% endif %}\n",
+      ),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    local_template(src.path(), &project_dir).generate().unwrap();
+    let read = |file: &str| fs::read_to_string(project_dir.join(file)).unwrap();
+    assert_eq!(read("trimmed.toml"), "[\n  license = \"MIT\"\n]\n");
+    assert_eq!(read("trimmed.md"), "# Title\nLicensed under MIT.\n");
+
+    // Blank lines are left where the blocks were otherwise.
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[variables]\nlicense = \"MIT\"\n",
+      ),
+      ("untrimmed.toml.hbs", content),
+    ]);
+    let project_dir = dest.path().join("untrimmed");
+    local_template(src.path(), &project_dir).generate().unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("untrimmed.toml")).unwrap(),
+      "[\n  \n  license = \"MIT\"\n  \n]\n"
+    );
+  }
+
+  #[test]
+  fn test_only() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"backend/*.log\"]\n\n[rename]\nsrc = \"{{project-name}}\"\n",
+      ),
+      ("backend/src/main.rs.hbs", "// {{project-name}}\n"),
+      ("backend/debug.log", ""),
+      ("frontend/index.html", "<html></html>\n"),
+      ("README.md", "# Template\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+
+    // Files keep their path in the template.
+    let project_dir = dest.path().join("project");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.only = Some(PathBuf::from("backend"));
+    template.generate().unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("backend/project/main.rs")).unwrap(),
+      "// project\n"
+    );
+    assert!(!project_dir.join("backend/debug.log").exists());
+    assert!(!project_dir.join("frontend").exists());
+    assert!(!project_dir.join("README.md").exists());
+
+    // Or the subdirectory is the project's root.
+    let project_dir = dest.path().join("root");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.only = Some(PathBuf::from("backend"));
+    template.template.only_as_root = true;
+    template.generate().unwrap();
+    assert!(project_dir.join("root/main.rs").exists());
+    assert!(project_dir.join("debug.log").exists());
+    assert!(!project_dir.join("frontend").exists());
+
+    let mut template = local_template(src.path(), &dest.path().join("none"));
+    template.template.only = Some(PathBuf::from("../backend"));
+    let err = template.generate().unwrap_err();
+    assert!(err
+      .to_string()
+      .contains("isn't a directory of the template"));
+  }
+
+  #[test]
+  fn test_default_engine() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "default_engine = \"liquid\"\n\n[filters]\nexclude = []\n\n[variables]\nport = \"8080\"\ndir = \"etc\"\n\n[rename]\nconfig = \"{{ dir | upcase }}\"\n\n[render]\ninclude = [\"config/*.conf\"]\n",
+      ),
+      (
+        "config/app.conf",
+        "{% if port %}port = {{ port | plus: 1 }}{% endif %}\n",
+      ),
+      ("config/notes.txt", "{{ port }}\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    local_template(src.path(), &project_dir).generate().unwrap();
+    let read = |file: &str| fs::read_to_string(project_dir.join(file)).unwrap();
+    assert_eq!(read("ETC/app.conf"), "port = 8081\n");
+    assert_eq!(read("ETC/notes.txt"), "{{ port }}\n");
+  }
+
+  #[test]
+  fn test_front_matter() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[variables]\ndocker = \"\"\nport = \"8080\"\n",
+      ),
+      (
+        "app.toml.hbs",
+        "---\npath = \"config/{{project-name}}.toml\"\nengine = \"liquid\"\n---\nport = {{ port | plus: 1 }}\n",
+      ),
+      (
+        "Dockerfile.hbs",
+        "---\nskip_if = \"(not docker)\"\n---\nEXPOSE {{port}}\n",
+      ),
+      ("README.md.hbs", "---\nlayout: post\n---\n# {{project-name}}\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let template = local_template(src.path(), &project_dir);
+    let plan = template.plan().unwrap();
+    assert!(plan.actions.iter().any(|action| action.kind
+      == ActionKind::Skip(SkipReason::Condition("(not docker)".to_string()))));
+
+    template.generate().unwrap();
+    let read = |file: &str| fs::read_to_string(project_dir.join(file)).unwrap();
+    assert_eq!(read("config/project.toml"), "port = 8081\n");
+    assert!(!project_dir.join("app.toml").exists());
+    assert!(!project_dir.join("Dockerfile").exists());
+    // Foreign front matter is rendered as-is.
+    assert_eq!(read("README.md"), "---\nlayout: post\n---\n# project\n");
+
+    // Paths outside of the project are refused.
+    for path in &["../../escaped.toml", "/tmp/escaped.toml"] {
+      let src = template_dir(&[
+        ("template.toml", "[filters]\nexclude = []\n"),
+        ("app.toml.hbs", &format!("---\npath = \"{}\"\n---\n", path)),
+      ]);
+      let err = local_template(src.path(), &project_dir).plan().unwrap_err();
+      assert!(
+        err.to_string().contains("outside of the project"),
+        "{}",
+        err
+      );
+    }
+  }
+
+  #[test]
+  fn test_strip_lines_matching() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "strip_lines_matching = \"^#\\\\s*template-only\"\n\n[filters]\nexclude = []\n\n[variables]\nport = \"8080\"\n",
+      ),
+      (
+        ".env.hbs",
+        "# template-only: the port is set by the deployment\nPORT={{port}}\n#template-only\n# Kept.\n",
+      ),
+      (
+        "notes.md.hbs",
+        "---\nstrip_lines_matching = \"^> note:\"\n---\n> note: for authors\n# template-only is kept here\n",
+      ),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.generate().unwrap();
+    let read = |file: &str| fs::read_to_string(project_dir.join(file)).unwrap();
+    assert_eq!(read(".env"), "PORT=8080\n# Kept.\n");
+    assert_eq!(read("notes.md"), "# template-only is kept here\n");
+
+    template.template.config.strip_lines_matching = Some("(".to_string());
+    let err = template.validate().unwrap_err();
+    assert!(err.to_string().contains("invalid strip_lines_matching"));
+  }
+
+  #[test]
+  fn test_strip_lines_rendered() {
+    /// Engine writing the content of the template as-is.
+    struct Verbatim;
+
+    impl TemplateEngine for Verbatim {
+      type Data = HashMap<String, String>;
+
+      fn render(
+        &self,
+        src: &Path,
+        target: &Path,
+        _: &Self::Data,
+      ) -> Result<()> {
+        fs::copy(src, target)?;
+        Ok(())
+      }
+    }
+
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "strip_lines_matching = \"^# note\"\n\n[filters]\nexclude = []\n",
+      ),
+      ("notes.txt.foo", "# note: for authors\nKept.\n"),
+      ("Cargo.toml", "# note: for authors\n[package]\n"),
+    ]);
+    let logo = b"# note\n\xff\xfe\n";
+    fs::write(src.path().join("logo.png.foo"), logo).unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    // Files rendered by custom engines, or as `cargo-generate` templates, are
+    // stripped too, unless they aren't text.
+    let mut template = local_template(src.path(), &project_dir);
+    template.register_engine("foo", Box::new(Verbatim));
+    template.template.cargo_generate = true;
+    template.generate().unwrap();
+    let read = |file: &str| fs::read(project_dir.join(file)).unwrap();
+    assert_eq!(read("notes.txt"), b"Kept.\n");
+    assert_eq!(read("Cargo.toml"), b"[package]\n");
+    assert_eq!(read("logo.png"), logo);
+  }
+
+  #[test]
+  fn test_keep_extension() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[render]\nkeep_extension = [\"templates/*.hbs\"]\n",
+      ),
+      ("templates/page.hbs", "<h1>{{project-name}}</h1>\n"),
+      ("README.md.hbs", "# {{project-name}}\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let template = local_template(src.path(), &project_dir);
+    let plan = template.plan().unwrap();
+    assert!(plan.actions.iter().any(
+      |action| action.target == Some(project_dir.join("templates/page.hbs"))
+    ));
+
+    template.generate().unwrap();
+    let read = |file: &str| fs::read_to_string(project_dir.join(file)).unwrap();
+    assert_eq!(read("templates/page.hbs"), "<h1>project</h1>\n");
+    assert_eq!(read("README.md"), "# project\n");
+    assert!(!project_dir.join("templates/page").exists());
+  }
+
+  #[test]
+  fn test_placeholder_filters() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"{{project-name}}-secrets/*\", \"data{{suffix}}\"]\n\n[variables]\nsuffix = \"[1]\"\n",
+      ),
+      ("my_project-secrets/key.pem", ""),
+      ("other-secrets/key.pem", ""),
+      ("data[1]/a.csv", ""),
+      ("data1/a.csv", ""),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    local_template(src.path(), &project_dir).generate().unwrap();
+
+    assert!(!project_dir.join("my_project-secrets").exists());
+    assert!(project_dir.join("other-secrets/key.pem").exists());
+    // The variable's `[1]` is matched literally, not as a character class.
+    assert!(!project_dir.join("data[1]").exists());
+    assert!(project_dir.join("data1/a.csv").exists());
+  }
+
+  #[test]
+  fn test_summary_only() {
+    let src = template_dir(&[
+      ("template.toml", "[filters]\nexclude = [\"*.log\"]\n"),
+      ("README.md", ""),
+      ("src/main.rs", ""),
+      ("debug.log", ""),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.verbosity = Verbosity::SummaryOnly;
+    let stats = template.generate().unwrap();
+
+    let mut out = Vec::new();
+    template.write_summary(&stats, &mut out).unwrap();
+    assert_eq!(
+      String::from_utf8(out).unwrap(),
+      format!(
+        "Generated 2 files (2 skipped) in \"{}\"\n",
+        template.project_info.path().display()
+      )
+    );
+  }
+
+  #[test]
+  fn test_stats_json_round_trip() {
+    let src = template_dir(&[
+      ("template.toml", "[filters]\nexclude = [\"*.log\"]\n"),
+      ("README.md", ""),
+      ("debug.log", ""),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.json = true;
+    template.template.verbosity = Verbosity::Quiet;
+    let stats = template.generate().unwrap();
+    assert_eq!(stats.schema_version, GenerationStats::SCHEMA_VERSION);
+    assert_eq!(stats.project_dir, template.project_info.path());
+
+    let mut out = Vec::new();
+    template.write_summary(&stats, &mut out).unwrap();
+    let parsed: GenerationStats =
+      serde_json::from_slice(&out).expect("summary is valid JSON");
+    assert_eq!(parsed, stats);
+  }
+
+  #[test]
+  fn test_fanout() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"template.toml\"]\n\n[variables]\nservices = \"auth, billing, search\"\n\n[[fanout]]\nsrc = \"services/service_template\"\nover = \"services\"\nname = \"{{item}}-service\"\n",
+      ),
+      (
+        "services/service_template/main.py.hbs",
+        "# {{item}} service of {{project-name}}\n",
+      ),
+      ("services/service_template/tests/.keep", ""),
+      ("README.md", ""),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    local_template(src.path(), &project_dir).generate().unwrap();
+
+    for service in &["auth", "billing", "search"] {
+      let dir = project_dir.join(format!("services/{}-service", service));
+      assert_eq!(
+        fs::read_to_string(dir.join("main.py")).unwrap(),
+        format!("# {} service of my_project\n", service)
+      );
+      assert!(dir.join("tests/.keep").exists());
+    }
+    assert!(!project_dir.join("services/service_template").exists());
+    assert!(project_dir.join("README.md").exists());
+
+    // Items can't name a copy outside of the fanned out directory's parent.
+    for services in
+      &["auth, ../../../escaped", "auth, nested/dir", "auth, /tmp"]
+    {
+      fs::write(
+        src.path().join("template.toml"),
+        format!(
+          "[filters]\nexclude = [\"template.toml\"]\n\n[variables]\nservices = {:?}\n\n[[fanout]]\nsrc = \"services/service_template\"\nover = \"services\"\n",
+          services
+        ),
+      )
+      .unwrap();
+      let project_dir = dest.path().join("other-project");
+      let err = local_template(src.path(), &project_dir)
+        .generate()
+        .unwrap_err();
+      assert_eq!(err.kind(), &ErrorKind::Config, "{}", err);
+      assert!(!dest.path().join("escaped").exists());
+    }
+  }
+
+  #[test]
+  fn test_update_since() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"template.toml\"]\n",
+      ),
+      ("README.md.hbs", "# {{project-name}}\n"),
+      ("setup.py", "version = 1\n"),
+    ]);
+    git::init_commit(src.path(), "First").unwrap();
+    let repo = git2::Repository::open(src.path()).unwrap();
+    let first = repo.head().unwrap().target().unwrap().to_string();
+    fs::write(src.path().join("setup.py"), "version = 2\n").unwrap();
+    git::init_commit(src.path(), "Second").unwrap();
+
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+    local_template(src.path(), &project_dir).generate().unwrap();
+    // The project was changed after it was generated.
+    fs::write(project_dir.join("README.md"), "# Edited\n").unwrap();
+    fs::write(project_dir.join("setup.py"), "version = 1\n").unwrap();
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.update = true;
+    template.template.since = Some(first);
+    let stats = template.generate().unwrap();
+
+    let setup = template.project_info.path().join("setup.py");
+    assert_eq!(stats.generated.into_iter().collect::<Vec<_>>(), vec![setup]);
+    assert_eq!(
+      fs::read_to_string(project_dir.join("setup.py")).unwrap(),
+      "version = 2\n"
+    );
+    assert_eq!(
+      fs::read_to_string(project_dir.join("README.md")).unwrap(),
+      "# Edited\n"
+    );
+  }
+
+  #[test]
+  fn test_nested_git_dirs() {
+    let src = template_dir(&[
+      ("template.toml", "[filters]\nexclude = []\n"),
+      ("examples/app/.git/HEAD", "ref: refs/heads/master\n"),
+      ("examples/app/main.rs", ""),
+      ("vendor/lib/.git", "gitdir: ../../.git/modules/lib\n"),
+      ("vendor/lib/lib.rs", ""),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let mut stats =
+      local_template(src.path(), &project_dir).generate().unwrap();
+    stats.skipped.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    assert_eq!(
+      stats.skipped,
+      vec![
+        (PathBuf::from("examples/app/.git"), SkipReason::Git),
+        (PathBuf::from("template.toml"), SkipReason::TemplateFile),
+        (PathBuf::from("vendor/lib/.git"), SkipReason::Git),
+      ]
+    );
+    assert!(project_dir.join("examples/app/main.rs").exists());
+    assert!(!project_dir.join("examples/app/.git").exists());
+    assert!(project_dir.join("vendor/lib/lib.rs").exists());
+    assert!(!project_dir.join("vendor/lib/.git").exists());
+  }
+
+  #[test]
+  fn test_render_only() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[rename]\nsrc = \"{{project-name}}\"\n",
+      ),
+      ("README.md.hbs", "# {{project-name}}\n"),
+      ("src/app.py.hbs", "NAME = \"{{project-name}}\"\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+    local_template(src.path(), &project_dir).generate().unwrap();
+
+    fs::write(src.path().join("README.md.hbs"), "# {{project-name}}!\n")
+      .unwrap();
+    fs::write(
+      src.path().join("src/app.py.hbs"),
+      "NAME = \"{{project-name}}\"\nDEBUG = True\n",
+    )
+    .unwrap();
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.render_only = Some("src/*.py.hbs".to_string());
+    let stats = template.generate().unwrap();
+    assert_eq!(stats.generated.len(), 1);
+    let read = |file: &str| fs::read_to_string(project_dir.join(file)).unwrap();
+    assert_eq!(read("project/app.py"), "NAME = \"project\"\nDEBUG = True\n");
+    assert_eq!(read("README.md"), "# project\n");
+
+    template.template.render_only = Some("*.rs".to_string());
+    let err = template.generate().unwrap_err();
+    assert!(err.to_string().contains("matches no file"), "{}", err);
+  }
+
+  #[test]
+  fn test_template_toml_strict() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[filtres]\nexclude = [\"*.log\"]\n",
+      ),
+      ("debug.log", ""),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.template_toml_strict = true;
+    let err = template.generate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+    assert_eq!(
+      err.to_string(),
+      "unknown keys in template.toml: \"filtres\" (did you mean \"filters\"?)"
+    );
+    assert!(!project_dir.join("debug.log").exists());
+
+    // They're only warned about otherwise.
+    let template = local_template(src.path(), &project_dir);
+    assert!(template
+      .warnings
+      .iter()
+      .any(|warning| warning.contains("\"filtres\"")));
+    template.generate().unwrap();
+    assert!(project_dir.join("debug.log").exists());
+  }
+
+  #[test]
+  fn test_dotfiles() {
+    // Default filters only match directories by their exact name.
+    let src = template_dir(&[
+      (".git/HEAD", "ref: refs/heads/master\n"),
+      (".gitignore", "target/\n"),
+      (".idea/workspace.xml", ""),
+      (".editorconfig", "root = true\n"),
+      ("venv", "python3\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    local_template(src.path(), &project_dir).generate().unwrap();
+    assert!(!project_dir.join(".git").exists());
+    assert!(!project_dir.join(".idea").exists());
+    for file in &[".gitignore", ".editorconfig", "venv"] {
+      assert!(project_dir.join(file).exists(), "{} was dropped", file);
+    }
+
+    // Wildcards don't match the leading dot of dotfiles.
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"*.example\", \"*rc\", \".secrets*\"]\n",
+      ),
+      (".env.example", "PORT=8080\n"),
+      (".npmrc", ""),
+      (".secrets.toml", ""),
+      ("app.example", ""),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    local_template(src.path(), &project_dir).generate().unwrap();
+    assert!(project_dir.join(".env.example").exists());
+    assert!(project_dir.join(".npmrc").exists());
+    assert!(!project_dir.join(".secrets.toml").exists());
+    assert!(!project_dir.join("app.example").exists());
+  }
+
+  #[test]
+  fn test_preserve_timestamps() {
+    let src = template_dir(&[
+      ("template.toml", "[filters]\nexclude = []\n"),
+      ("assets/logo.svg", "<svg/>"),
+    ]);
+    let logo = src.path().join("assets/logo.svg");
+    let mtime = FileTime::from_unix_time(1_000_000_000, 0);
+    filetime::set_file_mtime(&logo, mtime).unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let mtime_of = |path: &Path| {
+      FileTime::from_last_modification_time(&fs::metadata(path).unwrap())
+    };
+
+    let project_dir = dest.path().join("now");
+    local_template(src.path(), &project_dir).generate().unwrap();
+    assert_ne!(mtime_of(&project_dir.join("assets/logo.svg")), mtime);
+
+    let project_dir = dest.path().join("preserved");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.preserve_timestamps = true;
+    template.generate().unwrap();
+    assert_eq!(mtime_of(&project_dir.join("assets/logo.svg")), mtime);
+  }
+
+  #[test]
+  fn test_expect_tree_sha() {
+    let src = template_dir(&[
+      ("template.toml", "[filters]\nexclude = []\n"),
+      ("README.md", "# Project\n"),
+    ]);
+    git::init_commit(src.path(), "Initial commit").unwrap();
+    let (tree, commit) = git::head_shas(src.path()).unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let generate = |name: &str, sha: &str| {
+      let mut template = local_template(src.path(), &dest.path().join(name));
+      template.template.expect_tree_sha = Some(sha.to_string());
+      template.generate()
+    };
+
+    assert!(generate("tree", &tree).is_ok());
+    assert!(generate("commit", &commit).is_ok());
+
+    // Abbreviated SHAs are refused.
+    let err = generate("abbreviated", &commit[..7]).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+
+    let err = generate("mismatch", &"0".repeat(40)).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::GitError);
+    assert!(!dest.path().join("mismatch/README.md").exists());
+
+    // Uncommitted changes don't match the tree.
+    fs::write(src.path().join("README.md"), "# Tampered\n").unwrap();
+    let err = generate("dirty", &tree).unwrap_err();
+    assert!(err.to_string().contains("uncommitted changes"), "{}", err);
+    assert!(!dest.path().join("dirty/README.md").exists());
+  }
+
+  #[test]
+  fn test_run_scripts() {
+    let src = template_dir(&[(
+      "template.toml",
+      "[filters]\nexclude = []\n\n[scripts]\ninstall = \"echo {{project-name}} > installed.txt\"\nlint = \"echo linted > linted.txt\"\ngreet = \"echo {{greeting}} > greeting.txt\"\n",
+    )]);
+    let dest = tempfile::tempdir().unwrap();
+
+    let project_dir = dest.path().join("my-project");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.run = vec!["install".to_string()];
+    template.generate().unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("installed.txt"))
+        .unwrap()
+        .trim(),
+      "my_project"
+    );
+    assert!(!project_dir.join("linted.txt").exists());
+
+    // Values are passed as a single word, instead of run by the shell.
+    #[cfg(unix)]
+    {
+      let project_dir = dest.path().join("quoted");
+      let mut template = local_template(src.path(), &project_dir);
+      template.set_variable("greeting", "a; touch pwned $(touch pwned)");
+      template.template.run = vec!["greet".to_string()];
+      template.generate().unwrap();
+      assert_eq!(
+        fs::read_to_string(project_dir.join("greeting.txt"))
+          .unwrap()
+          .trim(),
+        "a; touch pwned $(touch pwned)"
+      );
+      assert!(!project_dir.join("pwned").exists());
+    }
+
+    let project_dir = dest.path().join("unknown");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.run = vec!["test".to_string()];
+    let err = template.generate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+    assert!(err.to_string().contains("\"install\", \"lint\""));
+  }
+
+  #[test]
+  fn test_git_init_commit_message() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "initial_commit_message = \"Initial commit for {{project-name}}\"\n\n[filters]\nexclude = []\n",
+      ),
+      ("README.md", "# Project\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let head_message = |project_dir: &Path| {
+      let repo = git2::Repository::open(project_dir).unwrap();
+      let commit = repo.head().unwrap().peel_to_commit().unwrap();
+      commit.message().unwrap().to_string()
+    };
+
+    let project_dir = dest.path().join("my-project");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.git_init = true;
+    template.generate().unwrap();
+    assert_eq!(head_message(&project_dir), "Initial commit for my_project");
+
+    // `--commit-message` overrides the template's.
+    let project_dir = dest.path().join("other");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.git_init = true;
+    template.template.commit_message = Some("Start {{project-name}}".into());
+    template.generate().unwrap();
+    assert_eq!(head_message(&project_dir), "Start other");
+  }
+
+  #[test]
+  fn test_template_archive() {
+    let src = template_dir(&[("README.md.hbs", "# {{ project-name }}\n")]);
+    let mut archive = tar::Builder::new(Vec::new());
+    archive.append_dir_all(".", src.path()).unwrap();
+    let archive = archive.into_inner().unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    let options = TemplateOptions::from_tar(io::Cursor::new(archive)).unwrap();
+    let template_dir = options.path();
+    let project = ProjectInfo::new(&project_dir);
+    Template::new(&project, &options).generate().unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("README.md")).unwrap(),
+      "# my_project\n"
+    );
+
+    // The extracted template is removed once done.
+    drop(options);
+    assert!(!template_dir.exists());
+  }
+
+  #[test]
+  fn test_disabled_helpers() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[helpers]\ndisable = [\"replace\"]\n",
+      ),
+      ("upper.txt.hbs", "{{up project-name}}\n"),
+      ("replaced.txt.hbs", "{{replace project-name \"_\" \"-\"}}\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.keep_going = true;
+    let err = template.generate().unwrap_err();
+    assert!(err.to_string().contains("replaced.txt.hbs"), "{}", err);
+    assert!(!project_dir.join("replaced.txt").exists());
+
+    let err = Engine::Handlebars
+      .parse(
+        "{{replace a \"b\" \"c\"}}",
+        &template.variables(),
+        &template.config.helpers,
+      )
+      .unwrap_err();
+    assert!(err.to_string().contains("Helper not defined"), "{}", err);
+
+    // Enabled helpers still work.
+    assert_eq!(
+      fs::read_to_string(project_dir.join("upper.txt")).unwrap(),
+      "MY_PROJECT\n"
+    );
+  }
+
+  #[test]
+  fn test_rename_pattern() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"template.toml\"]\n\n[variables]\nname = \"app\"\n\n[rename]\n\"setup.py\" = \"setup.py\"\n\"*.py\" = \"{{name}}.py\"\n",
+      ),
+      ("setup.py", "\n"),
+      ("src/main.py", "\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    // First matching pattern wins.
+    let template = local_template(src.path(), &project_dir);
+    template.generate().unwrap();
+    assert!(project_dir.join("setup.py").exists());
+    assert!(project_dir.join("src/app.py").exists());
+    assert!(!project_dir.join("src/main.py").exists());
+
+    // Two files in `src` renamed to `app.py`.
+    fs::write(src.path().join("src/lib.py"), "\n").unwrap();
+    let project_dir = dest.path().join("collision");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.abort_on_warning = true;
+    let err = template.validate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Warning);
+  }
+
+  #[test]
+  fn test_keep_going() {
+    let src = template_dir(&[
+      ("broken.txt.hbs", "{{#if name}}\n"),
+      ("unclosed.md.hbs", "{{name\n"),
+      ("README.md", "# Project\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+
+    let project_dir = dest.path().join("project");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.keep_going = true;
+    let err = template.generate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Error);
+    assert!(err.to_string().contains("broken.txt.hbs"));
+
+    // Every failure is aggregated.
+    assert!(err.to_string().starts_with("2 failure(s):"), "{}", err);
+    let mut failed: Vec<&PathBuf> =
+      err.failures().iter().map(|f| &f.0).collect();
+    failed.sort();
+    assert_eq!(
+      failed,
+      vec![Path::new("broken.txt.hbs"), Path::new("unclosed.md.hbs")]
+    );
+    assert!(project_dir.join("README.md").exists());
+    assert!(!project_dir.join("broken.txt").exists());
+  }
+
+  #[test]
+  fn test_raw_files() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"template.toml\"]\n\n[raw]\nfiles = [\"assets/styles.css.hbs\", \"dist/**\"]\n",
+      ),
+      ("assets/styles.css.hbs", "{{color}}\n"),
+      ("dist/js/app.js.hbs", "{{app}}\n"),
+      ("README.md.hbs", "# Project\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let template = local_template(src.path(), &project_dir);
+    template.generate().unwrap();
+
+    let read = |path: &str| fs::read_to_string(project_dir.join(path)).unwrap();
+    assert_eq!(read("assets/styles.css.hbs"), "{{color}}\n");
+    assert_eq!(read("dist/js/app.js.hbs"), "{{app}}\n");
+    assert_eq!(read("README.md"), "# Project\n");
+  }
+
+  /// Engine writing the uppercase content of the template.
+  struct Upper;
+
+  impl TemplateEngine for Upper {
+    type Data = HashMap<String, String>;
+
+    fn render(&self, src: &Path, target: &Path, _: &Self::Data) -> Result<()> {
+      let content = fs::read_to_string(src)?;
+      fs::write(target, content.to_uppercase())?;
+      Ok(())
+    }
+  }
+
+  #[test]
+  fn test_custom_engine() {
+    let src =
+      template_dir(&[("README.md.foo", "# project\n"), ("LICENSE", "MIT\n")]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.register_engine("foo", Box::new(Upper));
+    template.generate().unwrap();
+
+    let read = |path: &str| fs::read_to_string(project_dir.join(path)).unwrap();
+    assert_eq!(read("README.md"), "# PROJECT\n");
+    assert_eq!(read("LICENSE"), "MIT\n");
+  }
+
+  #[test]
+  fn test_multiple_config_files() {
+    let src = template_dir(&[
+      ("template.toml", "[filters]\nexclude = []\n"),
+      (".config/template.toml", "[filters]\nexclude = []\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+
+    let template = local_template(src.path(), &dest.path().join("project"));
+    let err = template.validate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+  }
+
+  /// Commits `file` with `content` onto `branch` of a non-bare `repo`.
+  fn commit(repo: &git2::Repository, branch: &str, file: &str, content: &str) {
+    fs::write(repo.workdir().unwrap().join(file), content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(file)).unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+
+    let sig = git2::Signature::now("tester", "tester@example.com").unwrap();
+    let parent = repo.head().and_then(|h| h.peel_to_commit()).ok();
+    let parents: Vec<_> = parent.iter().collect();
+    let refname = format!("refs/heads/{}", branch);
+    repo
+      .commit(Some(&refname), &sig, &sig, "commit", &tree, &parents)
+      .unwrap();
+  }
+
+  #[test]
+  fn test_lock_remote_template() {
+    let origin = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init(origin.path()).unwrap();
+    commit(&repo, "master", "README.md", "# {{ name }}\n");
+    repo.set_head("refs/heads/master").unwrap();
+    commit(&repo, "other", "README.md", "# Other {{ name }}\n");
+
+    let cache = tempfile::tempdir().unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("locked");
+    let remote = |branch: Option<&str>| {
+      let url = url::Url::from_file_path(origin.path()).unwrap();
+      let mut opts = GitOptions::new(url, branch.map(String::from));
+      opts.set_cache_dir(cache.path());
+      let project = ProjectInfo::new(&project_dir);
+      Template::new(&project, &TemplateOptions::Remote(opts))
+    };
+
+    // Record the commit `master` resolved to.
+    let mut template = remote(None);
+    template.template.write_lock = true;
+    template.generate().unwrap();
+    let lock = lock::TemplateLock::read(&project_dir).unwrap();
+    assert_eq!(
+      lock.commit,
+      repo.revparse_single("master").unwrap().id().to_string()
+    );
+    assert!(!project_dir.join(".git").join("HEAD").exists());
+
+    // Same commit is accepted.
+    let mut template = remote(None);
+    template.template.locked = true;
+    template.template.assume_yes = true;
+    assert!(template.generate().is_ok());
+
+    // A different commit is rejected.
+    let mut template = remote(Some("other"));
+    template.template.locked = true;
+    template.template.assume_yes = true;
+    let err = template.generate().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::GitError);
+  }
+
+  #[test]
+  fn test_git_default_branch() {
+    let origin = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init(origin.path()).unwrap();
+    let config = "[git]\ndefault_branch = \"main\"\n\n[filters]\nexclude = [\"template.toml\"]\n";
+    commit(&repo, "master", "template.toml", config);
+    repo.set_head("refs/heads/master").unwrap();
+    commit(&repo, "master", "README.md", "# Master\n");
+    commit(&repo, "main", "README.md", "# Main\n");
+
+    let cache = tempfile::tempdir().unwrap();
+    let dest = tempfile::tempdir().unwrap();
+    let generate = |name: &str, branch: Option<&str>| {
+      let url = url::Url::from_file_path(origin.path()).unwrap();
+      let mut opts = GitOptions::new(url, branch.map(String::from));
+      opts.set_cache_dir(cache.path());
+      let project_dir = dest.path().join(name);
+      let project = ProjectInfo::new(&project_dir);
+      Template::new(&project, &TemplateOptions::Remote(opts))
+        .generate()
+        .unwrap();
+      fs::read_to_string(project_dir.join("README.md")).unwrap()
+    };
+
+    // The declared branch is used, unless another one is given.
+    assert_eq!(generate("declared", None), "# Main\n");
+    assert_eq!(generate("given", Some("master")), "# Master\n");
+  }
+
+  #[test]
+  fn test_extends_base_template() {
+    let base = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"template.toml\"]\n\n[variables]\nlicense = \"MIT\"\n",
+      ),
+      ("README.md", "# Base\n"),
+      ("LICENSE.hbs", "{{ license }}\n"),
+    ]);
+    let child = template_dir(&[
+      (
+        "template.toml",
+        &format!(
+          "extends = {:?}\n\n[filters]\nexclude = []\n\n[variables]\nlicense = \"Apache-2.0\"\n",
+          base.path()
+        ),
+      ),
+      ("README.md", "# Child\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    local_template(child.path(), &project_dir)
+      .generate()
+      .unwrap();
+
+    assert_eq!(
+      fs::read_to_string(project_dir.join("README.md")).unwrap(),
+      "# Child\n"
+    );
+    assert_eq!(
+      fs::read_to_string(project_dir.join("LICENSE")).unwrap(),
+      "Apache-2.0\n"
+    );
+    // Filters of the base template apply to the child.
+    assert!(!project_dir.join("template.toml").exists());
+  }
+
+  #[test]
+  fn test_extends_run_options() {
+    let base = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = [\"template.toml\"]\n\n[variables]\nlicense = \"MIT\"\n",
+      ),
+      ("LICENSE.hbs", "{{ license }}\n"),
+      ("broken.txt.hbs", "{{#if name}}\n"),
+      ("NOTICE.foo", "notice\n"),
+    ]);
+    let child = template_dir(&[(
+      "template.toml",
+      &format!("extends = {:?}\n\n[filters]\nexclude = []\n", base.path()),
+    )]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    let mut args = Arguments::new(
+      &project_dir.to_string_lossy(),
+      &child.path().to_string_lossy(),
+      None,
+    )
+    .unwrap();
+    args.keep_going = true;
+    args
+      .defines
+      .insert("license".to_string(), "Apache-2.0".to_string());
+    let mut template = Template::from(&args);
+    template.register_engine("foo", Box::new(Upper));
+
+    // The base template's files are generated with the options & overrides.
+    let err = template.generate().unwrap_err();
+    let failed: Vec<&PathBuf> = err.failures().iter().map(|f| &f.0).collect();
+    assert_eq!(failed, vec![Path::new("broken.txt.hbs")]);
+    assert_eq!(
+      fs::read_to_string(project_dir.join("LICENSE")).unwrap(),
+      "Apache-2.0\n"
+    );
+    assert_eq!(
+      fs::read_to_string(project_dir.join("NOTICE")).unwrap(),
+      "NOTICE\n"
+    );
+
+    // So are the variables set afterwards.
+    let project_dir = dest.path().join("other-project");
+    args.project = ProjectInfo::new(&project_dir);
+    let mut template = Template::from(&args);
+    template.set_variable("license", "BSD-3-Clause");
+    template.generate().unwrap_err();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("LICENSE")).unwrap(),
+      "BSD-3-Clause\n"
+    );
+  }
+
+  #[test]
+  fn test_extends_from_nested_config() {
+    let dir = template_dir(&[
+      ("base/LICENSE", "MIT\n"),
+      (
+        "child/.project/template.toml",
+        "extends = \"../../base\"\n\n[filters]\nexclude = []\n",
+      ),
+      ("child/README.md", "# Child\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    local_template(&dir.path().join("child"), &project_dir)
+      .generate()
+      .unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("LICENSE")).unwrap(),
+      "MIT\n"
+    );
+  }
+
+  #[test]
+  fn test_extends_cycle() {
+    let base = template_dir(&[]);
+    let child = template_dir(&[(
+      "template.toml",
+      &format!("extends = {:?}\n\n[filters]\nexclude = []\n", base.path()),
+    )]);
+    fs::write(
+      base.path().join("template.toml"),
+      format!("extends = {:?}\n\n[filters]\nexclude = []\n", child.path()),
+    )
+    .unwrap();
+
+    let project = ProjectInfo::new(&base.path().join("my-project"));
+    let options = TemplateOptions::Local(child.path().to_path_buf());
+    let err = TemplateMeta::load(
+      &project,
+      &options,
+      config::TEMPLATE_FILE,
+      false,
+      Verbosity::Normal,
+      &mut Vec::new(),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+  }
+
+  #[test]
+  fn test_builtin_variables_without_config() {
+    let src = template_dir(&[("README.md.hbs", "# {{ project-name }}\n")]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+
+    local_template(src.path(), &project_dir).generate().unwrap();
+
+    assert_eq!(
+      fs::read_to_string(project_dir.join("README.md")).unwrap(),
+      "# my_project\n"
+    );
+  }
+
+  #[test]
+  fn test_confirm() {
+    let src = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[variables]\ndb = \"postgres\"\n",
+      ),
+      ("README.md.hbs", "# {{db}}\n"),
+    ]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("project");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.confirm = true;
+
+    // Nothing is written when it's not confirmed.
+    let mut output = Vec::new();
+    let mut input = io::Cursor::new("n\n");
+    let err = template
+      .generate_with(&project_dir, &mut input, &mut output, true)
+      .unwrap_err();
+    assert!(err.to_string().contains("Generation aborted"));
+    assert!(fs::read_dir(&project_dir).unwrap().next().is_none());
+    let output =
+      console::strip_ansi_codes(std::str::from_utf8(&output).unwrap())
+        .to_string();
+    assert!(output.contains("  db = \"postgres\"\n"), "{}", output);
+    assert!(output.contains("Files: 1\n"), "{}", output);
+
+    // It's never waited for without a terminal.
+    let mut input = io::Cursor::new("y\n");
+    let err = template
+      .generate_with(&project_dir, &mut input, &mut io::sink(), false)
+      .unwrap_err();
+    assert!(err.to_string().contains("needs a terminal"));
+    assert_eq!(input.position(), 0);
+
+    template
+      .generate_with(&project_dir, &mut input, &mut io::sink(), true)
+      .unwrap();
+    assert!(project_dir.join("README.md").exists());
+  }
+
+  #[test]
+  fn test_overwrite() {
+    let src = template_dir(&[("README.md", "# New\n")]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+    fs::create_dir_all(&project_dir).unwrap();
+    fs::write(project_dir.join("README.md"), "# Old\n").unwrap();
+
+    // The library doesn't wait for an answer.
+    let mut template = local_template(src.path(), &project_dir);
+    let mut input = io::Cursor::new("y\n");
+    let err = template
+      .generate_with(&project_dir, &mut input, &mut io::sink(), true)
+      .unwrap_err();
+    assert!(err.to_string().contains("not overwriting it"), "{}", err);
+    assert_eq!(input.position(), 0);
+
+    // The command line asks first.
+    template.template.ask_overwrite = true;
+    template
+      .generate_with(&project_dir, &mut input, &mut io::sink(), true)
+      .unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("README.md")).unwrap(),
+      "# New\n"
+    );
+  }
+
+  #[test]
+  fn test_assume_yes_overwrites() {
+    let src = template_dir(&[("README.md", "# New\n")]);
+    let dest = tempfile::tempdir().unwrap();
+    let project_dir = dest.path().join("my-project");
+    fs::create_dir_all(&project_dir).unwrap();
+    fs::write(project_dir.join("README.md"), "# Old\n").unwrap();
+
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.assume_yes = true;
+
+    // The answer isn't read.
+    let mut input = io::Cursor::new("n\n");
+    let confirmed =
+      template.confirm_overwrite(&project_dir, &mut input, &mut io::sink());
+    assert!(confirmed.unwrap());
+    assert_eq!(input.position(), 0);
+
+    template.generate().unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("README.md")).unwrap(),
+      "# New\n"
+    );
+  }
+
+  #[test]
+  fn test_keep_template_toml() {
+    let config =
+      "[filters]\nexclude = []\n\n[variables]\nauthor = \"{{author-email}}\"\n";
+    let src = template_dir(&[("template.toml", config), ("README.md", "")]);
+    let dest = tempfile::tempdir().unwrap();
+
+    // Excluded by default.
+    let project_dir = dest.path().join("default");
+    local_template(src.path(), &project_dir).generate().unwrap();
+    assert!(project_dir.join("README.md").exists());
+    assert!(!project_dir.join("template.toml").exists());
+
+    // Copied as written.
+    let project_dir = dest.path().join("kept");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.keep_template_toml = true;
+    template.generate().unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("template.toml")).unwrap(),
+      config
+    );
+
+    // Without the sections meant for the template's author.
+    fs::write(
+      src.path().join("template.toml"),
+      format!(
+        "{}\n[validate]\nauthor = \".*\"\n\n[scripts]\ndeploy = \"./deploy.sh\"\n",
+        config
+      ),
+    )
+    .unwrap();
+    let project_dir = dest.path().join("sanitized");
+    let mut template = local_template(src.path(), &project_dir);
+    template.template.keep_template_toml = true;
+    template.generate().unwrap();
+    assert_eq!(
+      fs::read_to_string(project_dir.join("template.toml")).unwrap(),
+      config
+    );
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn test_symlink_target_placeholder() {
+    let src = template_dir(&[("docs/guide.md", "# Guide\n")]);
+    std::os::unix::fs::symlink(
+      "docs/{{project-name}}.md",
+      src.path().join("GUIDE.md"),
+    )
+    .unwrap();
+    let dest = tempfile::tempdir().unwrap();
+
+    let project_dir = dest.path().join("my-project");
+    local_template(src.path(), &project_dir).generate().unwrap();
+    assert_eq!(
+      fs::read_link(project_dir.join("GUIDE.md")).unwrap(),
+      PathBuf::from("docs/my_project.md")
+    );
+
+    // Targets escaping the project directory.
+    std::os::unix::fs::symlink(
+      "../../{{project-name}}.secrets",
+      src.path().join("docs/escape"),
+    )
+    .unwrap();
+    let project_dir = dest.path().join("escape");
+    assert!(local_template(src.path(), &project_dir).generate().is_err());
+    assert!(fs::symlink_metadata(project_dir.join("docs/escape")).is_err());
   }
 }
@@ -0,0 +1,347 @@
+//! Generate a project into a tar archive instead of a directory, i.e.
+//! `--archive`.
+//!
+//! ```sh
+//! $ project --archive my-project.tar.gz new path/to/template my-project
+//! ```
+//!
+use crate::{
+  error::{self, Error, ErrorKind, Result},
+  template::{
+    config, engine::Engine, strip_lines, ActionKind, GenerationStats,
+    PlannedAction, Template,
+  },
+};
+
+use filetime::FileTime;
+use flate2::{write::GzEncoder, Compression};
+use tar::{Builder, EntryType, Header, HeaderMode};
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  fs::{self, File},
+  io::{self, Write},
+  path::{Path, PathBuf},
+  time::Instant,
+};
+
+/// Whether the tar `archive` is gzipped, given its extension, or an error if
+/// it isn't a `.tar`, `.tar.gz` or `.tgz` file.
+pub(crate) fn gzipped(archive: &Path) -> Result<bool> {
+  let name = archive.to_string_lossy();
+  if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+    Ok(true)
+  } else if name.ends_with(".tar") {
+    Ok(false)
+  } else {
+    Err(Error::new(
+      ErrorKind::Config,
+      &format!(
+        "unsupported archive {:?}, expected a .tar, .tar.gz or .tgz file",
+        archive
+      ),
+    ))
+  }
+}
+
+impl Template {
+  /// Generate the project into the tar archive `archive` instead of its
+  /// directory, i.e. `--archive`. It's gzipped if its extension is `.tar.gz`
+  /// or `.tgz`.
+  ///
+  /// Files are rendered in memory and stored under the project's directory
+  /// name, keeping their executable bit, so nothing is written but the
+  /// archive.
+  pub fn archive(&self, archive: &Path) -> Result<GenerationStats> {
+    let result =
+      gzipped(archive).and_then(|gzip| self.archive_into(archive, gzip));
+
+    // The project's directory is created when its name is parsed, and is
+    // left empty whether or not archiving succeeded, unless it already
+    // existed.
+    if self.project_info.created {
+      let _ = fs::remove_dir(&self.project_info.path);
+    }
+
+    let stats = result?;
+    // Nothing to do if stdout is closed.
+    let _ = self.write_summary_of(&stats, archive, &mut io::stdout());
+
+    Ok(stats)
+  }
+
+  /// Validate the template & write the project into the tar `archive`,
+  /// gzipped with `gzip`.
+  fn archive_into(
+    &self,
+    archive: &Path,
+    gzip: bool,
+  ) -> Result<GenerationStats> {
+    self.validate()?;
+    for warning in &self.warnings {
+      error::warn(warning, self.abort_on_warning)?;
+    }
+
+    let file = File::create(archive)?;
+    if gzip {
+      let (stats, encoder) =
+        self.write_archive(GzEncoder::new(file, Compression::default()))?;
+      encoder.finish()?;
+      Ok(stats)
+    } else {
+      Ok(self.write_archive(file)?.0)
+    }
+  }
+
+  /// Write the project as a tar archive into `writer`, which is returned
+  /// once the archive is complete.
+  fn write_archive<W: Write>(&self, writer: W) -> Result<(GenerationStats, W)> {
+    let project_dir = &self.project_info.path;
+    let prefix = PathBuf::from(project_dir.file_name().unwrap_or_default());
+
+    let start = Instant::now();
+    let mut stats = GenerationStats {
+      project_dir: project_dir.to_path_buf(),
+      ..GenerationStats::default()
+    };
+    let mut builder = Builder::new(writer);
+    self.plan_files(
+      project_dir,
+      None,
+      &mut |template, action, _, variables| {
+        template.append(&mut builder, action, variables, &prefix, &mut stats)
+      },
+    )?;
+    stats.elapsed = start.elapsed();
+
+    Ok((stats, builder.into_inner()?))
+  }
+
+  /// Render the project's files into memory, by path relative to the
+  /// project's directory, like they're rendered into an archive. Directories
+  /// & symbolic links are left out.
+  pub(crate) fn render_in_memory(&self) -> Result<BTreeMap<PathBuf, Vec<u8>>> {
+    self.validate()?;
+
+    let project_dir = &self.project_info.path;
+    let mut files = BTreeMap::new();
+    self.plan_files(
+      project_dir,
+      None,
+      &mut |template, action, _, variables| {
+        let target = match (&action.kind, &action.target) {
+          (ActionKind::Copy, Some(target))
+          | (ActionKind::Render, Some(target)) => target,
+          _ => return Ok(()),
+        };
+        let src = template.template_options.path().join(&action.source);
+        let variables =
+          variables.cloned().unwrap_or_else(|| template.variables());
+        let content = template.render_bytes(&src, &variables)?;
+        files.insert(target.strip_prefix(project_dir)?.to_path_buf(), content);
+        Ok(())
+      },
+    )?;
+
+    Ok(files)
+  }
+
+  /// Append the file of a planned `action` to the archive, under `prefix`.
+  fn append<W: Write>(
+    &self,
+    builder: &mut Builder<W>,
+    action: &PlannedAction,
+    variables: Option<&HashMap<String, String>>,
+    prefix: &Path,
+    stats: &mut GenerationStats,
+  ) -> Result<()> {
+    let target = match (&action.kind, &action.target) {
+      (ActionKind::Skip(reason), _) => {
+        stats.skipped.push((action.source.clone(), reason.clone()));
+        return Ok(());
+      }
+      (_, Some(target)) => target,
+      (_, None) => return Ok(()),
+    };
+    let project_dir = &self.project_info.path;
+    let path = prefix.join(target.strip_prefix(project_dir)?);
+
+    // Only the executable bit & modification time of the template's files
+    // are kept, not their owner.
+    let src = self.template_options.path().join(&action.source);
+    let metadata = fs::symlink_metadata(&src)?;
+    let mut header = Header::new_gnu();
+    header.set_metadata_in_mode(&metadata, HeaderMode::Deterministic);
+    header.set_mtime(
+      FileTime::from_last_modification_time(&metadata).unix_seconds() as u64,
+    );
+
+    match action.kind {
+      ActionKind::Create => {
+        header.set_size(0);
+        builder.append_data(&mut header, &path, io::empty())?;
+      }
+      ActionKind::Link => {
+        let link = self.link_target(&src, target, project_dir)?;
+        header.set_entry_type(EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, &path, link)?;
+      }
+      _ => {
+        let variables = variables.cloned().unwrap_or_else(|| self.variables());
+        let content = self.render_bytes(&src, &variables)?;
+        header.set_size(content.len() as u64);
+        builder.append_data(&mut header, &path, content.as_slice())?;
+      }
+    }
+    stats.generated.insert(target.to_path_buf());
+
+    Ok(())
+  }
+
+  /// Render the file `src` with the given `variables` into memory, like
+  /// `substitute_with` renders it into a file.
+  fn render_bytes(
+    &self,
+    src: &Path,
+    variables: &HashMap<String, String>,
+  ) -> Result<Vec<u8>> {
+    if src == self.config_file() {
+      return Ok(config::sanitize(&fs::read_to_string(src)?)?.into_bytes());
+    }
+    if self.is_raw(src) {
+      return Ok(fs::read(src)?);
+    }
+
+    // Custom engines & `cargo-generate` templates only render into files.
+    if self.has_custom_engine(src) || self.cargo_generate {
+      let dir = tempfile::tempdir()?;
+      let dest = dir.path().join(src.file_name().unwrap_or_default());
+      let written = self.substitute_with(src, &dest, variables)?;
+      return Ok(fs::read(written)?);
+    }
+
+    self.trace_render(src, variables);
+    let engine = self.engine_for(src);
+    if let Engine::None = engine {
+      return Ok(fs::read(src)?);
+    }
+    if let Some(content) = self.render_front_matter(src, engine, variables)? {
+      return Ok(content.into_bytes());
+    }
+    let mut content = fs::read_to_string(src)?;
+    if self.config.trim_blocks {
+      content = engine.trim_blocks(&content);
+    }
+
+    let content = engine.parse(&content, variables, &self.config.helpers)?;
+    match &self.config.strip_lines {
+      Some(pattern) => Ok(strip_lines(&content, pattern).into_bytes()),
+      None => Ok(content.into_bytes()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    info::ProjectInfo,
+    template::tests::{local_template, template_dir},
+  };
+
+  use flate2::read::GzDecoder;
+  use pretty_assertions::assert_eq;
+
+  use std::{fs, io::Read, path::PathBuf};
+
+  #[test]
+  fn test_archive() {
+    let template = template_dir(&[
+      (
+        "template.toml",
+        "[filters]\nexclude = []\n\n[rename]\nsrc = \"{{project-name}}\"\n",
+      ),
+      ("README.md.hbs", "# {{project-name}}\n"),
+      ("src/run.sh", "#!/bin/sh\n"),
+    ]);
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      let run = template.path().join("src/run.sh");
+      fs::set_permissions(run, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let project_dir = dir.path().join("my-project");
+    let archive = dir.path().join("my-project.tar.gz");
+    let stats = local_template(template.path(), &project_dir)
+      .archive(&archive)
+      .unwrap();
+    assert_eq!(stats.generated.len(), 2);
+
+    // Only the archive is written.
+    assert!(!project_dir.exists());
+
+    let mut entries = Vec::new();
+    let gz = GzDecoder::new(fs::File::open(&archive).unwrap());
+    for entry in tar::Archive::new(gz).entries().unwrap() {
+      let mut entry = entry.unwrap();
+      let mut content = String::new();
+      entry.read_to_string(&mut content).unwrap();
+      let mode = entry.header().mode().unwrap();
+      entries.push((entry.path().unwrap().into_owned(), content, mode));
+    }
+    entries.sort();
+
+    let executable = if cfg!(unix) { 0o755 } else { 0o644 };
+    assert_eq!(
+      entries,
+      vec![
+        (
+          PathBuf::from("my-project/README.md"),
+          "# my_project\n".to_string(),
+          0o644
+        ),
+        (
+          PathBuf::from("my-project/my_project/run.sh"),
+          "#!/bin/sh\n".to_string(),
+          executable
+        ),
+      ]
+    );
+
+    // The project's directory is removed when archiving fails too.
+    let err = local_template(template.path(), &project_dir)
+      .archive(&dir.path().join("my-project.zip"))
+      .unwrap_err();
+    assert!(err.to_string().contains("unsupported archive"));
+    assert!(!project_dir.exists());
+
+    let broken = template_dir(&[(
+      "template.toml",
+      "[filters]\nexclude = []\n\n[rename]\nsrc = \"{{#if}}\"\n",
+    )]);
+    let archive = dir.path().join("broken.tar");
+    assert!(local_template(broken.path(), &project_dir)
+      .archive(&archive)
+      .is_err());
+    assert!(!project_dir.exists());
+  }
+
+  #[test]
+  fn test_archive_keeps_existing_directory() {
+    let template = template_dir(&[("README.md", "# Project\n")]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let project_dir = dir.path().join("my-project");
+    fs::create_dir(&project_dir).unwrap();
+    assert!(!ProjectInfo::new(&project_dir).created);
+    let archive = dir.path().join("my-project.tar.gz");
+    local_template(template.path(), &project_dir)
+      .archive(&archive)
+      .unwrap();
+
+    // The directory was there before the run, so it's left alone.
+    assert!(project_dir.is_dir());
+  }
+}
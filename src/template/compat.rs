@@ -0,0 +1,86 @@
+//! Compatibility with [`cargo-generate`] templates.
+//!
+//! `cargo-generate` uses [Tera]-style filters e.g `{{ project-name | kebab_case }}`
+//! which are converted into the equivalent Handlebars helper calls e.g
+//! `{{kebab project-name}}`. The supported filters are:
+//!
+//! - `kebab_case` - converted to the `kebab` helper.
+//! - `snake_case` - converted to the `snake` helper.
+//! - `pascal_case` - converted to the `pascal` helper.
+//! - `title_case` - converted to the `title` helper.
+//!
+//! The `cargo-generate` built-in placeholders `{{project-name}}`, `{{crate_name}}`
+//! and `{{authors}}` are also provided.
+//!
+//! Unsupported `cargo-generate` features:
+//!
+//! - Any other Tera filter (e.g `upper_camel_case`, `shouty_snake_case`) or chained
+//!   filters. Such expressions are left as-is and will fail to render.
+//! - Tera tags such as `{% if %}`, `{% for %}` or `{% raw %}`.
+//! - The `cargo-generate.toml` configuration file (placeholders, prompts, conditionals).
+//!   It is copied over like any other file.
+//! - `.genignore` files.
+//! - The `{{os-arch}}` and `{{within_cargo_project}}` placeholders.
+//!
+//! [`cargo-generate`]: https://github.com/cargo-generate/cargo-generate
+//! [Tera]: https://tera.netlify.app/docs/#filters
+//!
+use crate::error::Result;
+
+use regex::{Captures, Regex};
+
+/// Tera filters and their equivalent Handlebars helpers.
+const FILTERS: &[(&str, &str)] = &[
+  ("kebab_case", "kebab"),
+  ("snake_case", "snake"),
+  ("pascal_case", "pascal"),
+  ("title_case", "title"),
+];
+
+/// Convert `{{ var | filter }}` expressions in `content` into Handlebars
+/// `{{filter var}}` helper calls.
+///
+/// Unsupported filters are left untouched.
+pub(crate) fn cargo_generate(content: &str) -> Result<String> {
+  let result = Regex::new(r"\{\{\s*([\w-]+)\s*\|\s*(\w+)\s*\}\}")?.replace_all(
+    content,
+    |caps: &Captures| match FILTERS
+      .iter()
+      .find(|(filter, _)| *filter == &caps[2])
+    {
+      Some((_, helper)) => format!("{{{{{} {}}}}}", helper, &caps[1]),
+      None => caps[0].to_string(),
+    },
+  );
+
+  Ok(result.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_cargo_generate() {
+    let content = r#"
+name = "{{ project-name | kebab_case }}"
+module = "{{crate_name|snake_case}}"
+struct = "{{ project-name | pascal_case }}"
+title = "{{ project-name | title_case }}"
+plain = "{{ project-name }}"
+unknown = "{{ project-name | upper_camel_case }}"
+"#;
+
+    let expected = r#"
+name = "{{kebab project-name}}"
+module = "{{snake crate_name}}"
+struct = "{{pascal project-name}}"
+title = "{{title project-name}}"
+plain = "{{ project-name }}"
+unknown = "{{ project-name | upper_camel_case }}"
+"#;
+
+    assert_eq!(cargo_generate(content).unwrap(), expected);
+  }
+}
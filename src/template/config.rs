@@ -1,39 +1,197 @@
 #![allow(dead_code)]
 
-use std::{collections::HashMap, path::Path};
+use std::{
+  collections::{BTreeSet, HashMap},
+  fs,
+  io::{BufRead, Write},
+  path::{Path, PathBuf},
+};
 
 use console::style;
+use indexmap::IndexMap;
+use regex::Regex;
 use serde::Deserialize;
 
-use crate::{emoji, error::Result, template::parser, Error, ErrorKind};
+use crate::{
+  defaults::{self, Defaults},
+  emoji,
+  error::Result,
+  logger,
+  template::{engine::Engine, helpers, parser, prompt, Verbosity},
+  Error, ErrorKind,
+};
 
-/// Default template file containing variable template substitution.
+/// Default template file containing variable template substitution, unless
+/// another name is given with `--config-name`.
 pub(crate) const TEMPLATE_FILE: &str = "template.toml";
 
+/// Directories, relative to the template's base directory, searched in order
+/// for the config file, i.e. [`TEMPLATE_FILE`] by default.
+///
+/// [`TEMPLATE_FILE`]: constant.TEMPLATE_FILE
+pub(crate) const SEARCH_PATHS: &[&str] = &["", ".project", ".config"];
+
+/// Directories left out of templates without a config file, matched by their
+/// exact name only, e.g. `.git` doesn't match a `.gitignore` file.
+pub(crate) const DEFAULT_EXCLUDES: &[&str] =
+  &["venv", ".git", ".idea", ".vscode"];
+
+/// Keys of the config file, with the keys of their table if it isn't a
+/// free-form map, e.g. `[filters]` but not `[variables]`.
+const KNOWN_KEYS: &[(&str, Option<&[&str]>)] = &[
+  ("variables", None),
+  ("filters", Some(&["include", "exclude"])),
+  ("rename", None),
+  ("move", None),
+  ("raw", Some(&["files"])),
+  ("render", Some(&["include", "keep_extension"])),
+  ("fanout", Some(&["src", "over", "name"])),
+  ("prompts", None),
+  ("validate", None),
+  ("scripts", None),
+  ("next_steps", None),
+  ("extends", None),
+  ("keep_template_toml", None),
+  ("helpers", Some(&["enable", "disable"])),
+  ("trim_blocks", None),
+  ("large_file_size", None),
+  ("default_engine", None),
+  ("git", Some(&["default_branch"])),
+  ("initial_commit_message", None),
+  ("strip_lines_matching", None),
+];
+
+/// Keys of the config file left out of the copy kept in the project, see
+/// [`sanitize`], since they're only meant for the template's author.
+///
+/// [`sanitize`]: fn.sanitize.html
+const PRIVATE_KEYS: &[&str] = &["scripts", "validate", "extends", "git"];
+
+/// Times a variable is asked for before giving up on a valid answer.
+const PROMPT_ATTEMPTS: usize = 3;
+
+/// Project-level defaults of template variables, see [`load_defaults`].
+///
+/// [`load_defaults`]: struct.TemplateConfig.html#method.load_defaults
+pub(crate) const PROJECT_DEFAULTS_FILE: &str = ".project-defaults.toml";
+
 #[derive(Debug, Deserialize)]
 pub(crate) struct TemplateConfig {
   /// Replace these variable keys with their value in template files.
+  #[serde(default, deserialize_with = "without_overlays")]
   pub(crate) variables: Option<HashMap<String, String>>,
+  /// Variables of an environment, e.g. `[variables.prod]`, merged over the
+  /// `[variables]` when it's selected with `--env`.
+  #[serde(skip)]
+  pub(crate) overlays: HashMap<String, HashMap<String, String>>,
   /// The files you want to include as template.
   pub(crate) filters: Option<Filters>,
-  /// Files or folders to rename.
-  pub(crate) rename: Option<HashMap<String, String>>,
+  /// Files or folders to rename, by name or by glob pattern e.g `"*.py"`.
+  /// The first matching entry is used.
+  pub(crate) rename: Option<IndexMap<String, String>>,
+  /// Files to move, from their path in the template to their path in the
+  /// generated project.
+  #[serde(rename = "move")]
+  pub(crate) moves: Option<HashMap<String, String>>,
+  /// Files copied over as-is without rendering.
+  pub(crate) raw: Option<Raw>,
+  /// How files are rendered.
+  pub(crate) render: Option<Render>,
+  /// Directories generated once per item of a list variable.
+  pub(crate) fanout: Option<Vec<Fanout>>,
+  /// Variables to ask for in interactive mode, with their default values.
+  pub(crate) prompts: Option<HashMap<String, String>>,
+  /// Regex patterns the values of variables must match.
+  pub(crate) validate: Option<HashMap<String, String>>,
+  /// Shell commands run in the generated project when named with `--run`,
+  /// rendered with the template's variables.
+  pub(crate) scripts: Option<IndexMap<String, String>>,
+  /// Steps printed once the project is generated, rendered with the
+  /// template's variables, as a list or one step per line.
+  pub(crate) next_steps: Option<NextSteps>,
+  /// Base template this template is generated on top of, as a path relative
+  /// to the template's base directory or a remote template.
+  pub(crate) extends: Option<String>,
+  /// Copy the `template.toml` file into the generated project, as written
+  /// i.e. with its built-in placeholders such as `{{author-email}}` left
+  /// unresolved. It's excluded by default.
+  #[serde(default)]
+  pub(crate) keep_template_toml: bool,
+  /// Built-in Handlebars helpers to register.
+  #[serde(default)]
+  pub(crate) helpers: Helpers,
+  /// Drop the lines of rendered files holding nothing but a block tag, e.g.
+  /// `{{#if license}}` or `{% endif %}`, instead of leaving blank lines.
+  #[serde(default)]
+  pub(crate) trim_blocks: bool,
+  /// Size in bytes from which copied files are copied in chunks, reporting
+  /// their progress. Defaults to [`LARGE_FILE_SIZE`].
+  ///
+  /// [`LARGE_FILE_SIZE`]: ../copy/constant.LARGE_FILE_SIZE.html
+  pub(crate) large_file_size: Option<u64>,
+  /// Engine of the files rendered without a template extension, i.e. in
+  /// `[render] include`, and of the rendered paths e.g. `[rename]` values:
+  /// `"handlebars"` by default, or `"liquid"`.
+  pub(crate) default_engine: Option<Engine>,
+  /// Settings of the template when it's cloned, i.e. the `[git]` clause.
+  pub(crate) git: Option<GitConfig>,
+  /// Message of the commit made with `--git-init`, rendered with the
+  /// template's variables e.g `"Initial commit for {{project-name}}"`.
+  pub(crate) initial_commit_message: Option<String>,
+  /// Regex of the lines dropped from rendered files, e.g.
+  /// `"^#\\s*template-only"` for notes to the template's authors. Files can
+  /// override it in their front matter.
+  pub(crate) strip_lines_matching: Option<String>,
+  /// `strip_lines_matching` compiled once, or `None` if it's unset or
+  /// invalid, which validating the template reports.
+  #[serde(skip)]
+  pub(crate) strip_lines: Option<Regex>,
+  /// Warnings raised while parsing the configuration.
+  #[serde(skip)]
+  pub(crate) warnings: Vec<String>,
+  /// Unknown keys of the config file, e.g. a misspelled `[filtres]`, which
+  /// are ignored unless running with `--template-toml-strict`.
+  #[serde(skip)]
+  pub(crate) unknown_keys: Vec<String>,
+  /// Directory the configuration was found in, which its relative paths,
+  /// e.g. `extends`, are resolved from. Defaults to the template's base
+  /// directory.
+  #[serde(skip)]
+  pub(crate) dir: PathBuf,
+  /// Name of the config file, i.e. [`TEMPLATE_FILE`] or `--config-name`.
+  ///
+  /// [`TEMPLATE_FILE`]: constant.TEMPLATE_FILE
+  #[serde(skip)]
+  pub(crate) file_name: String,
 }
 
 impl TemplateConfig {
-  /// Create & parse the `"template.toml"` file in the project base directory.
-  pub(crate) fn new(template_dir: &Path, project_name: &str) -> TemplateConfig {
-    match Self::parse(&template_dir, project_name) {
-      Ok(config) => config,
+  /// Create & parse the `file_name` config file, e.g `"template.toml"`, in the
+  /// project base directory. Falling back to the default configuration is
+  /// reported according to the `verbosity`.
+  pub(crate) fn new(
+    template_dir: &Path,
+    project_name: &str,
+    file_name: &str,
+    verbosity: Verbosity,
+  ) -> TemplateConfig {
+    match Self::discover(template_dir, project_name, file_name) {
+      Ok((config, _)) => config,
       Err(err) if err.kind() == &ErrorKind::NotFound => {
-        eprintln!(
-          "{} {}",
-          emoji::SHRUG,
-          style("Using default template configurations")
-            .bold()
-            .yellow()
-        );
-        TemplateConfig::default()
+        if verbosity.shows_progress() && !logger::is_json() {
+          eprintln!(
+            "{} {}",
+            emoji::SHRUG,
+            style("Using default template configurations")
+              .bold()
+              .yellow()
+          );
+        }
+        TemplateConfig {
+          dir: template_dir.to_path_buf(),
+          file_name: file_name.to_string(),
+          ..TemplateConfig::default()
+        }
       }
       Err(err) => {
         panic!(
@@ -46,11 +204,44 @@ impl TemplateConfig {
     }
   }
 
+  /// Search the [`SEARCH_PATHS`] of `template_dir` for a `file_name` config
+  /// file, returning the parsed configuration from the first one found along
+  /// with the directory it was found in.
+  ///
+  /// [`SEARCH_PATHS`]: constant.SEARCH_PATHS
+  pub(crate) fn discover(
+    template_dir: &Path,
+    project_name: &str,
+    file_name: &str,
+  ) -> Result<(TemplateConfig, PathBuf)> {
+    match Self::candidates(template_dir, file_name).into_iter().next() {
+      Some(dir) => Ok((Self::parse(&dir, project_name, file_name)?, dir)),
+      None => Err(Error::new(ErrorKind::NotFound, "No template file.")),
+    }
+  }
+
+  /// Directories within `template_dir` containing a `file_name` config file,
+  /// in the order they are searched.
+  pub(crate) fn candidates(
+    template_dir: &Path,
+    file_name: &str,
+  ) -> Vec<PathBuf> {
+    SEARCH_PATHS
+      .iter()
+      .map(|search_path| template_dir.join(search_path))
+      .filter(|dir| dir.join(file_name).is_file())
+      .collect()
+  }
+
   /// Parse a given `template.toml` file as substitute all default variables.
   ///
   /// Return as a `Result<TemplateConfig>` for successful and parse failure.
-  fn parse(template_dir: &dyn AsRef<Path>, project_name: &str) -> Result<Self> {
-    let template_path = template_dir.as_ref().join(TEMPLATE_FILE);
+  fn parse(
+    template_dir: &dyn AsRef<Path>,
+    project_name: &str,
+    file_name: &str,
+  ) -> Result<Self> {
+    let template_path = template_dir.as_ref().join(file_name);
     if !template_path.exists() {
       return Err(Error::new(ErrorKind::NotFound, "No template file."));
     }
@@ -59,28 +250,305 @@ impl TemplateConfig {
 
     // Deserialize the `template.toml` file into `TemplateConfig`.
     let mut config: TemplateConfig = toml::from_str(&parsed)?;
+    config.dir = template_dir.as_ref().to_path_buf();
+    config.file_name = file_name.to_string();
+
+    // The environment overlays of its `[variables]` are read apart.
+    let mut value: toml::Value = toml::from_str(&parsed)?;
+    config.overlays = take_overlays(&mut value)?;
+
+    // Unknown keys are ignored, e.g. to read the config files of newer
+    // versions, but most likely typos.
+    config.unknown_keys = unknown_keys(&value);
+    for key in &config.unknown_keys {
+      config.warnings.push(format!(
+        "unknown key {} in {}, it's ignored",
+        key, file_name
+      ));
+    }
+    for name in config.helpers.unknown() {
+      config.warnings.push(format!(
+        "unknown helper {} in the [helpers] of {}, it's ignored",
+        name, file_name
+      ));
+    }
+
+    config.strip_lines = config
+      .strip_lines_matching
+      .as_deref()
+      .and_then(|pattern| Regex::new(pattern).ok());
+
+    // Files are rendered with the `default_engine`, which can't be `none`.
+    if config.default_engine == Some(Engine::None) {
+      return Err(Error::new(
+        ErrorKind::Config,
+        &format!(
+          "default_engine of {} can't be \"none\", expected \"handlebars\" or \"liquid\"",
+          file_name
+        ),
+      ));
+    }
 
     // Assert both `include` & `exclude` isn't both provided.
     match &mut config.filters {
       Some(f) if f.include.is_some() && f.exclude.is_some() => {
         f.exclude = None;
-        eprintln!(
-          "{} {}",
-          emoji::WARN,
-          style(
-            "One of `include` or `exclude` should be provided, but not both."
-          )
-          .bold()
-          .yellow()
+        config.warnings.push(
+          "One of `include` or `exclude` should be provided, but not both."
+            .to_string(),
         );
       }
       Some(_) => (),
       None => (),
     };
 
+    // Prompt defaults can reference environment variables e.g
+    // `author = "${GIT_AUTHOR_NAME:-John Doe}"`.
+    if let Some(prompts) = &mut config.prompts {
+      let variables = config.variables.get_or_insert_with(HashMap::new);
+      for (name, default) in prompts.iter_mut() {
+        *default = parser::expand_env(default)?;
+        variables
+          .entry(name.to_string())
+          .or_insert_with(|| default.to_string());
+      }
+    }
+
     // Return the parsed configuration.
     Ok(config)
   }
+
+  /// Merge the variables of the environment overlay `env`, e.g.
+  /// `[variables.prod]`, over the configured variables.
+  ///
+  /// Returns false, changing nothing, if there's no such overlay.
+  pub(crate) fn apply_overlay(&mut self, env: &str) -> bool {
+    let overlay = match self.overlays.get(env) {
+      Some(overlay) => overlay.clone(),
+      None => return false,
+    };
+    self
+      .variables
+      .get_or_insert_with(HashMap::new)
+      .extend(overlay);
+    true
+  }
+
+  /// Merge the variables of a dotenv file at `path` into the configured
+  /// variables, overriding the ones from `template.toml`.
+  ///
+  /// With `lowercase`, keys are lowercased to match placeholder names e.g
+  /// `SERVICE_NAME=billing` is used as `{{ service_name }}`.
+  pub(crate) fn merge_env_file(
+    &mut self,
+    path: &Path,
+    lowercase: bool,
+  ) -> Result<()> {
+    let content = fs::read_to_string(path)?;
+    let variables = self.variables.get_or_insert_with(HashMap::new);
+    for (key, value) in parser::parse_env_file(&content)? {
+      let key = if lowercase { key.to_lowercase() } else { key };
+      variables.insert(key, value);
+    }
+
+    Ok(())
+  }
+
+  /// Variables of the environment `vars` whose name starts with `prefix`,
+  /// named without it & lowercased, e.g. `PROJECT_VAR_DB` is used as `{{db}}`
+  /// with `"PROJECT_VAR_"`.
+  pub(crate) fn prefixed_env_vars<I>(
+    prefix: &str,
+    vars: I,
+  ) -> HashMap<String, String>
+  where
+    I: IntoIterator<Item = (String, String)>,
+  {
+    vars
+      .into_iter()
+      .filter_map(|(key, value)| {
+        let name = key.strip_prefix(prefix)?;
+        if name.is_empty() {
+          return None;
+        }
+        Some((name.to_lowercase(), value))
+      })
+      .collect()
+  }
+
+  /// Load the user's default values of template variables, from the
+  /// `[defaults]` section of the user-level `defaults.toml` and of the
+  /// [`PROJECT_DEFAULTS_FILE`] in `project_dir`, which takes precedence.
+  ///
+  /// [`PROJECT_DEFAULTS_FILE`]: constant.PROJECT_DEFAULTS_FILE
+  pub(crate) fn load_defaults(
+    project_dir: &Path,
+  ) -> Result<HashMap<String, String>> {
+    let mut files = vec![defaults::config_dir().join(defaults::DEFAULTS_FILE)];
+    if Some(project_dir) != dirs::home_dir().as_deref() {
+      files.push(project_dir.join(PROJECT_DEFAULTS_FILE));
+    }
+
+    Self::merge_defaults(&files)
+  }
+
+  /// Merge the `[defaults]` section of `files`, later files taking
+  /// precedence. Missing files are skipped.
+  fn merge_defaults(files: &[PathBuf]) -> Result<HashMap<String, String>> {
+    let mut variables = HashMap::new();
+    for file in files {
+      variables.extend(Defaults::load_file(file)?.defaults);
+    }
+
+    Ok(variables)
+  }
+
+  /// Override the values of variables & the defaults of `[prompts]` with the
+  /// user's `defaults`.
+  pub(crate) fn apply_defaults(&mut self, defaults: HashMap<String, String>) {
+    if let Some(prompts) = &mut self.prompts {
+      for (name, default) in prompts.iter_mut() {
+        if let Some(value) = defaults.get(name) {
+          *default = value.to_string();
+        }
+      }
+    }
+    self
+      .variables
+      .get_or_insert_with(HashMap::new)
+      .extend(defaults);
+  }
+
+  /// Ask for the value of every variable in the `[prompts]` section, falling
+  /// back to its default if no answer was given.
+  pub(crate) fn prompt<R: BufRead, W: Write>(
+    &mut self,
+    input: &mut R,
+    output: &mut W,
+  ) -> Result<()> {
+    let prompts = match &self.prompts {
+      Some(prompts) => prompts,
+      None => return Ok(()),
+    };
+
+    let mut names: Vec<&String> = prompts.keys().collect();
+    names.sort();
+
+    let variables = self.variables.get_or_insert_with(HashMap::new);
+    for name in names {
+      let pattern = match self.validate.as_ref().and_then(|v| v.get(name)) {
+        Some(pattern) => Some(Regex::new(pattern)?),
+        None => None,
+      };
+
+      // Ask again while the answer doesn't match its pattern.
+      let mut answer = prompt::ask(input, output, name, &prompts[name])?;
+      for _ in 1..PROMPT_ATTEMPTS {
+        match &pattern {
+          Some(pattern) if !pattern.is_match(&answer) => {
+            writeln!(
+              output,
+              "{} {}",
+              emoji::WARN,
+              style(format!("{:?} must match {:?}", name, pattern.as_str()))
+                .yellow()
+            )?;
+            answer = prompt::ask(input, output, name, &prompts[name])?;
+          }
+          _ => break,
+        }
+      }
+      variables.insert(name.to_string(), answer);
+    }
+
+    Ok(())
+  }
+
+  /// Returns an error naming the first variable, in order, whose value
+  /// doesn't match its pattern in the `[validate]` section. Missing values
+  /// are empty.
+  pub(crate) fn validate_variables(&self) -> Result<()> {
+    let patterns = match &self.validate {
+      Some(patterns) => patterns,
+      None => return Ok(()),
+    };
+
+    let mut names: Vec<&String> = patterns.keys().collect();
+    names.sort();
+
+    for name in names {
+      let pattern = Regex::new(&patterns[name])?;
+      let value = self
+        .variables
+        .as_ref()
+        .and_then(|v| v.get(name))
+        .map(String::as_str)
+        .unwrap_or_default();
+      if !pattern.is_match(value) {
+        return Err(Error::new(
+          ErrorKind::Config,
+          &format!(
+            "variable {:?} = {:?} doesn't match pattern {:?}",
+            name,
+            value,
+            pattern.as_str()
+          ),
+        ));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Every violation of the template's variables by `vars`, e.g. of a
+  /// `validate-vars --vars-file`, ordered by variable name.
+  ///
+  /// Variables of `[prompts]` without a default, and of `[validate]` whose
+  /// pattern doesn't match an empty value, are required unless the template
+  /// gives them a non-empty value. Values must match their `[validate]`
+  /// pattern.
+  pub(crate) fn check_variables(
+    &self,
+    vars: &HashMap<String, String>,
+  ) -> Result<Vec<String>> {
+    let prompts = self.prompts.clone().unwrap_or_default();
+    let patterns = self.validate.clone().unwrap_or_default();
+    let variables = self.variables.clone().unwrap_or_default();
+
+    let names: BTreeSet<&String> =
+      prompts.keys().chain(patterns.keys()).collect();
+    let mut violations = Vec::new();
+    for name in names {
+      let pattern = match patterns.get(name) {
+        Some(pattern) => Some(Regex::new(pattern)?),
+        None => None,
+      };
+      // Prompts without a default are set to an empty value.
+      let value = vars.get(name).or_else(|| {
+        variables
+          .get(name)
+          .or_else(|| prompts.get(name))
+          .filter(|value| !value.is_empty())
+      });
+
+      match (value, &pattern) {
+        (Some(value), Some(pattern)) if !pattern.is_match(value) => violations
+          .push(format!(
+            "variable {:?} = {:?} doesn't match pattern {:?}",
+            name,
+            value,
+            pattern.as_str()
+          )),
+        (Some(_), _) => {}
+        (None, Some(pattern)) if pattern.is_match("") => {}
+        (None, _) => {
+          violations.push(format!("missing required variable {:?}", name))
+        }
+      }
+    }
+
+    Ok(violations)
+  }
 }
 
 impl Default for TemplateConfig {
@@ -88,9 +556,321 @@ impl Default for TemplateConfig {
     TemplateConfig {
       variables: None,
       rename: None,
+      moves: None,
+      raw: None,
+      render: None,
+      fanout: None,
       filters: Some(Filters::default()),
+      prompts: None,
+      validate: None,
+      scripts: None,
+      next_steps: None,
+      extends: None,
+      keep_template_toml: false,
+      trim_blocks: false,
+      large_file_size: None,
+      default_engine: None,
+      git: None,
+      helpers: Helpers::default(),
+      initial_commit_message: None,
+      strip_lines_matching: None,
+      strip_lines: None,
+      warnings: Vec::new(),
+      unknown_keys: Vec::new(),
+      overlays: HashMap::new(),
+      dir: PathBuf::new(),
+      file_name: TEMPLATE_FILE.to_string(),
+    }
+  }
+}
+
+/// Deserialize the `[variables]`, without their environment overlays which
+/// are read by [`take_overlays`].
+///
+/// [`take_overlays`]: fn.take_overlays.html
+fn without_overlays<'de, D>(
+  deserializer: D,
+) -> std::result::Result<Option<HashMap<String, String>>, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  let variables: Option<HashMap<String, toml::Value>> =
+    Option::deserialize(deserializer)?;
+  let variables = match variables {
+    Some(variables) => variables,
+    None => return Ok(None),
+  };
+
+  let mut values = HashMap::new();
+  for (name, value) in variables {
+    match value {
+      toml::Value::String(value) => {
+        values.insert(name, value);
+      }
+      toml::Value::Table(_) => {}
+      value => {
+        return Err(serde::de::Error::custom(format!(
+          "invalid type: {} for variable {:?}, expected a string",
+          value.type_str(),
+          name
+        )))
+      }
     }
   }
+
+  Ok(Some(values))
+}
+
+/// Remove the environment overlays, i.e. the tables of the `[variables]` of
+/// the config file `value`, e.g. `[variables.prod]`.
+fn take_overlays(
+  value: &mut toml::Value,
+) -> Result<HashMap<String, HashMap<String, String>>> {
+  let variables =
+    match value.get_mut("variables").and_then(|v| v.as_table_mut()) {
+      Some(variables) => variables,
+      None => return Ok(HashMap::new()),
+    };
+  let envs: Vec<String> = variables
+    .iter()
+    .filter(|(_, value)| value.is_table())
+    .map(|(env, _)| env.to_string())
+    .collect();
+
+  let mut overlays = HashMap::new();
+  for env in envs {
+    if let Some(overlay) = variables.remove(&env) {
+      overlays.insert(env, overlay.try_into()?);
+    }
+  }
+
+  Ok(overlays)
+}
+
+/// The config file `content` without its [`PRIVATE_KEYS`], to keep a copy
+/// in the generated project with `keep_template_toml`.
+///
+/// [`PRIVATE_KEYS`]: constant.PRIVATE_KEYS.html
+pub(crate) fn sanitize(content: &str) -> Result<String> {
+  let mut value: toml::Value = toml::from_str(content)?;
+  if let Some(table) = value.as_table_mut() {
+    for key in PRIVATE_KEYS {
+      table.remove(*key);
+    }
+  }
+  toml::to_string(&value)
+    .map_err(|err| Error::new(ErrorKind::ParseError, &err.to_string()))
+}
+
+/// Keys of the config file `value` which aren't in [`KNOWN_KEYS`], with the
+/// closest known key if there's one, e.g. `"filtres" (did you mean
+/// "filters"?)`.
+///
+/// [`KNOWN_KEYS`]: constant.KNOWN_KEYS.html
+fn unknown_keys(value: &toml::Value) -> Vec<String> {
+  let table = match value.as_table() {
+    Some(table) => table,
+    None => return Vec::new(),
+  };
+  let top_level: Vec<&str> = KNOWN_KEYS.iter().map(|(key, _)| *key).collect();
+
+  let mut unknown = Vec::new();
+  for (key, value) in table {
+    let fields = match KNOWN_KEYS.iter().find(|(known, _)| known == key) {
+      Some((_, fields)) => fields,
+      None => {
+        unknown.push(suggest(key, key, &top_level));
+        continue;
+      }
+    };
+    // Tables, or arrays of tables e.g. `[[fanout]]`.
+    let tables: Vec<&toml::value::Table> = match value {
+      toml::Value::Table(table) => vec![table],
+      toml::Value::Array(items) => {
+        items.iter().filter_map(toml::Value::as_table).collect()
+      }
+      _ => Vec::new(),
+    };
+    if let Some(fields) = fields {
+      for field in tables.iter().flat_map(|table| table.keys()) {
+        if !fields.contains(&field.as_str()) {
+          let path = format!("{}.{}", key, field);
+          unknown.push(suggest(&path, field, fields));
+        }
+      }
+    }
+  }
+  unknown.sort();
+  unknown.dedup();
+
+  unknown
+}
+
+/// The unknown key at `path` quoted, with the closest of the `known` keys to
+/// its `key`, if any.
+fn suggest(path: &str, key: &str, known: &[&str]) -> String {
+  match known
+    .iter()
+    .map(|known| (strsim::levenshtein(key, known), known))
+    .filter(|(distance, _)| *distance <= 2)
+    .min()
+  {
+    Some((_, known)) => format!("{:?} (did you mean {:?}?)", path, known),
+    None => format!("{:?}", path),
+  }
+}
+
+/// Files copied over byte-for-byte, even if they have a template extension
+/// which is then kept, e.g. to ship Handlebars templates in the project.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Raw {
+  /// Glob patterns of file paths relative to the template's base directory
+  /// e.g `"assets/styles.css.hbs"` or `"dist/**"`.
+  pub(crate) files: Vec<String>,
+}
+
+/// Directory generated once per item of a variable, whose value is a comma
+/// separated list, with the item bound to `{{item}}`.
+///
+/// ```toml
+/// # template.toml
+/// [variables]
+/// services = "auth, billing, search"
+///
+/// [[fanout]]
+/// src = "service_template"
+/// over = "services"
+/// name = "{{item}}-service"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Fanout {
+  /// Directory relative to the template's base directory.
+  pub(crate) src: String,
+  /// Variable holding the items.
+  pub(crate) over: String,
+  /// Name of each generated directory, rendered with `{{item}}`. Defaults to
+  /// the item itself.
+  pub(crate) name: Option<String>,
+}
+
+impl Fanout {
+  /// Items of the list `value`, e.g `"auth, billing"`.
+  pub(crate) fn items(value: &str) -> Vec<&str> {
+    value
+      .split(',')
+      .map(str::trim)
+      .filter(|item| !item.is_empty())
+      .collect()
+  }
+}
+
+/// Built-in Handlebars helpers to register, e.g. to avoid clashing with the
+/// template's own partials. All of them are registered by default.
+///
+/// ```toml
+/// # template.toml
+/// [helpers]
+/// enable = ["up", "low"]
+/// disable = ["replace"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct Helpers {
+  /// Only register these helpers.
+  pub(crate) enable: Option<Vec<String>>,
+  /// Don't register these helpers.
+  pub(crate) disable: Option<Vec<String>>,
+}
+
+impl Helpers {
+  /// Returns true if the helper `name` should be registered.
+  pub(crate) fn is_enabled(&self, name: &str) -> bool {
+    let listed = |names: &Vec<String>| names.iter().any(|n| n == name);
+
+    self.enable.as_ref().map(listed).unwrap_or(true)
+      && !self.disable.as_ref().map(listed).unwrap_or(false)
+  }
+
+  /// Names listed in `enable` or `disable` which aren't built-in helpers,
+  /// with the closest built-in one if there's one, like [`unknown_keys`].
+  ///
+  /// [`unknown_keys`]: fn.unknown_keys.html
+  pub(crate) fn unknown(&self) -> Vec<String> {
+    let known: Vec<&str> =
+      helpers::HELPERS.iter().map(|helper| helper.name).collect();
+    self
+      .enable
+      .iter()
+      .chain(&self.disable)
+      .flatten()
+      .filter(|name| !known.contains(&name.as_str()))
+      .map(|name| suggest(name, name, &known))
+      .collect()
+  }
+}
+
+/// How files are rendered, i.e. the `[render]` clause.
+///
+/// ```toml
+/// [render]
+/// include = ["config/*.conf"]
+/// keep_extension = ["templates/*.hbs"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Render {
+  /// Glob patterns of file paths relative to the template's base directory,
+  /// which are rendered with the `default_engine` even though they don't
+  /// have a template extension.
+  #[serde(default)]
+  pub(crate) include: Vec<String>,
+  /// Glob patterns of file paths relative to the template's base directory,
+  /// which are rendered but keep their template extension, e.g. to ship
+  /// Handlebars templates rendered with the project's variables. Unlike
+  /// `[raw]` files, which aren't rendered.
+  #[serde(default)]
+  pub(crate) keep_extension: Vec<String>,
+}
+
+/// Settings of remote templates, i.e. the `[git]` clause.
+///
+/// ```toml
+/// [git]
+/// default_branch = "main"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct GitConfig {
+  /// Branch checked out unless one is given with `--branch`, instead of the
+  /// remote's `HEAD`, e.g. for a template mirrored on hosts whose default
+  /// branches differ.
+  pub(crate) default_branch: Option<String>,
+}
+
+/// Steps printed once the project is generated, i.e. `next_steps`.
+///
+/// ```toml
+/// next_steps = ["cd {{project-name}}", "make setup"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum NextSteps {
+  /// One step per item.
+  List(Vec<String>),
+  /// One step per line.
+  Text(String),
+}
+
+impl NextSteps {
+  /// The steps, without blank lines.
+  pub(crate) fn steps(&self) -> Vec<&str> {
+    let steps: Vec<&str> = match self {
+      NextSteps::List(steps) => steps.iter().map(String::as_str).collect(),
+      NextSteps::Text(text) => text.lines().collect(),
+    };
+    steps
+      .into_iter()
+      .map(str::trim_end)
+      .filter(|step| !step.trim().is_empty())
+      .collect()
+  }
 }
 
 /// Files or Directories to be included or ignored while parsing
@@ -103,17 +883,290 @@ pub(crate) struct Filters {
   pub(crate) exclude: Option<Vec<String>>,
 }
 
+impl Filters {
+  /// Add the files of `other` which aren't already filtered.
+  pub(crate) fn merge(&mut self, other: &Filters) {
+    union(&mut self.include, &other.include);
+    union(&mut self.exclude, &other.exclude);
+  }
+}
+
+/// Add the entries of `other` missing from `files`, if both are provided.
+fn union(files: &mut Option<Vec<String>>, other: &Option<Vec<String>>) {
+  if let (Some(files), Some(other)) = (files, other) {
+    for file in other {
+      if !files.contains(file) {
+        files.push(file.clone());
+      }
+    }
+  }
+}
+
 impl Default for Filters {
   fn default() -> Filters {
     Filters {
       include: None,
       // Exclude these dirs by default.
-      exclude: Some(vec![
-        "venv".to_string(),
-        ".git".to_string(),
-        ".idea".to_string(),
-        ".vscode".to_string(),
-      ]),
+      exclude: Some(
+        DEFAULT_EXCLUDES.iter().map(|dir| dir.to_string()).collect(),
+      ),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  use std::{env, io::Cursor};
+
+  #[test]
+  fn test_prompt_defaults_from_env() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+      dir.path().join(TEMPLATE_FILE),
+      r#"
+[prompts]
+author = "${PROJECT_TEST_PROMPT_AUTHOR:-John Doe}"
+license = "${PROJECT_TEST_PROMPT_LICENSE:-MIT}"
+"#,
+    )
+    .unwrap();
+
+    env::set_var("PROJECT_TEST_PROMPT_AUTHOR", "Jane Doe");
+    env::remove_var("PROJECT_TEST_PROMPT_LICENSE");
+
+    let config =
+      TemplateConfig::parse(&dir.path(), "my_project", TEMPLATE_FILE).unwrap();
+    let variables = config.variables.unwrap();
+    assert_eq!(variables["author"], "Jane Doe");
+    assert_eq!(variables["license"], "MIT");
+  }
+
+  #[test]
+  fn test_discover_nested_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join(".project");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(
+      nested.join(TEMPLATE_FILE),
+      "[variables]\nname = \"nested\"\n",
+    )
+    .unwrap();
+
+    let (config, found) =
+      TemplateConfig::discover(dir.path(), "my_project", TEMPLATE_FILE)
+        .unwrap();
+    assert_eq!(found, nested);
+    assert_eq!(config.dir, nested);
+    assert_eq!(config.variables.unwrap()["name"], "nested");
+
+    // The base directory is searched first.
+    fs::write(
+      dir.path().join(TEMPLATE_FILE),
+      "[variables]\nname = \"root\"\n",
+    )
+    .unwrap();
+    let (config, found) =
+      TemplateConfig::discover(dir.path(), "my_project", TEMPLATE_FILE)
+        .unwrap();
+    assert_eq!(found, dir.path());
+    assert_eq!(config.variables.unwrap()["name"], "root");
+    assert_eq!(
+      TemplateConfig::candidates(dir.path(), TEMPLATE_FILE).len(),
+      2
+    );
+  }
+
+  #[test]
+  fn test_prompt_answers() {
+    let mut config = TemplateConfig::default();
+    let mut prompts = HashMap::new();
+    prompts.insert("author".to_string(), "Jane Doe".to_string());
+    prompts.insert("license".to_string(), "MIT".to_string());
+    config.prompts = Some(prompts);
+
+    // Answers are read in alphabetical order of the prompt names.
+    let mut input = Cursor::new("\nApache-2.0\n");
+    let mut output = Vec::new();
+    config.prompt(&mut input, &mut output).unwrap();
+
+    let variables = config.variables.unwrap();
+    assert_eq!(variables["author"], "Jane Doe");
+    assert_eq!(variables["license"], "Apache-2.0");
+  }
+
+  #[test]
+  fn test_merge_defaults() {
+    let dir = tempfile::tempdir().unwrap();
+    let user = dir.path().join(defaults::DEFAULTS_FILE);
+    fs::write(
+      &user,
+      "author = \"Jane\"\n\n[defaults]\nlicense = \"MIT\"\nci = \"github\"\n",
+    )
+    .unwrap();
+    let project = dir.path().join(PROJECT_DEFAULTS_FILE);
+    fs::write(&project, "[defaults]\nlicense = \"Apache-2.0\"\n").unwrap();
+
+    let missing = dir.path().join("missing.toml");
+    let defaults =
+      TemplateConfig::merge_defaults(&[user, project, missing]).unwrap();
+    assert_eq!(defaults.len(), 2);
+    assert_eq!(defaults["license"], "Apache-2.0");
+    assert_eq!(defaults["ci"], "github");
+
+    let mut config = TemplateConfig {
+      prompts: Some(
+        vec![("license".to_string(), "MIT".to_string())]
+          .into_iter()
+          .collect(),
+      ),
+      ..TemplateConfig::default()
+    };
+    config.apply_defaults(defaults);
+    assert_eq!(config.prompts.unwrap()["license"], "Apache-2.0");
+    assert_eq!(config.variables.unwrap()["ci"], "github");
+  }
+
+  #[test]
+  fn test_validate_variables() {
+    let pattern = "^[a-z][a-z0-9_]*$";
+    let config = |name: &str| TemplateConfig {
+      variables: Some(
+        vec![("name".to_string(), name.to_string())]
+          .into_iter()
+          .collect(),
+      ),
+      validate: Some(
+        vec![("name".to_string(), pattern.to_string())]
+          .into_iter()
+          .collect(),
+      ),
+      ..TemplateConfig::default()
+    };
+
+    assert!(config("my_project").validate_variables().is_ok());
+
+    let err = config("My-Project").validate_variables().unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+    assert!(err.to_string().contains("\"name\""));
+    assert!(err.to_string().contains(pattern));
+
+    // Invalid answers are asked again.
+    let mut config = TemplateConfig {
+      prompts: Some(
+        vec![("name".to_string(), "Default".to_string())]
+          .into_iter()
+          .collect(),
+      ),
+      ..config("")
+    };
+    let mut input = Cursor::new("Bad Name\nmy_project\n");
+    config.prompt(&mut input, &mut Vec::new()).unwrap();
+    assert!(config.validate_variables().is_ok());
+    assert_eq!(config.variables.unwrap()["name"], "my_project");
+  }
+
+  #[test]
+  fn test_overlays() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+      dir.path().join(TEMPLATE_FILE),
+      "[filters]\nexclude = []\n\n[variables]\nreplicas = \"1\"\nlog_level = \"debug\"\n\n[variables.prod]\nreplicas = \"3\"\n\n[variables.staging]\nreplicas = \"2\"\n",
+    )
+    .unwrap();
+    let mut config =
+      TemplateConfig::parse(&dir.path(), "project", TEMPLATE_FILE).unwrap();
+    assert!(config.unknown_keys.is_empty());
+    assert_eq!(config.variables.as_ref().unwrap()["replicas"], "1");
+
+    assert!(!config.apply_overlay("dev"));
+    assert!(config.apply_overlay("prod"));
+    let variables = config.variables.unwrap();
+    assert_eq!(variables["replicas"], "3");
+    assert_eq!(variables["log_level"], "debug");
+  }
+
+  #[test]
+  fn test_unknown_keys() {
+    let value: toml::Value = toml::from_str(
+      "trim_blocks = true\nlicence = \"MIT\"\n\n[filtres]\nexclude = []\n\n[variables]\nanything = \"1\"\n\n[render]\nkeep_extensions = []\n\n[[fanout]]\nsrc = \"a\"\nover = \"b\"\nname = \"c\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+      unknown_keys(&value),
+      vec![
+        "\"filtres\" (did you mean \"filters\"?)".to_string(),
+        "\"licence\"".to_string(),
+        "\"render.keep_extensions\" (did you mean \"keep_extension\"?)"
+          .to_string(),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_unknown_helpers() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+      dir.path().join(TEMPLATE_FILE),
+      "[helpers]\nenable = [\"up\", \"uper\"]\ndisable = [\"shout\"]\n",
+    )
+    .unwrap();
+
+    let config =
+      TemplateConfig::parse(&dir.path(), "my_project", TEMPLATE_FILE).unwrap();
+    assert_eq!(
+      config.warnings,
+      vec![
+        "unknown helper \"uper\" (did you mean \"up\"?) in the [helpers] of template.toml, it's ignored",
+        "unknown helper \"shout\" in the [helpers] of template.toml, it's ignored",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_default_engine_none() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+      dir.path().join(TEMPLATE_FILE),
+      "default_engine = \"none\"\n",
+    )
+    .unwrap();
+
+    let err = TemplateConfig::parse(&dir.path(), "my_project", TEMPLATE_FILE)
+      .unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Config);
+    assert!(err.to_string().contains("can't be \"none\""));
+  }
+
+  #[test]
+  fn test_check_variables() {
+    let config: TemplateConfig = toml::from_str(
+      "[prompts]\nauthor = \"\"\nlicense = \"MIT\"\n\n[validate]\nname = \"^[a-z][a-z0-9_]*$\"\nversion = \"^([0-9.]+)?$\"\n",
+    )
+    .unwrap();
+    let vars = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+      pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+    };
+
+    let valid = vars(&[("author", "Jane Doe"), ("name", "my_project")]);
+    assert!(config.check_variables(&valid).unwrap().is_empty());
+
+    // Every violation is reported.
+    let invalid = vars(&[("name", "My-Project"), ("version", "v1")]);
+    assert_eq!(
+      config.check_variables(&invalid).unwrap(),
+      vec![
+        "missing required variable \"author\"".to_string(),
+        "variable \"name\" = \"My-Project\" doesn't match pattern \"^[a-z][a-z0-9_]*$\""
+          .to_string(),
+        "variable \"version\" = \"v1\" doesn't match pattern \"^([0-9.]+)?$\""
+          .to_string(),
+      ]
+    );
+  }
+}
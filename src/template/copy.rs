@@ -0,0 +1,226 @@
+//! Chunked copy of large files, e.g. binary assets of a template.
+//!
+//! Files at least [`LARGE_FILE_SIZE`] bytes large, or the template's
+//! `large_file_size`, are copied in chunks into a temporary file next to
+//! their destination, which is renamed once complete. An interrupted copy
+//! never leaves a partial file behind under its final name.
+//!
+//! [`LARGE_FILE_SIZE`]: constant.LARGE_FILE_SIZE.html
+//!
+use std::{
+  fs::{self, File},
+  io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+  path::{Path, PathBuf},
+};
+
+/// Size from which files are copied in chunks, unless the template sets its
+/// `large_file_size`: 8 MiB.
+pub(crate) const LARGE_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Size of the chunks large files are copied by.
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Times a read failing with a transient error is retried.
+const RETRIES: usize = 3;
+
+/// Copy the file `src` into `dest` in chunks, reporting the bytes copied so
+/// far & the file's size to `progress` after every chunk.
+///
+/// Reads which fail with a transient error, e.g. `Interrupted`, are retried
+/// from where they stopped. The copy keeps the permissions of `src`, e.g.
+/// its executable bit, like `fs::copy`. Returns the number of bytes copied.
+pub(crate) fn copy_large(
+  src: &Path,
+  dest: &Path,
+  progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<u64> {
+  let file = File::open(src)?;
+  let metadata = file.metadata()?;
+  copy_chunked(
+    file,
+    metadata.len(),
+    dest,
+    Some(metadata.permissions()),
+    progress,
+  )
+}
+
+/// Temporary file `dest` is written into until it's complete, e.g.
+/// `.logo.png.part` for `logo.png`.
+pub(crate) fn partial_path(dest: &Path) -> PathBuf {
+  let mut name = std::ffi::OsString::from(".");
+  name.push(dest.file_name().unwrap_or_default());
+  name.push(".part");
+  dest.with_file_name(name)
+}
+
+/// Copy the `len` bytes of `reader` into `dest`, with the given
+/// `permissions` if any, see [`copy_large`].
+///
+/// [`copy_large`]: fn.copy_large.html
+fn copy_chunked<R: Read + Seek>(
+  reader: R,
+  len: u64,
+  dest: &Path,
+  permissions: Option<fs::Permissions>,
+  progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<u64> {
+  let partial = partial_path(dest);
+  let written =
+    write_chunks(reader, len, &partial, progress).and_then(|copied| {
+      if let Some(permissions) = permissions {
+        fs::set_permissions(&partial, permissions)?;
+      }
+      Ok(copied)
+    });
+  match written {
+    Ok(copied) => {
+      fs::rename(&partial, dest)?;
+      Ok(copied)
+    }
+    Err(err) => {
+      // Don't leave a partial copy behind.
+      let _ = fs::remove_file(&partial);
+      Err(err)
+    }
+  }
+}
+
+/// Write the chunks of `reader` into the `partial` file.
+fn write_chunks<R: Read + Seek>(
+  mut reader: R,
+  len: u64,
+  partial: &Path,
+  progress: &mut dyn FnMut(u64, u64),
+) -> io::Result<u64> {
+  let mut writer = BufWriter::new(File::create(partial)?);
+  let mut buf = vec![0; CHUNK_SIZE];
+  let mut copied = 0;
+  let mut retries = 0;
+
+  loop {
+    let read = match reader.read(&mut buf) {
+      Ok(0) => break,
+      Ok(read) => read,
+      Err(err) if is_transient(&err) && retries < RETRIES => {
+        log::warn!("retrying the copy into {:?}: {}", partial, err);
+        retries += 1;
+        // Resume from the last chunk written.
+        reader.seek(SeekFrom::Start(copied))?;
+        continue;
+      }
+      Err(err) => return Err(err),
+    };
+    writer.write_all(&buf[..read])?;
+    copied += read as u64;
+    retries = 0;
+    progress(copied, len);
+  }
+  writer.flush()?;
+
+  Ok(copied)
+}
+
+/// Whether a failed read may succeed if it's retried.
+fn is_transient(err: &io::Error) -> bool {
+  matches!(
+    err.kind(),
+    io::ErrorKind::Interrupted
+      | io::ErrorKind::TimedOut
+      | io::ErrorKind::WouldBlock
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use pretty_assertions::assert_eq;
+
+  use std::io::Cursor;
+
+  /// Reader failing with `kind` after `after` bytes, `times` times.
+  struct Flaky {
+    inner: Cursor<Vec<u8>>,
+    after: u64,
+    kind: io::ErrorKind,
+    times: usize,
+  }
+
+  impl Read for Flaky {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+      if self.inner.position() >= self.after && self.times > 0 {
+        self.times -= 1;
+        return Err(io::Error::new(self.kind, "simulated failure"));
+      }
+      let limit = if self.inner.position() < self.after {
+        (self.after - self.inner.position()) as usize
+      } else {
+        buf.len()
+      };
+      let len = buf.len().min(limit);
+      self.inner.read(&mut buf[..len])
+    }
+  }
+
+  impl Seek for Flaky {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+      self.inner.seek(pos)
+    }
+  }
+
+  #[test]
+  fn test_copy_large() {
+    let dir = tempfile::tempdir().unwrap();
+    let content: Vec<u8> = (0..3 * CHUNK_SIZE + 7).map(|i| i as u8).collect();
+    let src = dir.path().join("asset.bin");
+    fs::write(&src, &content).unwrap();
+
+    let dest = dir.path().join("copy.bin");
+    let mut reported = Vec::new();
+    let copied =
+      copy_large(&src, &dest, &mut |copied, len| reported.push((copied, len)))
+        .unwrap();
+    let len = content.len() as u64;
+    assert_eq!(copied, len);
+    assert_eq!(fs::read(&dest).unwrap(), content);
+    assert_eq!(reported.last(), Some(&(len, len)));
+    assert!(!partial_path(&dest).exists());
+
+    // The copy keeps the executable bit.
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      fs::set_permissions(&src, fs::Permissions::from_mode(0o755)).unwrap();
+      let dest = dir.path().join("run.bin");
+      copy_large(&src, &dest, &mut |_, _| {}).unwrap();
+      let mode = fs::metadata(&dest).unwrap().permissions().mode();
+      assert_eq!(mode & 0o777, 0o755);
+    }
+
+    // Transient failures are retried where they stopped.
+    let flaky = Flaky {
+      inner: Cursor::new(content.clone()),
+      after: CHUNK_SIZE as u64 + 3,
+      kind: io::ErrorKind::Interrupted,
+      times: 2,
+    };
+    let dest = dir.path().join("retried.bin");
+    copy_chunked(flaky, len, &dest, None, &mut |_, _| {}).unwrap();
+    assert_eq!(fs::read(&dest).unwrap(), content);
+
+    // An interrupted copy leaves no partial file.
+    let flaky = Flaky {
+      inner: Cursor::new(content),
+      after: CHUNK_SIZE as u64 + 3,
+      kind: io::ErrorKind::Other,
+      times: 1,
+    };
+    let dest = dir.path().join("interrupted.bin");
+    let err =
+      copy_chunked(flaky, len, &dest, None, &mut |_, _| {}).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Other);
+    assert!(!dest.exists());
+    assert!(!partial_path(&dest).exists());
+  }
+}
@@ -3,27 +3,107 @@
 //! - [Handlebars][handlebars]
 //! - [Liquid][liquid]
 //!
+//! A file is rendered when its final or second-to-last extension is a
+//! template extension, which is then dropped from the rendered file's name:
+//!
+//! - `README.md.hbs` renders into `README.md`.
+//! - `README.hbs.md` renders into `README.md`.
+//! - `README.md` is copied over as-is.
+//!
+//! The final extension takes precedence, i.e. `file.hbs.liquid` is rendered
+//! with Liquid into `file.hbs`.
+//!
 //! [handlebars]: https://handlebarsjs.com
 //! [liquid]: https://shopify.github.io/liquid/
 //!
 
-use crate::error::Result;
+use crate::{error::Result, template::config::Helpers};
 
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use strsim::levenshtein;
 
 use std::{
   collections::HashMap,
   ffi::OsStr,
   fs::{self, File},
-  io::{BufReader, Read, Write},
-  path::Path,
+  io::{BufReader, BufWriter, Read, Write},
+  path::{Path, PathBuf},
 };
 
 mod handlebars;
 mod liquid;
 
-/// Available templating engine.
-#[derive(Deserialize)]
+/// Drop the lines of `content` holding a single tag, opened by `open` &
+/// closed by `close`, for which `is_block` returns true given its inside.
+fn trim_standalone(
+  content: &str,
+  open: &str,
+  close: &str,
+  is_block: impl Fn(&str) -> bool,
+) -> String {
+  content
+    .split_inclusive('\n')
+    .filter(|line| {
+      let tag = line.trim();
+      let standalone = tag.starts_with(open)
+        && tag.ends_with(close)
+        && tag.matches(open).count() == 1;
+      !(standalone && is_block(&tag[open.len()..tag.len() - close.len()]))
+    })
+    .collect()
+}
+
+/// The `variables` referenced by the tags of `content`, e.g. `{{name}}`,
+/// `{{upper name}}` or `{% if license %}`, with their values, in order of
+/// first appearance, i.e. `--trace-renders`.
+pub(crate) fn referenced<'a>(
+  content: &str,
+  variables: &'a HashMap<String, String>,
+) -> Result<Vec<(&'a str, &'a str)>> {
+  let tags = Regex::new(r"(?s)\{[{%](.*?)[}%]\}")?;
+  let words = Regex::new(r"[\w-]+")?;
+
+  let mut found: Vec<(&str, &str)> = Vec::new();
+  for tag in tags.captures_iter(content) {
+    for word in words.find_iter(&tag[1]) {
+      if let Some((name, value)) = variables.get_key_value(word.as_str()) {
+        if !found.iter().any(|(seen, _)| seen == name) {
+          found.push((name, value));
+        }
+      }
+    }
+  }
+
+  Ok(found)
+}
+
+/// `variables`, with every other word of the tags of `content` bound to an
+/// empty value, e.g. to tell unset variables apart from invalid tags.
+pub(crate) fn unbound(
+  content: &str,
+  variables: &HashMap<String, String>,
+) -> Result<HashMap<String, String>> {
+  let tags = Regex::new(r"(?s)\{[{%](.*?)[}%]\}")?;
+  let words = Regex::new(r"[\w-]+")?;
+
+  let mut variables = variables.clone();
+  for tag in tags.captures_iter(content) {
+    for word in words.find_iter(&tag[1]) {
+      variables.entry(word.as_str().to_string()).or_default();
+    }
+  }
+
+  Ok(variables)
+}
+
+/// File extensions recognized by the templating engines.
+pub(crate) const EXTENSIONS: &[&str] = &["hbs", "liquid"];
+
+/// Available templating engine, e.g. `default_engine = "liquid"` in
+/// `template.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub(crate) enum Engine {
   /// Handlebars with file extension: "hbs".
   Handlebars,
@@ -43,11 +123,117 @@ impl Engine {
       Engine::None
     }
   }
+
+  /// Returns the engine for a file `path`, based on its final or second-to-last
+  /// extension, e.g `file.txt.hbs` or `file.hbs.txt`.
+  pub(crate) fn from_path(path: &Path) -> Engine {
+    match Self::template_extension(path) {
+      Some((ext, _)) => Engine::new(ext),
+      None => Engine::None,
+    }
+  }
+
+  /// Returns `path` with its template extension dropped, e.g `file.txt.hbs`
+  /// and `file.hbs.txt` both become `file.txt`.
+  ///
+  /// Paths without a template extension are returned as-is.
+  pub(crate) fn rendered_path(path: &Path) -> PathBuf {
+    match Self::template_extension(path) {
+      Some((_, rendered)) => rendered,
+      None => path.to_path_buf(),
+    }
+  }
+
+  /// Template extension of `path` & the path without it.
+  fn template_extension(path: &Path) -> Option<(&OsStr, PathBuf)> {
+    let is_template = |ext: &OsStr| !matches!(Engine::new(ext), Engine::None);
+
+    let ext = path.extension()?;
+    if is_template(ext) {
+      return Some((ext, path.with_extension("")));
+    }
+
+    let stem = Path::new(path.file_stem()?);
+    let inner = stem.extension()?;
+    if is_template(inner) {
+      let mut name = stem.with_extension("").into_os_string();
+      name.push(".");
+      name.push(ext);
+      return Some((inner, path.with_file_name(name)));
+    }
+
+    None
+  }
+
+  /// Render a template `content` string with the given `variables`, and the
+  /// built-in Handlebars helpers enabled by `helpers`.
+  ///
+  /// `Engine::None` returns the `content` as-is.
+  pub(crate) fn parse<T: Serialize>(
+    &self,
+    content: &str,
+    variables: &T,
+    helpers: &Helpers,
+  ) -> Result<String> {
+    match self {
+      Engine::Handlebars => handlebars::parse(content, variables, helpers),
+      Engine::Liquid => liquid::parse(content, variables),
+      Engine::None => Ok(content.to_string()),
+    }
+  }
+
+  /// Render a Handlebars `content` string like [`parse`], without
+  /// HTML-escaping the values of its variables, e.g. for shell commands.
+  ///
+  /// [`parse`]: #method.parse
+  pub(crate) fn parse_unescaped<T: Serialize>(
+    content: &str,
+    variables: &T,
+    helpers: &Helpers,
+  ) -> Result<String> {
+    handlebars::parse_unescaped(content, variables, helpers)
+  }
+
+  /// Drop the lines holding nothing but a block tag from `content`, e.g.
+  /// `{{#if license}}` or `{% endif %}`, i.e. `trim_blocks` in
+  /// `template.toml`. Otherwise their newline is left in the rendered file.
+  ///
+  /// `Engine::None` returns the `content` as-is.
+  pub(crate) fn trim_blocks(&self, content: &str) -> String {
+    match self {
+      Engine::Handlebars => handlebars::trim_blocks(content),
+      Engine::Liquid => liquid::trim_blocks(content),
+      Engine::None => content.to_string(),
+    }
+  }
+
+  /// Suggest a known engine extension for a given `ext` that is likely
+  /// a typo, e.g `"hbss"` or `"LIQUID"`.
+  ///
+  /// Returns `None` if `ext` is already a known extension or isn't close
+  /// enough (a single edit away or differing only by case) to any of them.
+  pub(crate) fn suggest(ext: &OsStr) -> Option<&'static str> {
+    let ext = ext.to_str()?;
+    if EXTENSIONS.contains(&ext) {
+      return None;
+    }
+
+    EXTENSIONS.iter().copied().find(|known| {
+      ext.eq_ignore_ascii_case(known) || levenshtein(ext, known) == 1
+    })
+  }
 }
 
-pub(crate) trait TemplateEngine {
+/// Renders template files. Implemented by the built-in [`Engine`]s, and by
+/// custom engines registered with [`Template::register_engine`].
+///
+/// [`Engine`]: enum.Engine.html
+/// [`Template::register_engine`]: ../struct.Template.html#method.register_engine
+pub trait TemplateEngine {
+  /// Variables the templates are rendered with.
   type Data: Serialize;
 
+  /// Render the template file `src` into the `target` file.
   fn render(
     &self,
     src: &Path,
@@ -56,6 +242,95 @@ pub(crate) trait TemplateEngine {
   ) -> Result<()>;
 }
 
+impl Engine {
+  /// Like [`TemplateEngine::render`], with only the built-in Handlebars
+  /// helpers enabled by `helpers`, and lines of block tags dropped with
+  /// `trim_blocks`.
+  ///
+  /// [`TemplateEngine::render`]: trait.TemplateEngine.html#tymethod.render
+  pub(crate) fn render_with(
+    &self,
+    src: &Path,
+    target: &Path,
+    variables: &HashMap<String, String>,
+    helpers: &Helpers,
+    trim_blocks: bool,
+  ) -> Result<()> {
+    match self {
+      // Rename the file. Get rid of ".hbs" or ".liquid".
+      Engine::Handlebars | Engine::Liquid => self.render_into(
+        src,
+        &Engine::rendered_path(target),
+        variables,
+        helpers,
+        trim_blocks,
+      ),
+      Engine::None => {
+        // Move file over to target.
+        fs::copy(src, target)?;
+        Ok(())
+      }
+    }
+  }
+
+  /// Same as [`render_with`], rendering into `rendered` as-is, i.e. without
+  /// dropping its template extension.
+  ///
+  /// [`render_with`]: #method.render_with
+  pub(crate) fn render_into(
+    &self,
+    src: &Path,
+    rendered: &Path,
+    variables: &HashMap<String, String>,
+    helpers: &Helpers,
+    trim_blocks: bool,
+  ) -> Result<()> {
+    match self {
+      Engine::Handlebars => {
+        // Stream the rendered template into the target file.
+        let mut reader = BufReader::new(File::open(src)?);
+        let mut writer = BufWriter::new(File::create(rendered)?);
+
+        if let Err(err) = handlebars::render_to_write(
+          &mut reader,
+          variables,
+          &mut writer,
+          helpers,
+          trim_blocks,
+        ) {
+          // Don't leave a partially rendered file behind.
+          drop(writer);
+          fs::remove_file(rendered)?;
+          return Err(err);
+        }
+        writer.flush()?;
+      }
+      Engine::Liquid => {
+        // Read contents of src file.
+        let template_file = File::open(src)?;
+        let mut buf_reader = BufReader::new(template_file);
+
+        let mut content = String::new();
+        buf_reader.read_to_string(&mut content)?;
+
+        if trim_blocks {
+          content = liquid::trim_blocks(&content);
+        }
+        let new_content = liquid::parse(&content, variables)?;
+
+        // Write new content into target file.
+        let mut target_file = File::create(rendered)?;
+        target_file.write_all(new_content.as_bytes())?;
+      }
+      Engine::None => {
+        fs::copy(src, rendered)?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
 impl TemplateEngine for Engine {
   type Data = HashMap<String, String>;
   fn render(
@@ -64,29 +339,102 @@ impl TemplateEngine for Engine {
     target: &Path,
     variables: &Self::Data,
   ) -> Result<()> {
-    // Read contents of src file.
-    let template_file = File::open(src)?;
-    let mut buf_reader = BufReader::new(template_file);
+    self.render_with(src, target, variables, &Helpers::default(), false)
+  }
+}
 
-    let mut content = String::new();
-    buf_reader.read_to_string(&mut content)?;
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
 
-    let new_content = match self {
-      Engine::Handlebars => handlebars::parse(&content, variables)?,
-      Engine::Liquid => liquid::parse(&content, variables)?,
-      Engine::None => {
-        // Move file over to target.
-        fs::copy(src, target)?;
-        return Ok(());
-      }
-    };
+  #[test]
+  fn test_referenced() {
+    let variables: HashMap<String, String> = vec![
+      ("project-name", "my_project"),
+      ("license", "MIT"),
+      ("unused", "value"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    let content = "# {{ upper project-name }}\n{% if license %}{{license}}{% endif %}\n{{project-name}} license\n";
+    assert_eq!(
+      referenced(content, &variables).unwrap(),
+      vec![("project-name", "my_project"), ("license", "MIT")]
+    );
+  }
 
-    // Rename the file. Get rid of ".hbs" or ".liquid".
-    let target = target.with_extension("");
+  #[test]
+  fn test_unbound() {
+    let variables: HashMap<String, String> =
+      vec![("license".to_string(), "MIT".to_string())]
+        .into_iter()
+        .collect();
 
-    // Write new content into target file.
-    let mut target_file = File::create(target)?;
-    target_file.write_all(new_content.as_bytes())?;
-    Ok(())
+    let unbound =
+      unbound("{{license}}-{{ upper app-name }}", &variables).unwrap();
+    assert_eq!(unbound["license"], "MIT");
+    assert_eq!(unbound["app-name"], "");
+    assert_eq!(unbound["upper"], "");
+  }
+
+  #[test]
+  fn test_suggest_extension() {
+    assert_eq!(Engine::suggest(OsStr::new("hbss")), Some("hbs"));
+    assert_eq!(Engine::suggest(OsStr::new("hb")), Some("hbs"));
+    assert_eq!(Engine::suggest(OsStr::new("LIQUID")), Some("liquid"));
+    assert_eq!(Engine::suggest(OsStr::new("hbs")), None);
+    assert_eq!(Engine::suggest(OsStr::new("txt")), None);
+  }
+
+  #[test]
+  fn test_compound_extensions() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut variables = HashMap::new();
+    variables.insert("name".to_string(), "project".to_string());
+
+    for file in &["before.hbs.txt", "after.txt.hbs"] {
+      let src = dir.path().join(file);
+      fs::write(&src, "# {{name}}\n").unwrap();
+      Engine::from_path(&src)
+        .render(&src, &src, &variables)
+        .unwrap();
+    }
+
+    let read = |file: &str| fs::read_to_string(dir.path().join(file)).unwrap();
+    assert_eq!(read("before.txt"), "# project\n");
+    assert_eq!(read("after.txt"), "# project\n");
+
+    let rendered = |path: &str| Engine::rendered_path(Path::new(path));
+    assert_eq!(rendered("dir/file.hbs.txt"), Path::new("dir/file.txt"));
+    assert_eq!(rendered("file.liquid.md"), Path::new("file.md"));
+    assert_eq!(rendered("file.hbs.liquid"), Path::new("file.hbs"));
+    assert_eq!(rendered("archive.tar.gz"), Path::new("archive.tar.gz"));
+    assert_eq!(rendered(".hbs"), Path::new(".hbs"));
+  }
+
+  #[test]
+  fn test_render_large_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("large.txt.hbs");
+    let target = dir.path().join("out").join("large.txt.hbs");
+    fs::create_dir_all(target.parent().unwrap()).unwrap();
+
+    // ~4MB template.
+    let line = "Lorem ipsum dolor sit amet, consectetur adipiscing elit.\n";
+    let body = line.repeat(4 * 1024 * 1024 / line.len());
+    let content = format!("# {{{{name}}}}\n{}# {{{{up name}}}}\n", body);
+    fs::write(&src, content).unwrap();
+
+    let mut variables = HashMap::new();
+    variables.insert("name".to_string(), "project".to_string());
+    Engine::Handlebars
+      .render(&src, &target, &variables)
+      .unwrap();
+
+    let rendered = fs::read_to_string(target.with_extension("")).unwrap();
+    assert_eq!(rendered, format!("# project\n{}# PROJECT\n", body));
   }
 }
@@ -1,9 +1,11 @@
 use handlebars::{Handlebars, HelperDef};
 use serde::Serialize;
 
+use std::io::{Read, Write};
+
 use crate::{
   error::{Error, ErrorKind, Result},
-  template::helpers,
+  template::{config::Helpers, helpers},
 };
 
 /// Helper function
@@ -49,15 +51,15 @@ use crate::{
 /// ```
 type HelperFn = dyn HelperDef + Send + Sync;
 
-/// Register builtin default Handlebar helpers.
+/// Register builtin default Handlebar helpers, which are enabled by `policy`.
 #[inline]
-fn register_default_helpers(handlebars: &mut Handlebars) {
+fn register_default_helpers(handlebars: &mut Handlebars, policy: &Helpers) {
   // Register handlebars helpers.
-  register_helper_fn(handlebars, "replace", Box::new(helpers::replace));
-  register_helper_fn(handlebars, "append", Box::new(helpers::append));
-  register_helper_fn(handlebars, "prepend", Box::new(helpers::prepend));
-  register_helper_fn(handlebars, "up", Box::new(helpers::up));
-  register_helper_fn(handlebars, "low", Box::new(helpers::low));
+  for info in helpers::HELPERS {
+    if policy.is_enabled(info.name) {
+      register_helper_fn(handlebars, info.name, Box::new(info.helper));
+    }
+  }
 }
 
 /// Register a new handlebar helper function.
@@ -71,16 +73,79 @@ fn register_helper_fn(
   hbs.register_helper(name, helper_fn);
 }
 
-pub(crate) fn parse<T: Serialize>(
-  content: &str,
-  variables: &T,
-) -> Result<String> {
+/// Create a Handlebars registry in strict mode with the default helpers
+/// enabled by `policy`.
+fn registry<'reg>(policy: &Helpers) -> Handlebars<'reg> {
   let mut hb = Handlebars::new();
   hb.set_strict_mode(true);
 
   // Register default helpers.
-  register_default_helpers(&mut hb);
+  register_default_helpers(&mut hb, policy);
+  hb
+}
 
+pub(crate) fn parse<T: Serialize>(
+  content: &str,
+  variables: &T,
+  policy: &Helpers,
+) -> Result<String> {
+  registry(policy)
+    .render_template(content, variables)
+    .map_err(|e| Error::new(ErrorKind::ParseError, &e.to_string()))
+}
+
+/// Like [`parse`], without HTML-escaping the values of `{{variables}}`, e.g.
+/// for shell commands.
+pub(crate) fn parse_unescaped<T: Serialize>(
+  content: &str,
+  variables: &T,
+  policy: &Helpers,
+) -> Result<String> {
+  let mut hb = registry(policy);
+  hb.register_escape_fn(::handlebars::no_escape);
   hb.render_template(content, variables)
     .map_err(|e| Error::new(ErrorKind::ParseError, &e.to_string()))
 }
+
+/// Render the template read from `source` straight into `writer`, without
+/// buffering the rendered output.
+///
+/// With `trim_blocks`, the template is read first to drop the lines of its
+/// block tags, see [`trim_blocks`].
+pub(crate) fn render_to_write<T: Serialize, R: Read, W: Write>(
+  source: &mut R,
+  variables: &T,
+  writer: W,
+  policy: &Helpers,
+  trim_blocks: bool,
+) -> Result<()> {
+  let result = if trim_blocks {
+    let mut content = String::new();
+    source.read_to_string(&mut content)?;
+    registry(policy).render_template_to_write(
+      &self::trim_blocks(&content),
+      variables,
+      writer,
+    )
+  } else {
+    registry(policy).render_template_source_to_write(source, variables, writer)
+  };
+  result.map_err(|e| Error::new(ErrorKind::ParseError, &e.to_string()))
+}
+
+/// Drop the lines holding nothing but a block tag, e.g. `{{#if license}}`,
+/// `{{else}}` or `{{/if}}`, which are otherwise left as blank lines.
+///
+/// Handlebars' own `~` whitespace control, e.g. `{{~#if license~}}`, also
+/// trims the whitespace around a tag, but up to the next non-blank
+/// character, i.e. including the indentation of the next line.
+pub(crate) fn trim_blocks(content: &str) -> String {
+  super::trim_standalone(content, "{{", "}}", |tag| {
+    let tag = tag.trim_start_matches('~').trim_start();
+    tag.starts_with('#')
+      || tag.starts_with('/')
+      || tag.starts_with('^')
+      || tag.starts_with('!')
+      || tag.starts_with("else")
+  })
+}
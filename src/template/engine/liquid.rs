@@ -2,6 +2,15 @@ use crate::error::Result;
 
 use serde::Serialize;
 
+/// Drop the lines holding nothing but a tag, e.g. `{% if license %}` or
+/// `{% assign name = project %}`, which are otherwise left as blank lines.
+///
+/// Liquid's own `{%-` & `-%}` whitespace control also trims the whitespace
+/// around a tag, but up to the next non-blank character.
+pub(crate) fn trim_blocks(content: &str) -> String {
+  super::trim_standalone(content, "{%", "%}", |_| true)
+}
+
 pub(crate) fn parse<T: Serialize>(
   content: &str,
   variables: &T,
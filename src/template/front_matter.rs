@@ -0,0 +1,165 @@
+//! Per-file settings of rendered files, in a TOML front matter block
+//! delimited by `---` lines at the start of the file, which is dropped from
+//! the rendered file:
+//!
+//! ```txt
+//! ---
+//! path = "config/{{project-name}}.toml"
+//! engine = "liquid"
+//! skip_if = "(eq database \"none\")"
+//! strip_lines_matching = "^#\\s*note:"
+//! ---
+//! [database]
+//! url = "{{ database_url }}"
+//! ```
+//!
+//! Files without front matter are rendered as-is.
+//!
+use crate::template::engine::Engine;
+
+use serde::Deserialize;
+
+use std::{
+  fs::File,
+  io::{self, BufRead, BufReader},
+  path::Path,
+};
+
+/// Delimiter of the front matter block, alone on its line.
+const DELIMITER: &str = "---";
+
+/// Settings of a single file, overriding the template's.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FrontMatter {
+  /// Path of the rendered file relative to the project's directory, rendered
+  /// with the template's variables, instead of its renamed path.
+  pub(crate) path: Option<String>,
+  /// Engine the file is rendered with, instead of its extension's.
+  pub(crate) engine: Option<Engine>,
+  /// Handlebars expression, e.g. `docker` or `(eq license "none")`, the file
+  /// is left out of the project for when it's truthy.
+  pub(crate) skip_if: Option<String>,
+  /// Regex of the lines dropped from the rendered file, instead of the
+  /// template's `strip_lines_matching`.
+  pub(crate) strip_lines_matching: Option<String>,
+}
+
+/// Split the front matter of `content` from its body.
+///
+/// Returns `None` & the whole `content` if it doesn't start with a front
+/// matter block of these settings, e.g. with the YAML front matter of a
+/// Jekyll site, which is then rendered as-is.
+pub(crate) fn split(content: &str) -> (Option<FrontMatter>, &str) {
+  let rest = match strip_line(content) {
+    Some(rest) => rest,
+    None => return (None, content),
+  };
+
+  let mut offset = 0;
+  for line in rest.split_inclusive('\n') {
+    if let Some(body) = strip_line(&rest[offset..]) {
+      return match toml::from_str(&rest[..offset]) {
+        Ok(front_matter) => (Some(front_matter), body),
+        Err(err) => {
+          log::warn!("front matter left as-is: {}", err);
+          (None, content)
+        }
+      };
+    }
+    offset += line.len();
+  }
+
+  (None, content)
+}
+
+/// Read the front matter block at the start of the file `path`, with its
+/// delimiters, without reading the rest of the file, e.g. to plan it.
+///
+/// Returns `None` if the file doesn't start with a front matter block.
+pub(crate) fn read_block(path: &Path) -> io::Result<Option<String>> {
+  let mut reader = BufReader::new(File::open(path)?);
+  if !reader.fill_buf()?.starts_with(DELIMITER.as_bytes()) {
+    return Ok(None);
+  }
+
+  let mut block = String::new();
+  reader.read_line(&mut block)?;
+  if strip_line(&block).is_none() {
+    return Ok(None);
+  }
+  loop {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+      return Ok(None);
+    }
+    block.push_str(&line);
+    if strip_line(&line).is_some() {
+      return Ok(Some(block));
+    }
+  }
+}
+
+/// The rest of `content` after its first line, if it's the [`DELIMITER`].
+///
+/// [`DELIMITER`]: constant.DELIMITER.html
+fn strip_line(content: &str) -> Option<&str> {
+  let rest = content.strip_prefix(DELIMITER)?;
+  if rest.is_empty() {
+    return Some(rest);
+  }
+  rest
+    .strip_prefix("\r\n")
+    .or_else(|| rest.strip_prefix('\n'))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_split() {
+    let content =
+      "---\npath = \"app.toml\"\nengine = \"liquid\"\n---\nname = 1\n";
+    let (front_matter, body) = split(content);
+    assert_eq!(
+      front_matter,
+      Some(FrontMatter {
+        path: Some("app.toml".to_string()),
+        engine: Some(Engine::Liquid),
+        skip_if: None,
+        strip_lines_matching: None,
+      })
+    );
+    assert_eq!(body, "name = 1\n");
+    assert_eq!(split("---\nskip_if = \"docker\"\n---").1, "");
+
+    // Other files are left as-is.
+    for content in &[
+      "---- title\n",
+      "# ---\n",
+      "---\nskip_if = \"docker\"\n",
+      "---\nlayout: post\n---\n# Title\n",
+    ] {
+      assert_eq!(split(content), (None, *content));
+    }
+  }
+
+  #[test]
+  fn test_read_block() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.toml.hbs");
+    std::fs::write(&path, "---\npath = \"app.toml\"\n---\nname = 1\n").unwrap();
+    assert_eq!(
+      read_block(&path).unwrap().as_deref(),
+      Some("---\npath = \"app.toml\"\n---\n")
+    );
+
+    for content in &["# Title\n", "---\npath = \"app.toml\"\n", "----\n"] {
+      std::fs::write(&path, content).unwrap();
+      assert_eq!(read_block(&path).unwrap(), None);
+    }
+  }
+}
@@ -1,6 +1,134 @@
 use handlebars::{
   Context, Handlebars, Helper, HelperResult, Output, RenderContext,
+  RenderError, Renderable,
 };
+use heck::{CamelCase, KebabCase, SnakeCase, TitleCase};
+use serde_json::Value;
+
+use std::{
+  cmp::Ordering,
+  io::{self, Write},
+};
+
+/// Signature of the built-in helpers, which block helpers render their
+/// blocks with.
+pub(crate) type BuiltinHelper = for<'reg, 'rc> fn(
+  &Helper<'reg, 'rc>,
+  &'reg Handlebars<'reg>,
+  &'rc Context,
+  &mut RenderContext<'reg, 'rc>,
+  &mut dyn Output,
+) -> HelperResult;
+
+/// A built-in helper along with its usage, listed by `project helpers`.
+pub(crate) struct HelperInfo {
+  /// Name the helper is registered with.
+  pub(crate) name: &'static str,
+  /// Number of parameters, including the input.
+  pub(crate) arity: usize,
+  /// Usage example.
+  pub(crate) usage: &'static str,
+  /// The helper itself.
+  pub(crate) helper: BuiltinHelper,
+}
+
+/// Built-in helpers, in order of registration.
+pub(crate) const HELPERS: &[HelperInfo] = &[
+  HelperInfo {
+    name: "replace",
+    arity: 3,
+    usage: r#"{{replace input "Roger" "Brian"}}"#,
+    helper: replace,
+  },
+  HelperInfo {
+    name: "append",
+    arity: 2,
+    usage: r#"{{append input "-suffix"}}"#,
+    helper: append,
+  },
+  HelperInfo {
+    name: "prepend",
+    arity: 2,
+    usage: r#"{{prepend input "prefix-"}}"#,
+    helper: prepend,
+  },
+  HelperInfo {
+    name: "up",
+    arity: 1,
+    usage: "{{up input}}",
+    helper: up,
+  },
+  HelperInfo {
+    name: "low",
+    arity: 1,
+    usage: "{{low input}}",
+    helper: low,
+  },
+  HelperInfo {
+    name: "kebab",
+    arity: 1,
+    usage: "{{kebab input}}",
+    helper: kebab,
+  },
+  HelperInfo {
+    name: "snake",
+    arity: 1,
+    usage: "{{snake input}}",
+    helper: snake,
+  },
+  HelperInfo {
+    name: "pascal",
+    arity: 1,
+    usage: "{{pascal input}}",
+    helper: pascal,
+  },
+  HelperInfo {
+    name: "title",
+    arity: 1,
+    usage: "{{title input}}",
+    helper: title,
+  },
+  HelperInfo {
+    name: "columnize",
+    arity: 1,
+    usage: "{{columnize rows}}",
+    helper: columnize,
+  },
+  HelperInfo {
+    name: "if_eq",
+    arity: 2,
+    usage: r#"{{#if_eq lang "python"}}...{{else}}...{{/if_eq}}"#,
+    helper: if_eq,
+  },
+  HelperInfo {
+    name: "if_ne",
+    arity: 2,
+    usage: r#"{{#if_ne lang "python"}}...{{else}}...{{/if_ne}}"#,
+    helper: if_ne,
+  },
+  HelperInfo {
+    name: "if_gt",
+    arity: 2,
+    usage: r#"{{#if_gt replicas "1"}}...{{else}}...{{/if_gt}}"#,
+    helper: if_gt,
+  },
+  HelperInfo {
+    name: "if_lt",
+    arity: 2,
+    usage: r#"{{#if_lt replicas "3"}}...{{else}}...{{/if_lt}}"#,
+    helper: if_lt,
+  },
+];
+
+/// Write the name, arity & usage of every built-in helper into `out`, i.e.
+/// `project helpers`.
+pub fn write_helpers<W: Write>(out: &mut W) -> io::Result<()> {
+  for info in HELPERS {
+    writeln!(out, "{:<8} {}  {}", info.name, info.arity, info.usage)?;
+  }
+
+  Ok(())
+}
 
 /// Handlebars helper to replace a string by another in the vars.
 ///
@@ -109,6 +237,265 @@ pub fn prepend(
   Ok(())
 }
 
+/// Handlebars helper to convert the input to kebab-case.
+///
+/// ```properties
+/// {{kebab input}}
+/// ```
+pub fn kebab(
+  h: &Helper<'_, '_>,
+  _: &Handlebars<'_>,
+  _: &Context,
+  _rc: &mut RenderContext<'_, '_>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  // get parameter from helper or throw an error
+  let param = h.param(0).and_then(|v| v.value().as_str()).unwrap_or("");
+  out.write(param.to_kebab_case().as_ref())?;
+  Ok(())
+}
+
+/// Handlebars helper to convert the input to snake_case.
+///
+/// ```properties
+/// {{snake input}}
+/// ```
+pub fn snake(
+  h: &Helper<'_, '_>,
+  _: &Handlebars<'_>,
+  _: &Context,
+  _rc: &mut RenderContext<'_, '_>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  // get parameter from helper or throw an error
+  let param = h.param(0).and_then(|v| v.value().as_str()).unwrap_or("");
+  out.write(param.to_snake_case().as_ref())?;
+  Ok(())
+}
+
+/// Handlebars helper to convert the input to PascalCase.
+///
+/// ```properties
+/// {{pascal input}}
+/// ```
+pub fn pascal(
+  h: &Helper<'_, '_>,
+  _: &Handlebars<'_>,
+  _: &Context,
+  _rc: &mut RenderContext<'_, '_>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  // get parameter from helper or throw an error
+  let param = h.param(0).and_then(|v| v.value().as_str()).unwrap_or("");
+  out.write(param.to_camel_case().as_ref())?;
+  Ok(())
+}
+
+/// Handlebars helper to convert the input to Title Case.
+///
+/// ```properties
+/// {{title input}}
+/// ```
+pub fn title(
+  h: &Helper<'_, '_>,
+  _: &Handlebars<'_>,
+  _: &Context,
+  _rc: &mut RenderContext<'_, '_>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  // get parameter from helper or throw an error
+  let param = h.param(0).and_then(|v| v.value().as_str()).unwrap_or("");
+  out.write(param.to_title_case().as_ref())?;
+  Ok(())
+}
+
+/// Handlebars helper to render rows as a Markdown table, with the first row
+/// as its header & columns padded to their widest cell.
+///
+/// The rows are an array of arrays, or a variable holding one as JSON:
+/// ```properties
+/// {{columnize rows}}
+/// ```
+pub fn columnize(
+  h: &Helper<'_, '_>,
+  _: &Handlebars<'_>,
+  _: &Context,
+  _rc: &mut RenderContext<'_, '_>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  let param = h
+    .param(0)
+    .map(|v| v.value().clone())
+    .ok_or_else(|| RenderError::new("columnize: missing rows"))?;
+  // Variables are strings, so rows may be given as JSON.
+  let rows = match param {
+    Value::String(s) => serde_json::from_str(&s).map_err(|err| {
+      RenderError::new(format!("columnize: invalid rows: {}", err))
+    })?,
+    value => value,
+  };
+
+  let rows: Vec<Vec<String>> = rows
+    .as_array()
+    .ok_or_else(|| RenderError::new("columnize: rows must be an array"))?
+    .iter()
+    .map(|row| match row {
+      Value::Array(cells) => Ok(cells.iter().map(cell).collect()),
+      _ => Err(RenderError::new("columnize: each row must be an array")),
+    })
+    .collect::<Result<_, _>>()?;
+
+  let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+  // Separators need at least 3 dashes.
+  let mut widths = vec![3; columns];
+  for row in &rows {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
+    }
+  }
+
+  let line = |cells: &[String]| {
+    let cells: Vec<String> = widths
+      .iter()
+      .enumerate()
+      .map(|(i, width)| {
+        let cell = cells.get(i).map(String::as_str).unwrap_or("");
+        format!("{:<width$}", cell, width = width)
+      })
+      .collect();
+    format!("| {} |\n", cells.join(" | "))
+  };
+
+  let mut table = String::new();
+  for (i, row) in rows.iter().enumerate() {
+    table.push_str(&line(row));
+    if i == 0 {
+      let dashes: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+      table.push_str(&line(&dashes));
+    }
+  }
+  out.write(&table)?;
+  Ok(())
+}
+
+/// Handlebars block helper rendering its block if both parameters are equal,
+/// or its `{{else}}` block otherwise.
+///
+/// ```properties
+/// {{#if_eq lang "python"}}pip install{{else}}npm install{{/if_eq}}
+/// ```
+pub fn if_eq<'reg, 'rc>(
+  h: &Helper<'reg, 'rc>,
+  r: &'reg Handlebars<'reg>,
+  ctx: &'rc Context,
+  rc: &mut RenderContext<'reg, 'rc>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  let ordering = compare(h)?;
+  render_if(ordering == Ordering::Equal, h, r, ctx, rc, out)
+}
+
+/// Handlebars block helper rendering its block if both parameters differ,
+/// or its `{{else}}` block otherwise.
+///
+/// ```properties
+/// {{#if_ne license "none"}}See LICENSE.{{/if_ne}}
+/// ```
+pub fn if_ne<'reg, 'rc>(
+  h: &Helper<'reg, 'rc>,
+  r: &'reg Handlebars<'reg>,
+  ctx: &'rc Context,
+  rc: &mut RenderContext<'reg, 'rc>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  let ordering = compare(h)?;
+  render_if(ordering != Ordering::Equal, h, r, ctx, rc, out)
+}
+
+/// Handlebars block helper rendering its block if the first parameter is
+/// greater than the second, or its `{{else}}` block otherwise.
+///
+/// Parameters are compared as numbers if both are, e.g. `"10"` & `"9"`, and
+/// as text otherwise:
+/// ```properties
+/// {{#if_gt replicas "1"}}replicated{{else}}single{{/if_gt}}
+/// ```
+pub fn if_gt<'reg, 'rc>(
+  h: &Helper<'reg, 'rc>,
+  r: &'reg Handlebars<'reg>,
+  ctx: &'rc Context,
+  rc: &mut RenderContext<'reg, 'rc>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  let ordering = compare(h)?;
+  render_if(ordering == Ordering::Greater, h, r, ctx, rc, out)
+}
+
+/// Handlebars block helper rendering its block if the first parameter is
+/// less than the second, or its `{{else}}` block otherwise, comparing them
+/// like `if_gt`.
+///
+/// Numbers are compared as floats, so versions like `"3.10"` & `"3.8"` aren't
+/// compared as versions, `3.10` being less than `3.8`:
+/// ```properties
+/// {{#if_lt replicas "3"}}single zone{{else}}multi zone{{/if_lt}}
+/// ```
+pub fn if_lt<'reg, 'rc>(
+  h: &Helper<'reg, 'rc>,
+  r: &'reg Handlebars<'reg>,
+  ctx: &'rc Context,
+  rc: &mut RenderContext<'reg, 'rc>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  let ordering = compare(h)?;
+  render_if(ordering == Ordering::Less, h, r, ctx, rc, out)
+}
+
+/// Compare the two parameters of a comparison helper, as numbers if both
+/// are, e.g. variables which are always strings, and as text otherwise.
+fn compare(h: &Helper<'_, '_>) -> Result<Ordering, RenderError> {
+  let (a, b) = match (h.param(0), h.param(1)) {
+    (Some(a), Some(b)) => (cell(a.value()), cell(b.value())),
+    _ => {
+      return Err(RenderError::new(format!(
+        "{}: expected 2 parameters",
+        h.name()
+      )))
+    }
+  };
+
+  match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+    (Ok(a), Ok(b)) => Ok(a.partial_cmp(&b).unwrap_or(Ordering::Equal)),
+    _ => Ok(a.cmp(&b)),
+  }
+}
+
+/// Render the block of helper `h` if `condition` holds, or its `{{else}}`
+/// block otherwise, if any.
+fn render_if<'reg, 'rc>(
+  condition: bool,
+  h: &Helper<'reg, 'rc>,
+  r: &'reg Handlebars<'reg>,
+  ctx: &'rc Context,
+  rc: &mut RenderContext<'reg, 'rc>,
+  out: &mut dyn Output,
+) -> HelperResult {
+  let block = if condition { h.template() } else { h.inverse() };
+  match block {
+    Some(block) => block.render(r, ctx, rc, out),
+    None => Ok(()),
+  }
+}
+
+/// Text of a table cell, without the quotes of strings.
+fn cell(value: &Value) -> String {
+  match value {
+    Value::String(s) => s.clone(),
+    Value::Null => String::new(),
+    value => value.to_string(),
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -216,4 +603,99 @@ mod tests {
     println!("{}", res);
     assert_eq!(res, "Repeat after me: Brian is in the kitchen.");
   }
+
+  #[test]
+  fn should_change_case() {
+    setup();
+    let mut vars = BTreeMap::new();
+    vars.insert("name", "my cool project");
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_helper("kebab", Box::new(kebab));
+    handlebars.register_helper("snake", Box::new(snake));
+    handlebars.register_helper("pascal", Box::new(pascal));
+    handlebars.register_helper("title", Box::new(title));
+    let res = handlebars
+      .render_template(
+        "{{kebab name}} {{snake name}} {{pascal name}} {{title name}}",
+        &vars,
+      )
+      .unwrap();
+    println!("{}", res);
+    assert_eq!(
+      res,
+      "my-cool-project my_cool_project MyCoolProject My Cool Project"
+    );
+  }
+
+  #[test]
+  fn should_columnize() {
+    setup();
+    let mut vars = BTreeMap::new();
+    vars.insert("rows", r#"[["Crate", "Version"], ["serde", 1.0]]"#);
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_helper("columnize", Box::new(columnize));
+    let res = handlebars
+      .render_template("{{columnize rows}}", &vars)
+      .unwrap();
+    println!("{}", res);
+    assert_eq!(
+      res,
+      "| Crate | Version |\n| ----- | ------- |\n| serde | 1.0     |\n"
+    );
+
+    vars.insert("rows", r#""not rows""#);
+    assert!(handlebars
+      .render_template("{{columnize rows}}", &vars)
+      .is_err());
+  }
+
+  #[test]
+  fn should_compare() {
+    setup();
+    let mut vars = BTreeMap::new();
+    vars.insert("lang", "python");
+    vars.insert("replicas", "10");
+
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    handlebars.register_helper("if_eq", Box::new(if_eq));
+    handlebars.register_helper("if_gt", Box::new(if_gt));
+    let template =
+      r#"{{#if_eq lang "python"}}pip {{lang}}{{else}}npm{{/if_eq}}"#;
+    let res = handlebars.render_template(template, &vars).unwrap();
+    assert_eq!(res, "pip python");
+
+    vars.insert("lang", "javascript");
+    let res = handlebars.render_template(template, &vars).unwrap();
+    assert_eq!(res, "npm");
+
+    // Numbers aren't compared as text.
+    let res = handlebars
+      .render_template(r#"{{#if_gt replicas "9"}}many{{/if_gt}}"#, &vars)
+      .unwrap();
+    assert_eq!(res, "many");
+
+    assert!(handlebars
+      .render_template("{{#if_eq lang}}{{/if_eq}}", &vars)
+      .is_err());
+  }
+
+  #[test]
+  fn should_list_helpers() {
+    let mut out = Vec::new();
+    write_helpers(&mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    for usage in &[
+      r#"replace  3  {{replace input "Roger" "Brian"}}"#,
+      r#"append   2  {{append input "-suffix"}}"#,
+      r#"prepend  2  {{prepend input "prefix-"}}"#,
+      "up       1  {{up input}}",
+      "low      1  {{low input}}",
+    ] {
+      assert!(out.lines().any(|line| line == *usage), "{}", out);
+    }
+  }
 }
@@ -0,0 +1,170 @@
+//! Variables inherited from the manifest of a parent project, i.e.
+//! `--inherit-from`, e.g. to scaffold a crate or package within it.
+//!
+//! Well-known fields of a `Cargo.toml` or `package.json` are mapped into
+//! variables, which `-D key=value` still overrides:
+//!
+//! | Variable      | `Cargo.toml`                        | `package.json`             |
+//! | ------------- | ----------------------------------- | -------------------------- |
+//! | `author`      | first of `authors`                  | `author`                   |
+//! | `authors`     | `authors`, comma-separated          | `author` & `contributors`  |
+//! | `license`     | `license`                           | `license`                  |
+//! | `edition`     | `edition`                           |                            |
+//! | `parent_name` | `name`                              | `name`                     |
+//! | `name_prefix` | `name` & `-`, e.g. `tokio-`         | scope, e.g. `@babel/`      |
+//!
+//! Fields are read from `[package]`, falling back to `[workspace.package]`.
+//!
+use crate::error::{Error, ErrorKind, Result};
+
+use serde_json::Value;
+
+use std::{collections::HashMap, fs, path::Path};
+
+/// Variables inherited from the manifest at `path`, parsed as a
+/// `package.json` if its extension is `.json`, or as a `Cargo.toml`
+/// otherwise. Missing fields are left out.
+pub(crate) fn inherit_from(path: &Path) -> Result<HashMap<String, String>> {
+  let content = fs::read_to_string(path)?;
+  let invalid = |err: String| {
+    Error::new(
+      ErrorKind::ParseError,
+      &format!("invalid manifest {:?}: {}", path, err),
+    )
+  };
+
+  if path.extension() == Some("json".as_ref()) {
+    let manifest: Value =
+      serde_json::from_str(&content).map_err(|err| invalid(err.to_string()))?;
+    Ok(from_package_json(&manifest))
+  } else {
+    let manifest: toml::Value =
+      toml::from_str(&content).map_err(|err| invalid(err.to_string()))?;
+    Ok(from_cargo_toml(&manifest))
+  }
+}
+
+/// Variables of a `Cargo.toml`.
+fn from_cargo_toml(manifest: &toml::Value) -> HashMap<String, String> {
+  let workspace = manifest.get("workspace").and_then(|w| w.get("package"));
+  let field = |name: &str| {
+    manifest
+      .get("package")
+      .and_then(|package| package.get(name))
+      .or_else(|| workspace.and_then(|package| package.get(name)))
+  };
+
+  let mut variables = HashMap::new();
+  let authors: Vec<&str> = field("authors")
+    .and_then(toml::Value::as_array)
+    .map(|authors| authors.iter().filter_map(toml::Value::as_str).collect())
+    .unwrap_or_default();
+  insert_authors(&mut variables, &authors);
+  for name in &["license", "edition"] {
+    if let Some(value) = field(name).and_then(toml::Value::as_str) {
+      variables.insert(name.to_string(), value.to_string());
+    }
+  }
+  if let Some(name) = field("name").and_then(toml::Value::as_str) {
+    variables.insert("parent_name".to_string(), name.to_string());
+    variables.insert("name_prefix".to_string(), format!("{}-", name));
+  }
+
+  variables
+}
+
+/// Variables of a `package.json`.
+fn from_package_json(manifest: &Value) -> HashMap<String, String> {
+  let mut variables = HashMap::new();
+  let authors: Vec<String> = manifest
+    .get("author")
+    .into_iter()
+    .chain(
+      manifest
+        .get("contributors")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten(),
+    )
+    .filter_map(person)
+    .collect();
+  let authors: Vec<&str> = authors.iter().map(String::as_str).collect();
+  insert_authors(&mut variables, &authors);
+  if let Some(license) = manifest.get("license").and_then(Value::as_str) {
+    variables.insert("license".to_string(), license.to_string());
+  }
+  if let Some(name) = manifest.get("name").and_then(Value::as_str) {
+    variables.insert("parent_name".to_string(), name.to_string());
+    // Packages of a scope share it, others the parent's name.
+    let prefix = match name.find('/') {
+      Some(slash) if name.starts_with('@') => name[..=slash].to_string(),
+      _ => format!("{}-", name),
+    };
+    variables.insert("name_prefix".to_string(), prefix);
+  }
+
+  variables
+}
+
+/// An npm person, either `"Name <email>"` or `{ "name", "email" }`.
+fn person(value: &Value) -> Option<String> {
+  match value {
+    Value::String(person) => Some(person.clone()),
+    Value::Object(person) => {
+      let name = person.get("name")?.as_str()?;
+      match person.get("email").and_then(Value::as_str) {
+        Some(email) => Some(format!("{} <{}>", name, email)),
+        None => Some(name.to_string()),
+      }
+    }
+    _ => None,
+  }
+}
+
+/// Insert the first of `authors` as `author`, and all of them as `authors`.
+fn insert_authors(variables: &mut HashMap<String, String>, authors: &[&str]) {
+  if let Some(author) = authors.first() {
+    variables.insert("author".to_string(), author.to_string());
+    variables.insert("authors".to_string(), authors.join(", "));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_inherit_from() {
+    let dir = tempfile::tempdir().unwrap();
+    let cargo = dir.path().join("Cargo.toml");
+    fs::write(
+      &cargo,
+      "[package]\nname = \"acme\"\nauthors = [\"Jane Doe <jane@acme.dev>\", \"John Doe\"]\nlicense = \"MIT OR Apache-2.0\"\nedition = \"2018\"\n\n[dependencies]\nserde = \"1.0\"\n",
+    )
+    .unwrap();
+    let variables = inherit_from(&cargo).unwrap();
+    assert_eq!(variables["author"], "Jane Doe <jane@acme.dev>");
+    assert_eq!(variables["authors"], "Jane Doe <jane@acme.dev>, John Doe");
+    assert_eq!(variables["license"], "MIT OR Apache-2.0");
+    assert_eq!(variables["edition"], "2018");
+    assert_eq!(variables["name_prefix"], "acme-");
+
+    let package = dir.path().join("package.json");
+    fs::write(
+      &package,
+      r#"{ "name": "@acme/core", "license": "ISC", "author": { "name": "Jane Doe", "email": "jane@acme.dev" } }"#,
+    )
+    .unwrap();
+    let variables = inherit_from(&package).unwrap();
+    assert_eq!(variables["author"], "Jane Doe <jane@acme.dev>");
+    assert_eq!(variables["license"], "ISC");
+    assert_eq!(variables["name_prefix"], "@acme/");
+    assert!(!variables.contains_key("edition"));
+
+    fs::write(&cargo, "[package\n").unwrap();
+    let err = inherit_from(&cargo).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::ParseError);
+  }
+}
@@ -0,0 +1,62 @@
+//! Lock file recording the exact commit a remote template resolved to.
+//!
+//! ```toml
+//! # project.lock
+//! remote = "https://github.com/owner/repo.git"
+//! commit = "0b9a6f6e8f7d2d6c3f5c1e4b5a3d2c1b0a9f8e7d"
+//! ```
+//!
+use crate::error::{Error, ErrorKind, Result};
+
+use serde::{Deserialize, Serialize};
+
+use std::{fs, path::Path};
+
+/// Lock file written into the generated project.
+pub(crate) const LOCK_FILE: &str = "project.lock";
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub(crate) struct TemplateLock {
+  /// URL of the remote template.
+  pub(crate) remote: String,
+  /// SHA of the commit the remote template resolved to.
+  pub(crate) commit: String,
+}
+
+impl TemplateLock {
+  /// Read the lock file in `project_dir`.
+  pub(crate) fn read(project_dir: &Path) -> Result<TemplateLock> {
+    let path = project_dir.join(LOCK_FILE);
+    if !path.exists() {
+      return Err(Error::new(
+        ErrorKind::NotFound,
+        &format!("No lock file found at {}", path.display()),
+      ));
+    }
+
+    Ok(toml::from_str(&fs::read_to_string(path)?)?)
+  }
+
+  /// Write the lock file into `project_dir`.
+  pub(crate) fn write(&self, project_dir: &Path) -> Result<()> {
+    let content = toml::to_string(self)
+      .map_err(|e| Error::new(ErrorKind::ParseError, &e.to_string()))?;
+    fs::write(project_dir.join(LOCK_FILE), content)?;
+    Ok(())
+  }
+
+  /// Returns an error if `other` doesn't match the locked template.
+  pub(crate) fn verify(&self, other: &TemplateLock) -> Result<()> {
+    if self == other {
+      return Ok(());
+    }
+
+    Err(Error::new(
+      ErrorKind::GitError,
+      &format!(
+        "Template resolved to {} at {}, but {} is locked to {} at {}",
+        other.remote, other.commit, LOCK_FILE, self.remote, self.commit
+      ),
+    ))
+  }
+}
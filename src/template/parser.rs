@@ -1,12 +1,21 @@
-use crate::{authors, error::Result};
+use crate::{
+  authors,
+  error::{Error, ErrorKind, Result},
+  info::ProjectInfo,
+};
 
-use regex::Regex;
-use std::{fs, io::Read, path::Path};
+use regex::{Captures, Regex};
+use std::{collections::HashMap, env, fs, io::Read, path::Path};
 
 /// Default variables substitution in `template.toml`.
 ///
 /// - `{{ project-name }}` - Project name.
 ///
+/// - `{{ project-name-title }}`, `{{ project-name-upper-camel }}`,
+///   `{{ project-name-lower-camel }}` & `{{ project-name-screaming-snake }}` -
+///   Project name in Title Case, UpperCamelCase, lowerCamelCase &
+///   SCREAMING_SNAKE_CASE.
+///
 /// - `{{ author-name }}` - Author's name, gotten from Git config.
 ///
 /// - `{{ author-email }}` - Author's email address, gotten from Git config.
@@ -17,8 +26,15 @@ fn default_variables(
   author_email: &str,
 ) -> Result<String> {
   // Project name.
-  let result = Regex::new(r"\{\{\s?project-name\s?\}\}")?
-    .replace_all(haystack, project_name);
+  let mut result = Regex::new(r"\{\{\s?project-name\s?\}\}")?
+    .replace_all(haystack, project_name)
+    .to_string();
+  for (name, value) in name_cases(project_name) {
+    let pattern = format!(r"\{{\{{\s?{}\s?\}}\}}", name);
+    result = Regex::new(&pattern)?
+      .replace_all(&result, value.as_str())
+      .to_string();
+  }
 
   // Author name.
   let result =
@@ -68,6 +84,133 @@ pub(super) fn parse_template_file(
   )
 }
 
+/// Values of the [`default_variables`], which are also available to the
+/// templating engines, even without a `template.toml` file.
+///
+/// [`default_variables`]: fn.default_variables
+pub(crate) fn builtin_variables(project_name: &str) -> HashMap<String, String> {
+  let (author_name, author_email) =
+    authors::discover_author().unwrap_or_default();
+
+  let mut variables: HashMap<String, String> = name_cases(project_name)
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect();
+  variables.insert("project-name".to_string(), project_name.to_string());
+  variables.insert("author-name".to_string(), author_name);
+  variables
+    .insert("author-email".to_string(), author_email.unwrap_or_default());
+  variables
+}
+
+/// The `project-name-*` placeholders of `project_name` in each case, see
+/// [`ProjectInfo`].
+///
+/// [`ProjectInfo`]: ../../struct.ProjectInfo.html
+fn name_cases(project_name: &str) -> Vec<(&'static str, String)> {
+  let project = ProjectInfo {
+    name: project_name.to_string(),
+    path: project_name.into(),
+    created: false,
+  };
+  vec![
+    ("project-name-title", project.name_title_case()),
+    ("project-name-upper-camel", project.name_upper_camel_case()),
+    ("project-name-lower-camel", project.name_lower_camel_case()),
+    (
+      "project-name-screaming-snake",
+      project.name_screaming_snake_case(),
+    ),
+  ]
+}
+
+/// Expand environment variable references in `value`.
+///
+/// - `${VAR}` - Replaced by the value of `$VAR` or an empty string if unset.
+///
+/// - `${VAR:-fallback}` - Replaced by the value of `$VAR` or `fallback` if unset.
+pub(crate) fn expand_env(value: &str) -> Result<String> {
+  let result = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?\}")?
+    .replace_all(value, |caps: &Captures| match env::var(&caps[1]) {
+      Ok(var) if !var.is_empty() => var,
+      _ => caps.get(2).map(|m| m.as_str()).unwrap_or("").to_string(),
+    });
+
+  Ok(result.to_string())
+}
+
+/// Parse the `KEY=value` lines of a dotenv file.
+///
+/// - Blank lines & lines starting with `#` are ignored.
+///
+/// - An optional `export ` prefix is allowed, e.g `export KEY=value`.
+///
+/// - Unquoted values are trimmed & anything after a whitespace followed by
+///   `#` is a comment.
+///
+/// - Single-quoted values are taken literally, while double-quoted values
+///   expand the `\n`, `\t`, `\"` & `\\` escapes.
+pub(crate) fn parse_env_file(content: &str) -> Result<HashMap<String, String>> {
+  let line_re =
+    Regex::new(r"^\s*(?:export\s+)?([A-Za-z_][A-Za-z0-9_.-]*)\s*=\s*(.*)$")?;
+
+  let mut variables = HashMap::new();
+  for (i, line) in content.lines().enumerate() {
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+      continue;
+    }
+
+    let value = line_re
+      .captures(line)
+      .and_then(|caps| Some((caps[1].to_string(), env_value(&caps[2])?)));
+    match value {
+      Some((key, value)) => variables.insert(key, value),
+      None => {
+        return Err(Error::new(
+          ErrorKind::ParseError,
+          &format!("Invalid line {} in env file: {:?}", i + 1, line),
+        ))
+      }
+    };
+  }
+
+  Ok(variables)
+}
+
+/// Value of a dotenv variable, or `None` if a quoted value isn't closed.
+fn env_value(raw: &str) -> Option<String> {
+  let mut chars = raw.chars();
+  match chars.next() {
+    Some('\'') => {
+      let rest = chars.as_str();
+      rest.find('\'').map(|end| rest[..end].to_string())
+    }
+    Some('"') => {
+      let mut value = String::new();
+      while let Some(c) = chars.next() {
+        match c {
+          '"' => return Some(value),
+          '\\' => match chars.next()? {
+            'n' => value.push('\n'),
+            't' => value.push('\t'),
+            other => value.push(other),
+          },
+          _ => value.push(c),
+        }
+      }
+      None
+    }
+    _ => {
+      let value = match raw.find(" #").or_else(|| raw.find("\t#")) {
+        Some(comment) => &raw[..comment],
+        None => raw,
+      };
+      Some(value.trim().to_string())
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -81,6 +224,7 @@ email = "[{{author-name}} <{{author-email}}>]"
 
 [directories]
 template = "{{project-name}}"
+module = "{{ project-name-upper-camel }}"
   "#;
 
     let expected_str = r#"
@@ -90,6 +234,7 @@ email = "[Victor I. Afolabi <vafolabi@lotlinx.com>]"
 
 [directories]
 template = "lotlinx"
+module = "Lotlinx"
   "#;
 
     let res = default_variables(
@@ -104,4 +249,44 @@ template = "lotlinx"
       assert_eq!(expected_str, &expected);
     }
   }
+
+  #[test]
+  fn test_expand_env() {
+    env::set_var("PROJECT_TEST_EXPAND_ENV", "Jane Doe");
+    env::remove_var("PROJECT_TEST_EXPAND_ENV_UNSET");
+
+    let res = expand_env("${PROJECT_TEST_EXPAND_ENV:-John Doe}").unwrap();
+    assert_eq!(res, "Jane Doe");
+
+    let res = expand_env("${PROJECT_TEST_EXPAND_ENV_UNSET:-John Doe}").unwrap();
+    assert_eq!(res, "John Doe");
+
+    let res = expand_env("<${PROJECT_TEST_EXPAND_ENV_UNSET}>").unwrap();
+    assert_eq!(res, "<>");
+
+    let res = expand_env("no references").unwrap();
+    assert_eq!(res, "no references");
+  }
+
+  #[test]
+  fn test_parse_env_file() {
+    let content = r#"
+# Service settings.
+service_name=billing
+export DB_NAME = 'billing db' # comment
+GREETING="Hello \"world\"\n"
+EMPTY=
+URL=https://example.com/#anchor
+"#;
+
+    let variables = parse_env_file(content).unwrap();
+    assert_eq!(variables["service_name"], "billing");
+    assert_eq!(variables["DB_NAME"], "billing db");
+    assert_eq!(variables["GREETING"], "Hello \"world\"\n");
+    assert_eq!(variables["EMPTY"], "");
+    assert_eq!(variables["URL"], "https://example.com/#anchor");
+    assert_eq!(variables.len(), 5);
+
+    assert!(parse_env_file("not a variable").is_err());
+  }
 }
@@ -0,0 +1,84 @@
+//! Interactive prompts.
+//!
+use crate::error::Result;
+
+use console::style;
+
+use std::io::{BufRead, Write};
+
+/// Ask a `question` on `output` and read the answer from `input`.
+///
+/// The `default` is shown alongside the question and returned if the
+/// answer is empty.
+pub(crate) fn ask<R: BufRead, W: Write>(
+  input: &mut R,
+  output: &mut W,
+  question: &str,
+  default: &str,
+) -> Result<String> {
+  write!(
+    output,
+    "{} [{}]: ",
+    style(question).bold(),
+    style(default).dim()
+  )?;
+  output.flush()?;
+
+  let mut answer = String::new();
+  input.read_line(&mut answer)?;
+
+  let answer = answer.trim();
+  if answer.is_empty() {
+    Ok(default.to_string())
+  } else {
+    Ok(answer.to_string())
+  }
+}
+
+/// Ask a yes/no `question` on `output` and read the answer from `input`.
+///
+/// The `default` is returned if the answer is empty.
+pub(crate) fn confirm<R: BufRead, W: Write>(
+  input: &mut R,
+  output: &mut W,
+  question: &str,
+  default: bool,
+) -> Result<bool> {
+  let choices = if default { "Y/n" } else { "y/N" };
+  write!(output, "{} [{}] ", style(question).bold(), choices)?;
+  output.flush()?;
+
+  let mut answer = String::new();
+  input.read_line(&mut answer)?;
+
+  match answer.trim().to_lowercase().as_str() {
+    "" => Ok(default),
+    "y" | "yes" => Ok(true),
+    _ => Ok(false),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  use std::io::Cursor;
+
+  #[test]
+  fn test_confirm() {
+    let mut output = Vec::new();
+    for (answer, default, expected) in &[
+      ("\n", true, true),
+      ("\n", false, false),
+      ("y\n", false, true),
+      ("Yes\n", false, true),
+      ("n\n", true, false),
+      ("nope\n", true, false),
+    ] {
+      let mut input = Cursor::new(*answer);
+      let res = confirm(&mut input, &mut output, "Continue?", *default);
+      assert_eq!(res.unwrap(), *expected);
+    }
+  }
+}
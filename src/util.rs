@@ -135,6 +135,116 @@ pub fn diff_paths(path: &Path, base: &Path) -> Option<PathBuf> {
   }
 }
 
+/// Resolves the `.` & `..` components of `path` without accessing the file
+/// system, e.g. since symlink targets may not exist yet.
+///
+/// # Example
+///
+/// ```rust
+/// # use project::util::normalize;
+/// # use std::path::{Path, PathBuf};
+///
+/// # fn main() {
+/// let path = Path::new("/foo/./bar/../baz");
+/// assert_eq!(normalize(path), PathBuf::from("/foo/baz"));
+/// # }
+/// ```
+pub fn normalize(path: &Path) -> PathBuf {
+  let mut normalized = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        normalized.pop();
+      }
+      component => normalized.push(component),
+    }
+  }
+  normalized
+}
+
+/// Quote `arg` to be passed as a single argument in a shell command, i.e.
+/// between single quotes with `sh`, or double quotes with `cmd` on Windows.
+/// Arguments without special characters are left as-is.
+///
+/// # Example
+///
+/// ```rust
+/// # use project::util::shell_quote;
+///
+/// # fn main() {
+///
+/// # #[cfg(unix)] {
+/// assert_eq!(shell_quote("--fast"), "--fast");
+/// assert_eq!(shell_quote("my project"), "'my project'");
+/// assert_eq!(shell_quote("it's"), r"'it'\''s'");
+/// # }
+///
+/// # }
+/// ```
+pub fn shell_quote(arg: &str) -> String {
+  let plain = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+  if !arg.is_empty() && arg.chars().all(plain) {
+    return arg.to_string();
+  }
+
+  if cfg!(windows) {
+    format!("\"{}\"", arg.replace('"', "\\\""))
+  } else {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+  }
+}
+
+/// System directories a project is never generated into, on top of the root
+/// & home directories.
+#[cfg(unix)]
+const SYSTEM_DIRS: &[&str] = &[
+  "/bin", "/boot", "/dev", "/etc", "/lib", "/opt", "/proc", "/sbin", "/sys",
+  "/usr", "/var",
+];
+#[cfg(windows)]
+const SYSTEM_DIRS: &[&str] = &[
+  "C:\\Windows",
+  "C:\\Program Files",
+  "C:\\Program Files (x86)",
+];
+#[cfg(not(any(unix, windows)))]
+const SYSTEM_DIRS: &[&str] = &[];
+
+/// Whether `path` is a directory generating a project into would be
+/// catastrophic, i.e. the root, home or a system directory, rather than a
+/// project's.
+///
+/// # Example
+///
+/// ```rust
+/// # use project::util::is_sensitive_dir;
+/// # use std::path::Path;
+///
+/// # fn main() {
+///
+/// # #[cfg(unix)] {
+/// assert!(is_sensitive_dir(Path::new("/")));
+/// assert!(is_sensitive_dir(Path::new("/usr")));
+/// assert!(!is_sensitive_dir(Path::new("/usr/src/my-project")));
+/// # }
+///
+/// # }
+/// ```
+pub fn is_sensitive_dir(path: &Path) -> bool {
+  let canonical =
+    |path: &Path| path.canonicalize().unwrap_or_else(|_| path.into());
+  let path = canonical(path);
+  if path.parent().is_none() {
+    return true;
+  }
+
+  dirs::home_dir()
+    .into_iter()
+    .chain(SYSTEM_DIRS.iter().map(PathBuf::from))
+    .any(|dir| canonical(&dir) == path)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -168,4 +278,15 @@ mod tests {
     assert_eq!(diff_paths(&baz, &quux), Some("../baz".into()));
     assert_eq!(diff_paths(&bar, &quux), Some("../".into()));
   }
+
+  #[test]
+  fn test_normalize() {
+    assert_eq!(normalize(Path::new("foo/./bar")), PathBuf::from("foo/bar"));
+    assert_eq!(
+      normalize(Path::new("foo/bar/../baz")),
+      PathBuf::from("foo/baz")
+    );
+    // Parents of the root are the root.
+    assert_eq!(normalize(Path::new("/../etc")), PathBuf::from("/etc"));
+  }
 }
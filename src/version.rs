@@ -0,0 +1,69 @@
+//! Version & build information, printed with `--version`.
+//!
+use crate::template::engine;
+
+use serde::Serialize;
+
+use std::fmt;
+
+/// Version & build information of `project`.
+#[derive(Debug, Serialize)]
+pub struct Version {
+  /// Crate version.
+  pub version: &'static str,
+  /// Commit `project` was built from, or `"unknown"` if it wasn't built
+  /// from a git repository.
+  pub git_sha: &'static str,
+  /// Compiled-in templating engines, by file extension.
+  pub features: Vec<&'static str>,
+}
+
+impl Version {
+  pub fn new() -> Version {
+    Version {
+      version: clap::crate_version!(),
+      git_sha: env!("PROJECT_GIT_SHA"),
+      features: engine::EXTENSIONS.to_vec(),
+    }
+  }
+
+  /// Version information as a JSON object, i.e. `--format json`.
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).expect("version is serializable")
+  }
+}
+
+impl Default for Version {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl fmt::Display for Version {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      "{} {} ({})",
+      clap::crate_name!(),
+      self.version,
+      self.git_sha
+    )?;
+    write!(f, "engines: {}", self.features.join(", "))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_version_json() {
+    let json: serde_json::Value =
+      serde_json::from_str(&Version::new().to_json()).unwrap();
+
+    assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(json["git_sha"], env!("PROJECT_GIT_SHA"));
+    assert_eq!(json["features"], serde_json::json!(["hbs", "liquid"]));
+  }
+}
@@ -0,0 +1,261 @@
+//! Regenerate a project whenever its local template changes, i.e. `--watch`,
+//! while authoring the template.
+//!
+//! ```sh
+//! $ project --watch new path/to/template my-project
+//! ```
+//!
+//! Only the changed template files are rendered again, like with
+//! `render-only`, unless the template's config file changed or files were
+//! removed or renamed, which regenerates the whole project. The files it no
+//! longer generates are then removed from the project. The project's files
+//! which changed are reported every time, unless `--quiet`.
+//!
+use crate::{
+  diff::{Snapshot, SnapshotReport},
+  emoji,
+  error::{Error, ErrorKind, Result},
+  info::TemplateOptions,
+  template::{config, Template},
+  Arguments,
+};
+
+use console::style;
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+
+use std::{
+  collections::{BTreeSet, HashSet},
+  fs,
+  panic::{self, AssertUnwindSafe},
+  path::{Path, PathBuf},
+  sync::mpsc,
+  time::Duration,
+};
+
+/// Delay the events of a file are debounced by, e.g. an editor saving it in
+/// several writes.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Template files regenerated after a change.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Regenerate {
+  /// Only these files, relative to the template's directory.
+  Files(HashSet<PathBuf>),
+  /// Every file of the template.
+  All,
+}
+
+/// Generate the project of `args`, then regenerate it whenever a file of its
+/// local template changes, until watching the template fails.
+///
+/// Failed regenerations are reported, but don't stop watching, e.g. until a
+/// broken template file is fixed.
+pub fn watch(args: &Arguments) -> Result<()> {
+  let template_dir = match &args.template {
+    TemplateOptions::Local(dir) => dir.canonicalize()?,
+    _ => {
+      return Err(Error::new(
+        ErrorKind::Config,
+        "--watch only watches local templates",
+      ))
+    }
+  };
+  let project_dir = args.project.path();
+  let config_name =
+    args.config_name.as_deref().unwrap_or(config::TEMPLATE_FILE);
+
+  let mut generated = Template::from(args).generate()?.generated;
+
+  let (tx, rx) = mpsc::channel();
+  let mut watcher = notify::watcher(tx, DEBOUNCE)?;
+  watcher.watch(&template_dir, RecursiveMode::Recursive)?;
+  if args.verbosity.shows_progress() {
+    eprintln!(
+      "{} {}",
+      style("Watching").bold(),
+      style(template_dir.display()).bold().white()
+    );
+  }
+
+  for event in rx {
+    let files =
+      match regenerate_for(&event, &template_dir, &project_dir, config_name) {
+        Some(files) => files,
+        None => continue,
+      };
+    match regenerate(args, &files, &mut generated) {
+      Ok(report) if args.verbosity.shows_progress() => print!("{}", report),
+      Ok(_) => {}
+      Err(err) if args.verbosity.shows_errors() => {
+        eprintln!("{} {}", emoji::ERROR, style(err).bold().red())
+      }
+      Err(_) => {}
+    }
+  }
+
+  Ok(())
+}
+
+/// Template files to regenerate after `event`, or `None` if it's ignored.
+fn regenerate_for(
+  event: &DebouncedEvent,
+  template_dir: &Path,
+  project_dir: &Path,
+  config_name: &str,
+) -> Option<Regenerate> {
+  let path = match event {
+    DebouncedEvent::Create(path) | DebouncedEvent::Write(path) => path,
+    DebouncedEvent::Remove(path) | DebouncedEvent::Rename(path, _) => {
+      return Some(Regenerate::All).filter(|_| !path.starts_with(project_dir))
+    }
+    DebouncedEvent::Rescan => return Some(Regenerate::All),
+    DebouncedEvent::Error(err, path) => {
+      log::warn!("couldn't watch {:?}: {}", path, err);
+      return None;
+    }
+    // Notices precede the debounced events, & permissions aren't rendered.
+    DebouncedEvent::NoticeWrite(_)
+    | DebouncedEvent::NoticeRemove(_)
+    | DebouncedEvent::Chmod(_) => return None,
+  };
+
+  // A project generated within its template mustn't regenerate itself.
+  if path.is_dir() || path.starts_with(project_dir) {
+    return None;
+  }
+  let relative = path.strip_prefix(template_dir).ok()?;
+  if relative.file_name() == Some(config_name.as_ref()) {
+    return Some(Regenerate::All);
+  }
+
+  Some(Regenerate::Files(
+    std::iter::once(relative.to_path_buf()).collect(),
+  ))
+}
+
+/// Regenerate the `files` of the project of `args`, reporting which of the
+/// project's files changed.
+///
+/// `generated` are the project's files generated so far. Those the whole
+/// template no longer generates, e.g. after a template file is removed, are
+/// removed from the project, while files added to it by hand are kept.
+pub(crate) fn regenerate(
+  args: &Arguments,
+  files: &Regenerate,
+  generated: &mut BTreeSet<PathBuf>,
+) -> Result<SnapshotReport> {
+  let snapshot = Snapshot::take(&args.project.path())?;
+
+  // The template is loaded again since its config file may have changed, and
+  // panics if it's broken.
+  let template = panic::catch_unwind(AssertUnwindSafe(|| Template::from(args)))
+    .map_err(|_| Error::new(ErrorKind::Config, "couldn't load the template"))?;
+  let changed = match files {
+    Regenerate::Files(files) => Some(files),
+    Regenerate::All => None,
+  };
+  let stats = template.regenerate(changed)?;
+
+  if changed.is_none() {
+    for stale in generated.difference(&stats.generated) {
+      if let Err(err) = fs::remove_file(stale) {
+        log::warn!("couldn't remove {:?}: {}", stale, err);
+      }
+    }
+    generated.clear();
+  }
+  generated.extend(stats.generated);
+
+  snapshot.compare()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::diff::Change;
+
+  use pretty_assertions::assert_eq;
+
+  use std::fs;
+
+  #[test]
+  fn test_regenerate() {
+    let template = tempfile::tempdir().unwrap();
+    let template_dir = template.path().canonicalize().unwrap();
+    fs::write(
+      template_dir.join("template.toml"),
+      "[filters]\nexclude = []\n",
+    )
+    .unwrap();
+    fs::write(template_dir.join("README.md.hbs"), "# {{project-name}}\n")
+      .unwrap();
+    fs::write(template_dir.join("LICENSE"), "MIT\n").unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let project_dir = dir.path().join("my-project");
+    let args = Arguments::new(
+      &project_dir.to_string_lossy(),
+      &template_dir.to_string_lossy(),
+      None,
+    )
+    .unwrap();
+    let project_dir = args.project.path();
+    let mut generated = Template::from(&args).generate().unwrap().generated;
+
+    // Editing a template file regenerates it.
+    let readme = template_dir.join("README.md.hbs");
+    fs::write(&readme, "# {{project-name}}\n\nEdited.\n").unwrap();
+    let event = DebouncedEvent::Write(readme);
+    let files =
+      regenerate_for(&event, &template_dir, &project_dir, "template.toml")
+        .unwrap();
+    assert_eq!(
+      files,
+      Regenerate::Files(
+        vec![PathBuf::from("README.md.hbs")].into_iter().collect()
+      )
+    );
+
+    let report = regenerate(&args, &files, &mut generated).unwrap();
+    assert_eq!(
+      report.changes,
+      vec![Change::Changed(PathBuf::from("README.md"), String::new())]
+    );
+    assert_eq!(
+      fs::read_to_string(project_dir.join("README.md")).unwrap(),
+      "# my_project\n\nEdited.\n"
+    );
+
+    // Editing the config file regenerates every file.
+    let event = DebouncedEvent::Write(template_dir.join("template.toml"));
+    assert_eq!(
+      regenerate_for(&event, &template_dir, &project_dir, "template.toml"),
+      Some(Regenerate::All)
+    );
+    let event = DebouncedEvent::Chmod(template_dir.join("LICENSE"));
+    assert_eq!(
+      regenerate_for(&event, &template_dir, &project_dir, "template.toml"),
+      None
+    );
+
+    // Removing a template file removes the file it generated, but not the
+    // files added to the project.
+    fs::write(project_dir.join("NOTES.md"), "Mine.\n").unwrap();
+    fs::remove_file(template_dir.join("LICENSE")).unwrap();
+    let event = DebouncedEvent::Remove(template_dir.join("LICENSE"));
+    let files =
+      regenerate_for(&event, &template_dir, &project_dir, "template.toml")
+        .unwrap();
+    let report = regenerate(&args, &files, &mut generated).unwrap();
+    assert_eq!(
+      report.changes,
+      vec![Change::Removed(PathBuf::from("LICENSE"))]
+    );
+    assert!(!project_dir.join("LICENSE").exists());
+    assert!(project_dir.join("NOTES.md").exists());
+    assert_eq!(
+      generated,
+      vec![project_dir.join("README.md")].into_iter().collect()
+    );
+  }
+}
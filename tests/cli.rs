@@ -0,0 +1,883 @@
+use std::{
+  fs,
+  io::Write,
+  process::{Command, Stdio},
+};
+
+#[test]
+fn test_silent_failure() {
+  let dir = tempfile::tempdir().unwrap();
+  let missing = dir.path().join("missing-template");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("--silent")
+    .arg("new")
+    .arg(&missing)
+    .arg("my-project")
+    .output()
+    .unwrap();
+
+  assert!(!output.status.success());
+  assert!(output.stdout.is_empty());
+  assert!(output.stderr.is_empty(), "{:?}", output.stderr);
+
+  // Errors are still reported without `--silent`.
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("--quiet")
+    .arg("new")
+    .arg(&missing)
+    .arg("my-project")
+    .output()
+    .unwrap();
+
+  assert!(!output.status.success());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("ERROR:"));
+}
+
+#[test]
+fn test_log_file() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("README.md.hbs"), "# {{project-name}}\n").unwrap();
+  let log_file = dir.path().join("project.log");
+  fs::write(&log_file, "previous run\n").unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("--quiet")
+    .arg("--log-file")
+    .arg(&log_file)
+    .arg("new")
+    .arg(&template)
+    .arg("my-project")
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+
+  // The console stays clean, even though the template has no config file,
+  // and the log is truncated first.
+  assert!(output.stdout.is_empty());
+  assert!(
+    output.stderr.is_empty(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  let log = fs::read_to_string(&log_file).unwrap();
+  assert!(!log.contains("previous run"), "{}", log);
+  assert!(
+    log.contains("INFO  project::template: generating"),
+    "{}",
+    log
+  );
+  assert!(log.contains("DEBUG project::template: Render"), "{}", log);
+}
+
+#[test]
+fn test_trace_renders() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(
+    template.join("template.toml"),
+    "[filters]\nexclude = []\n\n[variables]\nlicense = \"MIT\"\n",
+  )
+  .unwrap();
+  fs::write(
+    template.join("README.md.hbs"),
+    "# {{project-name}}\n\nLicensed under {{license}}.\n",
+  )
+  .unwrap();
+  fs::write(template.join("LICENSE"), "{{license}}\n").unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("--quiet")
+    .arg("--trace-renders")
+    .arg("new")
+    .arg(&template)
+    .arg("my-project")
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+
+  // Only rendered files are traced, onto stderr without `--log-file`.
+  let trace = String::from_utf8_lossy(&output.stderr);
+  assert!(
+    trace.contains(
+      "rendering \"README.md.hbs\" with project-name = \"my_project\", license = \"MIT\""
+    ),
+    "{}",
+    trace
+  );
+  assert!(!trace.contains("LICENSE"), "{}", trace);
+}
+
+#[test]
+fn test_batch() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("template.toml"), "[filters]\nexclude = []\n")
+    .unwrap();
+  fs::write(
+    template.join("README.md.hbs"),
+    "# {{project-name}} ({{port}})\n",
+  )
+  .unwrap();
+  fs::write(
+    dir.path().join("services.toml"),
+    format!(
+      "template = {:?}\n\n[vars]\nport = \"8080\"\n\n[[project]]\nname = \"billing\"\n\n[[project]]\nname = \"auth\"\nvars = {{ port = \"8081\" }}\n",
+      template
+    ),
+  )
+  .unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .args([
+      "--quiet",
+      "--workspace",
+      "services",
+      "batch",
+      "services.toml",
+    ])
+    .args(["--jobs", "2"])
+    .output()
+    .unwrap();
+  assert!(output.status.success(), "{:?}", output);
+
+  let workspace = dir.path().join("services");
+  assert_eq!(
+    fs::read_to_string(workspace.join("billing/README.md")).unwrap(),
+    "# billing (8080)\n"
+  );
+  assert_eq!(
+    fs::read_to_string(workspace.join("auth/README.md")).unwrap(),
+    "# auth (8081)\n"
+  );
+}
+
+#[test]
+fn test_check() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("template.toml"), "[filters]\nexclude = []\n")
+    .unwrap();
+  fs::write(template.join("README.md.hbs"), "# {{project-name}}\n").unwrap();
+  fs::write(template.join("Makefile"), "all:\n").unwrap();
+
+  let project = |args: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_project"))
+      .current_dir(dir.path())
+      .env("USER", "me")
+      .env("XDG_CONFIG_HOME", dir.path())
+      .arg("--quiet")
+      .args(args)
+      .output()
+      .unwrap()
+  };
+  let template = template.to_str().unwrap();
+  assert!(project(&["new", template, "my-project"]).status.success());
+
+  // Files the project adds aren't drift.
+  fs::write(dir.path().join("my-project/NOTES.md"), "").unwrap();
+  let output = project(&["check", template, "my-project"]);
+  assert!(output.status.success(), "{:?}", output);
+  assert!(output.stdout.is_empty());
+
+  fs::write(dir.path().join("my-project/README.md"), "# Edited\n").unwrap();
+  fs::remove_file(dir.path().join("my-project/Makefile")).unwrap();
+  let output = project(&["check", template, "my-project"]);
+  assert!(!output.status.success());
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "- Makefile\n~ README.md\n"
+  );
+}
+
+#[test]
+fn test_env_overlay() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(
+    template.join("template.toml"),
+    "[filters]\nexclude = []\n\n[variables]\nreplicas = \"1\"\nlog_level = \"debug\"\n\n[variables.dev]\nreplicas = \"1\"\n\n[variables.prod]\nreplicas = \"3\"\n",
+  )
+  .unwrap();
+  fs::write(
+    template.join("deploy.yml.hbs"),
+    "replicas: {{replicas}}\nlog: {{log_level}}\n",
+  )
+  .unwrap();
+
+  let run = |env: &str, name: &str| {
+    Command::new(env!("CARGO_BIN_EXE_project"))
+      .current_dir(dir.path())
+      .env("USER", "me")
+      .env("XDG_CONFIG_HOME", dir.path())
+      .args(["--quiet", "--env", env, "new"])
+      .arg(&template)
+      .arg(name)
+      .output()
+      .unwrap()
+  };
+
+  let output = run("prod", "prod-project");
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(
+    fs::read_to_string(dir.path().join("prod-project/deploy.yml")).unwrap(),
+    "replicas: 3\nlog: debug\n"
+  );
+
+  // The overlay overrides the user's defaults.
+  fs::create_dir_all(dir.path().join("project")).unwrap();
+  fs::write(
+    dir.path().join("project/defaults.toml"),
+    "[defaults]\nreplicas = \"5\"\nlog_level = \"info\"\n",
+  )
+  .unwrap();
+  let output = run("prod", "defaults-project");
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(
+    fs::read_to_string(dir.path().join("defaults-project/deploy.yml")).unwrap(),
+    "replicas: 3\nlog: info\n"
+  );
+
+  let output = run("qa", "qa-project");
+  assert!(!output.status.success());
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(
+    stderr.contains(
+      "unknown --env \"qa\", available environments are [\"dev\", \"prod\"]"
+    ),
+    "{}",
+    stderr
+  );
+}
+
+#[test]
+fn test_validate_vars() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(
+    template.join("template.toml"),
+    "[filters]\nexclude = []\n\n[prompts]\ndatabase = \"\"\n\n[validate]\nname = \"^[a-z_]+$\"\n",
+  )
+  .unwrap();
+  fs::write(dir.path().join("vars.toml"), "name = \"my_project\"\n").unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("validate-vars")
+    .arg(&template)
+    .arg("--vars-file")
+    .arg("vars.toml")
+    .output()
+    .unwrap();
+  assert!(!output.status.success());
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(
+    stderr.contains(
+      "has 1 invalid variable(s):\n  missing required variable \"database\""
+    ),
+    "{}",
+    stderr
+  );
+  // Nothing is generated, nor the defaults created.
+  assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn test_next_steps() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(
+    template.join("template.toml"),
+    "next_steps = \"\"\"\nrun `make setup` in {{project-name}}\ncargo run --bin {{project-name}}\n\"\"\"\n\n[filters]\nexclude = []\n",
+  )
+  .unwrap();
+  fs::write(template.join("README.md"), "# Template\n").unwrap();
+
+  let run = |flag: &str, name: &str| {
+    Command::new(env!("CARGO_BIN_EXE_project"))
+      .current_dir(dir.path())
+      .env("USER", "me")
+      .env("XDG_CONFIG_HOME", dir.path())
+      .arg(flag)
+      .arg("new")
+      .arg(&template)
+      .arg(name)
+      .output()
+      .unwrap()
+  };
+
+  let output = run("--verbose", "my-project");
+  assert!(output.status.success());
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(
+    stdout.contains(
+      "Next steps:\n\trun `make setup` in my_project\n\tcargo run --bin my_project\n"
+    ),
+    "{}",
+    stdout
+  );
+
+  // They're printed alone, without generating the project.
+  let output = run("--print-next-steps", "other-project");
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "run `make setup` in other_project\ncargo run --bin other_project\n"
+  );
+  assert!(!dir.path().join("other-project/README.md").exists());
+}
+
+#[test]
+fn test_input_json() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(
+    template.join("template.toml"),
+    "[filters]\nexclude = []\n\n[variables]\nlicense = \"MIT\"\n",
+  )
+  .unwrap();
+  fs::write(
+    template.join("README.md.hbs"),
+    "# {{project-name}}\n\nLicensed under {{license}}.\n",
+  )
+  .unwrap();
+  fs::write(template.join("debug.log"), "\n").unwrap();
+
+  let request = format!(
+    r#"{{
+      "template": {:?},
+      "name": "my-project",
+      "variables": {{ "license": "Apache-2.0" }},
+      "exclude": ["*.log"]
+    }}"#,
+    template.display().to_string()
+  );
+  let mut child = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("--quiet")
+    .arg("generate")
+    .arg("--input-json")
+    .arg("-")
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  child
+    .stdin
+    .take()
+    .unwrap()
+    .write_all(request.as_bytes())
+    .unwrap();
+  let output = child.wait_with_output().unwrap();
+  assert!(output.status.success(), "{:?}", output);
+
+  let project = dir.path().join("my-project");
+  assert_eq!(
+    fs::read_to_string(project.join("README.md")).unwrap(),
+    "# my_project\n\nLicensed under Apache-2.0.\n"
+  );
+  assert!(!project.join("debug.log").exists());
+
+  // Required fields are reported.
+  let request = dir.path().join("request.json");
+  fs::write(&request, r#"{ "template": "template" }"#).unwrap();
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("generate")
+    .arg("--input-json")
+    .arg(&request)
+    .output()
+    .unwrap();
+  assert!(!output.status.success());
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("missing field `name`"), "{}", stderr);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_first_run() {
+  let dir = tempfile::tempdir().unwrap();
+  let defaults = dir.path().join("project/defaults.toml");
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("README.md"), "# Project\n").unwrap();
+  let run = || {
+    Command::new(env!("CARGO_BIN_EXE_project"))
+      .current_dir(dir.path())
+      .env("XDG_CONFIG_HOME", dir.path())
+      .env("CARGO_NAME", "Jane Doe")
+      .env("CARGO_EMAIL", "jane@example.com")
+      .args(["--force", "new"])
+      .arg(&template)
+      .arg("my-project")
+      .output()
+      .unwrap()
+  };
+
+  // Other commands don't create the defaults.
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("helpers")
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+  assert!(!defaults.exists());
+
+  assert!(run().status.success());
+  let content = fs::read_to_string(&defaults).unwrap();
+  assert_eq!(content, "author = \"Jane Doe <jane@example.com>\"\n");
+
+  // Later runs keep the user's changes.
+  fs::write(&defaults, "author = \"X\"\n").unwrap();
+  assert!(run().status.success());
+  assert_eq!(fs::read_to_string(&defaults).unwrap(), "author = \"X\"\n");
+
+  // Nothing is created with `--no-first-run`.
+  fs::remove_file(&defaults).unwrap();
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("--no-first-run")
+    .arg("helpers")
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+  assert!(!defaults.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_sensitive_dir() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("template.toml"), "[filters]\nexclude = []\n")
+    .unwrap();
+  fs::write(template.join("README.md.hbs"), "# {{project-name}}\n").unwrap();
+  let home = dir.path().join("me");
+  fs::create_dir(&home).unwrap();
+
+  let run = |flags: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_project"))
+      .current_dir(dir.path())
+      .env("USER", "me")
+      .env("HOME", &home)
+      .env("XDG_CONFIG_HOME", dir.path())
+      .arg("--quiet")
+      .args(flags)
+      .arg("new")
+      .arg(&template)
+      .arg(&home)
+      .output()
+      .unwrap()
+  };
+
+  // The home directory is refused by default.
+  let output = run(&[]);
+  assert!(!output.status.success());
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("refusing to generate into"), "{}", stderr);
+  assert!(!home.join("README.md").exists());
+
+  let output = run(&["--i-know-what-im-doing"]);
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(
+    fs::read_to_string(home.join("README.md")).unwrap(),
+    "# me\n"
+  );
+}
+
+#[test]
+fn test_print_diff_after() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("template.toml"), "[filters]\nexclude = []\n")
+    .unwrap();
+  fs::write(template.join("README.md.hbs"), "# {{project-name}}\n").unwrap();
+  fs::write(template.join("LICENSE"), "MIT\n").unwrap();
+  fs::write(template.join("main.rs"), "fn main() {}\n").unwrap();
+
+  // One file is overwritten with new content, the other with the same.
+  let project = dir.path().join("my-project");
+  fs::create_dir(&project).unwrap();
+  fs::write(project.join("README.md"), "# Draft\n").unwrap();
+  fs::write(project.join("LICENSE"), "MIT\n").unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .args(["--quiet", "--force", "--print-diff-after", "new"])
+    .arg(&template)
+    .arg("my-project")
+    .output()
+    .unwrap();
+  assert!(output.status.success(), "{:?}", output);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(stdout.contains("+ main.rs\n"), "{}", stdout);
+  assert!(stdout.contains("~ README.md\n"), "{}", stdout);
+  assert!(!stdout.contains("LICENSE"), "{}", stdout);
+  assert!(
+    stdout.contains("1 added, 1 changed, 0 removed, 1 unchanged"),
+    "{}",
+    stdout
+  );
+}
+
+#[test]
+fn test_inherit_from() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("template.toml"), "[filters]\nexclude = []\n")
+    .unwrap();
+  fs::write(
+    template.join("Cargo.toml.hbs"),
+    "[package]\nname = \"{{name_prefix}}{{project-name}}\"\nauthors = [\"{{authors}}\"]\nlicense = \"{{license}}\"\n",
+  )
+  .unwrap();
+  fs::write(
+    dir.path().join("Cargo.toml"),
+    "[package]\nname = \"acme\"\nauthors = [\"Jane Doe\"]\nlicense = \"Apache-2.0\"\n",
+  )
+  .unwrap();
+
+  // `-D` overrides the inherited variables.
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .args([
+      "--quiet",
+      "--inherit-from",
+      "Cargo.toml",
+      "-D",
+      "license=MIT",
+    ])
+    .arg("new")
+    .arg(&template)
+    .arg("core")
+    .output()
+    .unwrap();
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(
+    fs::read_to_string(dir.path().join("core/Cargo.toml")).unwrap(),
+    "[package]\nname = \"acme-core\"\nauthors = [\"Jane Doe\"]\nlicense = \"MIT\"\n"
+  );
+
+  // A missing manifest is reported, without a panic.
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .args(["--inherit-from", "missing.toml"])
+    .arg("new")
+    .arg(&template)
+    .arg("other")
+    .output()
+    .unwrap();
+  assert!(!output.status.success());
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("couldn't inherit variables"), "{}", stderr);
+  assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn test_env_prefix() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(
+    template.join("template.toml"),
+    "env_prefix = \"APP_\"\n\n[filters]\nexclude = []\n\n[variables]\ndb = \"sqlite\"\nport = \"80\"\n",
+  )
+  .unwrap();
+  fs::write(
+    template.join("config.toml.hbs"),
+    "db = \"{{db}}\"\nport = {{port}}\n",
+  )
+  .unwrap();
+
+  let run = |flags: &[&str], name: &str| {
+    Command::new(env!("CARGO_BIN_EXE_project"))
+      .current_dir(dir.path())
+      .env("USER", "me")
+      .env("XDG_CONFIG_HOME", dir.path())
+      .env("PROJECT_VAR_DB", "postgres")
+      .env("APP_PORT", "8080")
+      .arg("--quiet")
+      .args(flags)
+      .arg("new")
+      .arg(&template)
+      .arg(name)
+      .output()
+      .unwrap()
+  };
+  let read = |name: &str| {
+    fs::read_to_string(dir.path().join(name).join("config.toml")).unwrap()
+  };
+
+  let output = run(&["--env-prefix", "PROJECT_VAR_"], "flag");
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(read("flag"), "db = \"postgres\"\nport = 80\n");
+
+  // The template's own `env_prefix` is ignored.
+  let output = run(&[], "ignored");
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(read("ignored"), "db = \"sqlite\"\nport = 80\n");
+
+  // The user's `env-prefix` is used without the flag, below `-D`.
+  fs::write(
+    dir.path().join("project/defaults.toml"),
+    "env-prefix = \"APP_\"\n",
+  )
+  .unwrap();
+  let output = run(&["-D", "db=mysql"], "defaults");
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(read("defaults"), "db = \"mysql\"\nport = 8080\n");
+}
+
+#[test]
+fn test_json_logs() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(
+    template.join("template.toml"),
+    "[filters]\nexclude = [\"*.bak\"]\n",
+  )
+  .unwrap();
+  fs::write(template.join("README.md.hbs"), "# {{project-name}}\n").unwrap();
+  fs::write(template.join("README.md.bak"), "# Old\n").unwrap();
+
+  let run = |args: &[&str]| {
+    let output = Command::new(env!("CARGO_BIN_EXE_project"))
+      .current_dir(dir.path())
+      .env("USER", "me")
+      .env("XDG_CONFIG_HOME", dir.path())
+      .args(args)
+      .output()
+      .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let events: Vec<String> = stderr
+      .lines()
+      .map(|line| {
+        let value: serde_json::Value = serde_json::from_str(line)
+          .unwrap_or_else(|err| panic!("{:?} isn't JSON: {}", line, err));
+        value["event"].as_str().expect(line).to_string()
+      })
+      .collect();
+    (output.status.success(), events)
+  };
+
+  let template = template.to_string_lossy();
+  let (success, events) = run(&["--json-logs", "new", &template, "my-project"]);
+  assert!(success);
+  for event in &["started", "file_rendered", "file_skipped", "finished"] {
+    assert!(events.iter().any(|e| e == event), "{:?}", events);
+  }
+
+  // Failures are logged too.
+  let missing = dir.path().join("missing-template");
+  let (success, events) = run(&[
+    "--json-logs",
+    "new",
+    &missing.to_string_lossy(),
+    "other-project",
+  ]);
+  assert!(!success);
+  assert!(events.iter().any(|e| e == "log"), "{:?}", events);
+}
+
+#[test]
+fn test_first_run_without_user() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("template.toml"), "[filters]\nexclude = []\n")
+    .unwrap();
+  fs::write(template.join("README.md.hbs"), "# {{project-name}}\n").unwrap();
+
+  // Neither a user nor a `git` config to discover the author from.
+  let mut command = Command::new(env!("CARGO_BIN_EXE_project"));
+  for var in &[
+    "USER",
+    "USERNAME",
+    "NAME",
+    "CARGO_NAME",
+    "GIT_AUTHOR_NAME",
+    "GIT_COMMITTER_NAME",
+    "GIT_CONFIG_GLOBAL",
+    "GIT_CONFIG_SYSTEM",
+  ] {
+    command.env_remove(var);
+  }
+  let output = command
+    .current_dir(dir.path())
+    .env("HOME", dir.path())
+    .env("XDG_CONFIG_HOME", dir.path())
+    .env("GIT_CONFIG_NOSYSTEM", "1")
+    .arg("--silent")
+    .arg("new")
+    .arg(&template)
+    .arg("my-project")
+    .output()
+    .unwrap();
+  assert!(output.status.success(), "{:?}", output);
+  assert!(dir.path().join("my-project/README.md").exists());
+}
+
+#[test]
+fn test_quiet_remote() {
+  let origin = tempfile::tempdir().unwrap();
+  fs::write(origin.path().join("README.md.hbs"), "# {{project-name}}\n")
+    .unwrap();
+  project::git::init_commit(origin.path(), "Initial commit").unwrap();
+  let url = url::Url::from_file_path(origin.path()).unwrap();
+
+  // Neither cloning nor using the cached clone is reported.
+  let dir = tempfile::tempdir().unwrap();
+  for name in ["first", "second"] {
+    let output = Command::new(env!("CARGO_BIN_EXE_project"))
+      .current_dir(dir.path())
+      .env("USER", "me")
+      .env("XDG_CONFIG_HOME", dir.path())
+      .arg("--quiet")
+      .arg("--cache-dir")
+      .arg(dir.path().join("cache"))
+      .arg("new")
+      .arg(url.as_str())
+      .arg(name)
+      .output()
+      .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(
+      output.stderr.is_empty(),
+      "{}",
+      String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(dir.path().join(name).join("README.md").exists());
+  }
+}
+
+#[test]
+fn test_template_from_stdin() {
+  let dir = tempfile::tempdir().unwrap();
+  let mut builder = tar::Builder::new(Vec::new());
+  for (path, content) in &[
+    ("template.toml", "[filters]\nexclude = []\n"),
+    ("README.md.hbs", "# {{project-name}}\n"),
+  ] {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+      .append_data(&mut header, path, content.as_bytes())
+      .unwrap();
+  }
+  let archive = builder.into_inner().unwrap();
+
+  let mut child = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .arg("--quiet")
+    .arg("new")
+    .arg("-")
+    .arg("my-project")
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  child.stdin.take().unwrap().write_all(&archive).unwrap();
+  let output = child.wait_with_output().unwrap();
+  assert!(output.status.success(), "{:?}", output);
+
+  assert_eq!(
+    fs::read_to_string(dir.path().join("my-project/README.md")).unwrap(),
+    "# my_project\n"
+  );
+}
+
+#[test]
+fn test_unsupported_archive() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("README.md"), "# Project\n").unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", dir.path())
+    .args(["--archive", "my-project.zip", "new"])
+    .arg(&template)
+    .arg("my-project")
+    .output()
+    .unwrap();
+
+  assert!(!output.status.success());
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("unsupported archive"), "{}", stderr);
+  // The extension is checked before the project's directory is created.
+  assert!(!dir.path().join("my-project").exists());
+}
+
+#[test]
+fn test_first_run_warning() {
+  let dir = tempfile::tempdir().unwrap();
+  let template = dir.path().join("template");
+  fs::create_dir(&template).unwrap();
+  fs::write(template.join("README.md"), "# Project\n").unwrap();
+  // The config directory can't be created under a file.
+  let config_home = dir.path().join("config");
+  fs::write(&config_home, "").unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_project"))
+    .current_dir(dir.path())
+    .env("USER", "me")
+    .env("XDG_CONFIG_HOME", &config_home)
+    .arg("new")
+    .arg(&template)
+    .arg("my-project")
+    .output()
+    .unwrap();
+
+  // Without a logger, the warning is still reported.
+  assert!(output.status.success(), "{:?}", output);
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("couldn't create the defaults"), "{}", stderr);
+}